@@ -1,9 +1,18 @@
-use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired, AudioStatus};
 use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Default playback frequency in Hz.
+pub const DEFAULT_FREQUENCY: i32 = 44100;
 
 /// Manages audio playback using SDL2.
 pub struct AudioManager {
-    device: AudioDevice<SquareWave>,
+    device: Option<AudioDevice<SquareWave>>,
+    playing: bool,
+    muted: bool,
+    min_beep: Duration,
+    pan: f32,
+    started_at: Option<Instant>,
 }
 
 impl AudioManager {
@@ -17,6 +26,69 @@ impl AudioManager {
     ///
     /// Returns an error if SDL2 fails to get the audio subsystem or open the playback device.
     pub fn new(sdl_context: &sdl2::Sdl) -> Result<Self, Box<dyn Error>> {
+        Self::with_channels(sdl_context, 1)
+    }
+
+    /// Creates a new `AudioManager` instance with the given number of output channels.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdl_context` - A reference to an initialized SDL context.
+    /// * `channels` - Number of output channels (1 for mono, 2 for stereo).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to get the audio subsystem or open the playback device.
+    pub fn with_channels(sdl_context: &sdl2::Sdl, channels: u8) -> Result<Self, Box<dyn Error>> {
+        Self::with_spec(sdl_context, DEFAULT_FREQUENCY, channels, None)
+    }
+
+    /// Creates a new mono `AudioManager` instance with the given frequency and buffer size.
+    ///
+    /// Smaller buffers lower the latency between the sound timer and the audible beep.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdl_context` - A reference to an initialized SDL context.
+    /// * `freq` - Playback frequency in Hz.
+    /// * `samples` - Buffer size in samples, which must be a power of two.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer size is invalid, or SDL2 fails to get the audio
+    /// subsystem or open the playback device.
+    pub fn new_with_spec(
+        sdl_context: &sdl2::Sdl,
+        freq: i32,
+        samples: u16,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::with_spec(sdl_context, freq, 1, Some(samples))
+    }
+
+    /// Creates a new `AudioManager` instance with a fully specified playback device.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdl_context` - A reference to an initialized SDL context.
+    /// * `freq` - Playback frequency in Hz.
+    /// * `channels` - Number of output channels (1 for mono, 2 for stereo).
+    /// * `samples` - Buffer size in samples, which must be a power of two, or `None`
+    ///   for the SDL2 default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer size is invalid, or SDL2 fails to get the audio
+    /// subsystem or open the playback device.
+    pub fn with_spec(
+        sdl_context: &sdl2::Sdl,
+        freq: i32,
+        channels: u8,
+        samples: Option<u16>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if let Some(samples) = samples {
+            validate_buffer_size(samples)?;
+        }
+
         // Retrieve the SDL2 audio subsystem.
         let audio_subsystem = sdl_context
             .audio()
@@ -24,36 +96,189 @@ impl AudioManager {
 
         // Define the desired audio specification.
         let desired_spec = AudioSpecDesired {
-            freq: Some(44100), // 44.1 kHz frequency
-            channels: Some(1), // Mono audio
-            samples: None,     // Default sample size
+            freq: Some(freq),         // Playback frequency
+            channels: Some(channels), // Mono or stereo audio
+            samples,                  // Buffer size, or the default
         };
 
         // Open the audio playback device with the desired specification.
         let device = audio_subsystem
             .open_playback(None, &desired_spec, |spec| {
-                SquareWave::new(440.0, 0.25, spec.freq as f32)
+                SquareWave::new(440.0, 0.25, spec.freq as f32, spec.channels as usize)
             })
             .map_err(|e| format!("Failed to open audio playback device: {}", e))?;
 
         // Return the AudioManager instance.
-        Ok(AudioManager { device })
+        let mut audio_manager = Self::headless();
+        audio_manager.device = Some(device);
+        Ok(audio_manager)
+    }
+
+    /// Creates a new `AudioManager` instance without an output device.
+    ///
+    /// The playback status is still tracked, but no sound is produced.
+    pub fn headless() -> Self {
+        AudioManager {
+            device: None,
+            playing: false,
+            muted: false,
+            min_beep: Duration::ZERO,
+            pan: 0.0,
+            started_at: None,
+        }
+    }
+
+    /// Replaces the playback device with one of a different specification.
+    ///
+    /// The mute state, minimum beep duration, panning and any ongoing beep carry over
+    /// to the new device.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdl_context` - A reference to an initialized SDL context.
+    /// * `freq` - Playback frequency in Hz.
+    /// * `channels` - Number of output channels (1 for mono, 2 for stereo).
+    /// * `samples` - Buffer size in samples, which must be a power of two, or `None`
+    ///   for the SDL2 default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer size is invalid, or SDL2 fails to get the audio
+    /// subsystem or open the playback device. The current device is kept in that case.
+    pub fn reopen(
+        &mut self,
+        sdl_context: &sdl2::Sdl,
+        freq: i32,
+        channels: u8,
+        samples: Option<u16>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut reopened = Self::with_spec(sdl_context, freq, channels, samples)?;
+        reopened.inherit(self);
+        *self = reopened;
+        Ok(())
+    }
+
+    fn inherit(&mut self, previous: &AudioManager) {
+        self.min_beep = previous.min_beep;
+        self.set_pan(previous.pan);
+        self.set_muted(previous.muted);
+        if previous.playing {
+            self.start_at(previous.started_at.unwrap_or_else(Instant::now));
+        }
+    }
+
+    /// Sets the minimum duration of a beep.
+    ///
+    /// Stopping the playback earlier is deferred until the minimum duration has elapsed,
+    /// so that very short sound timer values are still audible.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_beep` - Minimum playback duration once a beep has started.
+    pub fn set_min_beep(&mut self, min_beep: Duration) {
+        self.min_beep = min_beep;
+    }
+
+    /// Mutes or unmutes the audio playback.
+    ///
+    /// While muted, the playback is kept paused, but beeps are still tracked
+    /// so that unmuting in the middle of one resumes it.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        if let Some(device) = self.device.as_ref() {
+            if muted {
+                device.pause();
+            } else if self.playing {
+                device.resume();
+            }
+        }
+    }
+
+    /// Returns `true` if the audio playback is muted.
+    pub fn is_muted(&self) -> bool {
+        self.muted
     }
 
     /// Starts the audio playback.
-    pub fn start(&self) {
-        self.device.resume();
+    pub fn start(&mut self) {
+        self.start_at(Instant::now());
+    }
+
+    /// Stops the audio playback, unless the current beep is shorter than the minimum duration.
+    pub fn stop(&mut self) {
+        self.stop_at(Instant::now());
+    }
+
+    /// Stops the audio playback right away, even if the current beep is shorter than the
+    /// minimum duration.
+    pub fn silence(&mut self) {
+        self.started_at = None;
+        self.stop();
     }
 
-    /// Stops the audio playback.
-    pub fn stop(&self) {
-        self.device.pause();
+    fn start_at(&mut self, now: Instant) {
+        if !self.playing {
+            self.started_at = Some(now);
+        }
+        self.playing = true;
+        if let Some(device) = self.device.as_ref() {
+            if !self.muted {
+                device.resume();
+            }
+        }
+    }
+
+    fn stop_at(&mut self, now: Instant) {
+        if let Some(started_at) = self.started_at {
+            if now.duration_since(started_at) < self.min_beep {
+                return;
+            }
+        }
+
+        self.playing = false;
+        self.started_at = None;
+        if let Some(device) = self.device.as_ref() {
+            device.pause();
+        }
+    }
+
+    /// Sets the stereo panning of the audio playback.
+    ///
+    /// Has no audible effect on a mono device.
+    ///
+    /// # Arguments
+    ///
+    /// * `pan` - Panning from -1.0 (full left) to 1.0 (full right), clamped to that range.
+    pub fn set_pan(&mut self, pan: f32) {
+        self.pan = pan.clamp(-1.0, 1.0);
+        if let Some(device) = self.device.as_mut() {
+            device.lock().pan = self.pan;
+        }
     }
 
     /// Gets the current status of the audio playback.
-    pub fn status(&self) -> sdl2::audio::AudioStatus {
-        self.device.status()
+    pub fn status(&self) -> AudioStatus {
+        match self.device.as_ref() {
+            Some(device) => device.status(),
+            None if self.playing && !self.muted => AudioStatus::Playing,
+            None => AudioStatus::Paused,
+        }
+    }
+}
+
+/// Checks that an audio buffer size is a power of two, as SDL2 requires.
+///
+/// # Errors
+///
+/// Returns an error if `samples` is not a power of two.
+pub fn validate_buffer_size(samples: u16) -> Result<(), String> {
+    if !samples.is_power_of_two() {
+        return Err(format!(
+            "Audio buffer size must be a power of two, got {}",
+            samples
+        ));
     }
+    Ok(())
 }
 
 /// Generates a square wave for audio playback.
@@ -61,6 +286,8 @@ struct SquareWave {
     phase_inc: f32,
     phase: f32,
     volume: f32,
+    channels: usize,
+    pan: f32,
 }
 
 impl SquareWave {
@@ -71,11 +298,26 @@ impl SquareWave {
     /// * `freq` - Frequency of the square wave.
     /// * `volume` - Volume of the square wave.
     /// * `sample_rate` - Sample rate of the audio playback.
-    fn new(freq: f32, volume: f32, sample_rate: f32) -> Self {
+    /// * `channels` - Number of interleaved output channels.
+    fn new(freq: f32, volume: f32, sample_rate: f32, channels: usize) -> Self {
         SquareWave {
             phase_inc: freq / sample_rate,
             phase: 0.0,
             volume,
+            channels: channels.max(1),
+            pan: 0.0,
+        }
+    }
+
+    /// Returns the gain applied to the given channel for the current panning.
+    ///
+    /// Only the first two channels of a stereo device are panned.
+    fn channel_gain(&self, channel: usize) -> f32 {
+        match (self.channels, channel) {
+            (1, _) => 1.0,
+            (_, 0) => (1.0 - self.pan).min(1.0),
+            (_, 1) => (1.0 + self.pan).min(1.0),
+            _ => 1.0,
         }
     }
 }
@@ -86,17 +328,21 @@ impl AudioCallback for SquareWave {
     /// Fills the output buffer with audio data.
     ///
     /// Generates a square wave and writes it to the output buffer.
+    /// Samples are interleaved per channel and weighted by the current panning.
     ///
     /// # Arguments
     ///
     /// * `out` - Mutable reference to the output buffer to be filled with audio data.
     fn callback(&mut self, out: &mut [f32]) {
-        for x in out.iter_mut() {
-            *x = if self.phase <= 0.5 {
+        for frame in out.chunks_mut(self.channels) {
+            let sample = if self.phase <= 0.5 {
                 self.volume
             } else {
                 -self.volume
             };
+            for (channel, x) in frame.iter_mut().enumerate() {
+                *x = sample * self.channel_gain(channel);
+            }
             self.phase = (self.phase + self.phase_inc) % 1.0;
         }
     }
@@ -108,7 +354,7 @@ mod tests {
 
     #[test]
     fn test_square_wave_callback() {
-        let mut square_wave = SquareWave::new(440.0, 0.25, 44100.0);
+        let mut square_wave = SquareWave::new(440.0, 0.25, 44100.0, 1);
         let mut buffer = [0.0; 100];
 
         square_wave.callback(&mut buffer);
@@ -117,5 +363,88 @@ mod tests {
             assert!(*x == 0.25 || *x == -0.25);
         }
     }
+
+    #[test]
+    fn test_validate_buffer_size() {
+        assert!(validate_buffer_size(512).is_ok());
+        assert!(validate_buffer_size(1).is_ok());
+        assert!(validate_buffer_size(0).is_err());
+        assert!(validate_buffer_size(1000).is_err());
+    }
+
+    #[test]
+    fn test_headless_status() {
+        let mut audio_manager = AudioManager::headless();
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+
+        audio_manager.start();
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+
+        audio_manager.stop();
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    }
+
+    #[test]
+    fn test_headless_muted() {
+        let mut audio_manager = AudioManager::headless();
+        audio_manager.set_muted(true);
+
+        audio_manager.start();
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+
+        audio_manager.set_muted(false);
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+    }
+
+    #[test]
+    fn test_min_beep() {
+        let mut audio_manager = AudioManager::headless();
+        audio_manager.set_min_beep(Duration::from_millis(50));
+
+        // A sound timer of 1 stops the playback after a single 60 Hz frame
+        let clock = Instant::now();
+        audio_manager.start_at(clock);
+        audio_manager.stop_at(clock + Duration::from_millis(17));
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+
+        audio_manager.stop_at(clock + Duration::from_millis(34));
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+
+        audio_manager.stop_at(clock + Duration::from_millis(50));
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    }
+
+    #[test]
+    fn test_inherit_settings() {
+        let mut previous = AudioManager::headless();
+        previous.set_min_beep(Duration::from_millis(50));
+        previous.set_pan(2.0);
+        previous.set_muted(true);
+        previous.start();
+
+        let mut audio_manager = AudioManager::headless();
+        audio_manager.inherit(&previous);
+        assert_eq!(audio_manager.min_beep, Duration::from_millis(50));
+        assert_eq!(audio_manager.pan, 1.0);
+        assert!(audio_manager.is_muted());
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+
+        audio_manager.set_muted(false);
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+    }
+
+    #[test]
+    fn test_square_wave_full_left_pan() {
+        let mut square_wave = SquareWave::new(440.0, 0.25, 44100.0, 2);
+        square_wave.pan = -1.0;
+        let mut buffer = [0.0; 100];
+
+        square_wave.callback(&mut buffer);
+
+        for frame in buffer.chunks(2) {
+            assert!(frame[0] == 0.25 || frame[0] == -0.25);
+            assert_eq!(frame[1], 0.0);
+        }
+    }
 }
 
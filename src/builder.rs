@@ -0,0 +1,201 @@
+use std::io;
+
+use crate::error::InitError;
+use crate::{copy_program, validate_start_address, Chip8, Mode, MEMORY_SIZE, ROM_START_ADDRESS};
+
+/// Configures and creates a `Chip8` instance from a program.
+pub struct Chip8Builder {
+    mode: Mode,
+    scale: usize,
+    program: Vec<u8>,
+    start_address: usize,
+    fill_unused: u8,
+    max_cycles: Option<u64>,
+    register_seed: Option<u64>,
+}
+
+impl Chip8Builder {
+    /// Creates a new `Chip8Builder` instance with an empty program.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode.
+    pub fn new(mode: Mode) -> Self {
+        Chip8Builder {
+            mode,
+            scale: 10,
+            program: Vec::new(),
+            start_address: ROM_START_ADDRESS,
+            fill_unused: 0,
+            max_cycles: None,
+            register_seed: None,
+        }
+    }
+
+    /// Sets the display scaling factor of the window.
+    pub fn scale(mut self, scale: usize) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the program that is loaded at the start address.
+    pub fn program(mut self, program: &[u8]) -> Self {
+        self.program = program.to_vec();
+        self
+    }
+
+    /// Sets the address the program is loaded at and execution starts from, 0x200 by default.
+    ///
+    /// Must be even and inside memory; some variants like the ETI-660 use 0x600.
+    pub fn start_address(mut self, start_address: usize) -> Self {
+        self.start_address = start_address;
+        self
+    }
+
+    /// Sets the value of all memory outside the fontset and the program, zero by default.
+    pub fn fill_unused(mut self, byte: u8) -> Self {
+        self.fill_unused = byte;
+        self
+    }
+
+    /// Enables the watchdog, which stops `run` after this many cycles without progress.
+    pub fn max_cycles(mut self, max_cycles: u64) -> Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// Starts with `V0` to `VF` and `I` set to a pattern from this seed instead of zero.
+    pub fn random_registers(mut self, seed: u64) -> Self {
+        self.register_seed = Some(seed);
+        self
+    }
+
+    /// Creates a `Chip8` instance with an SDL2 window, input, and sound.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the start address is invalid, the program does not fit into
+    /// memory, or SDL2 fails to initialize.
+    pub fn build(self) -> Result<Chip8, InitError> {
+        let scale = self.scale;
+        let (start_address, max_cycles, register_seed) =
+            (self.start_address, self.max_cycles, self.register_seed);
+        let (mode, memory) = self.into_memory()?;
+        let mut chip8 = Chip8::new(mode, scale, memory)?;
+        configure(&mut chip8, start_address, max_cycles, register_seed);
+        Ok(chip8)
+    }
+
+    /// Creates a `Chip8` instance that runs without a window, input, or sound.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the start address is invalid or the program does not fit into memory.
+    pub fn build_headless(self) -> io::Result<Chip8> {
+        let (start_address, max_cycles, register_seed) =
+            (self.start_address, self.max_cycles, self.register_seed);
+        let (mode, memory) = self.into_memory()?;
+        let mut chip8 = Chip8::headless(mode, memory);
+        configure(&mut chip8, start_address, max_cycles, register_seed);
+        Ok(chip8)
+    }
+
+    fn into_memory(self) -> io::Result<(Mode, [u8; MEMORY_SIZE])> {
+        // The fontset is loaded on top of this when the machine is created
+        validate_start_address(self.start_address)?;
+        let mut memory = [self.fill_unused; MEMORY_SIZE];
+        copy_program(&mut memory, &self.program, self.start_address)?;
+        Ok((self.mode, memory))
+    }
+}
+
+/// Applies the options that are set after the machine is created.
+fn configure(
+    chip8: &mut Chip8,
+    start_address: usize,
+    max_cycles: Option<u64>,
+    register_seed: Option<u64>,
+) {
+    chip8.set_start_address(start_address);
+    chip8.set_max_cycles(max_cycles);
+    if let Some(seed) = register_seed {
+        chip8.randomize_registers(seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{CHIP8_FONTSET, ROM_START_ADDRESS};
+
+    #[test]
+    fn test_fill_unused() {
+        let chip8 = Chip8Builder::new(Mode::Chip8)
+            .program(&[0x12, 0x00])
+            .fill_unused(0xFF)
+            .build_headless()
+            .unwrap();
+
+        assert_eq!(chip8.memory[..CHIP8_FONTSET.len()], CHIP8_FONTSET);
+        assert_eq!(chip8.memory[CHIP8_FONTSET.len()], 0xFF);
+        assert_eq!(
+            chip8.memory[ROM_START_ADDRESS..ROM_START_ADDRESS + 2],
+            [0x12, 0x00]
+        );
+        assert_eq!(chip8.memory[ROM_START_ADDRESS + 2], 0xFF);
+        assert_eq!(chip8.memory[MEMORY_SIZE - 1], 0xFF);
+    }
+
+    #[test]
+    fn test_start_address() {
+        let mut chip8 = Chip8Builder::new(Mode::Chip8)
+            .program(&[0xAB, 0xCD])
+            .start_address(0x600)
+            .build_headless()
+            .unwrap();
+
+        assert_eq!(chip8.PC, 0x600);
+        assert_eq!(chip8.fetch_opcode(), 0xABCD);
+
+        chip8.reset().unwrap();
+        assert_eq!(chip8.PC, 0x600);
+    }
+
+    #[test]
+    fn test_random_registers() {
+        let chip8 = Chip8Builder::new(Mode::Chip8)
+            .random_registers(42)
+            .build_headless()
+            .unwrap();
+        assert_eq!(
+            chip8.V,
+            [149, 110, 235, 47, 38, 50, 215, 189, 3, 241, 102, 178, 51, 227, 239, 40]
+        );
+        assert_eq!(chip8.I, 0xF52);
+
+        let chip8 = Chip8Builder::new(Mode::Chip8).build_headless().unwrap();
+        assert_eq!(chip8.V, [0; 16]);
+        assert_eq!(chip8.I, 0);
+    }
+
+    #[test]
+    fn test_invalid_start_address() {
+        for start_address in [0x601, MEMORY_SIZE] {
+            let result = Chip8Builder::new(Mode::Chip8)
+                .start_address(start_address)
+                .build_headless();
+
+            assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn test_program_too_large() {
+        let result = Chip8Builder::new(Mode::Chip8)
+            .program(&[0; MEMORY_SIZE])
+            .build_headless();
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// A source of the current time.
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// A clock that reads the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only advances when told to, for deterministic tests.
+#[derive(Debug)]
+pub struct MockClock {
+    now: Cell<Instant>,
+}
+
+impl MockClock {
+    /// Creates a new `MockClock` instance, starting at the current system time.
+    pub fn new() -> Self {
+        MockClock {
+            now: Cell::new(Instant::now()),
+        }
+    }
+
+    /// Advances the clock.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - The amount of time to advance by.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+/// Decides when to execute cycles and frames, based on a clock.
+///
+/// Deadlines advance by exact periods, so no time is lost to scheduling jitter.
+/// If the emulator falls more than a frame behind, it skips ahead instead of
+/// running the backlog in a burst.
+pub struct Scheduler<C: Clock> {
+    clock: C,
+    speed: u32,
+    cycle_duration: Duration,
+    frame_duration: Duration,
+    next_cycle: Instant,
+    next_frame: Instant,
+}
+
+impl<C: Clock> Scheduler<C> {
+    /// Creates a new `Scheduler` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `clock` - The clock to read the time from.
+    /// * `speed` - Execution speed in cycles per second.
+    /// * `frame_rate` - Frames per second.
+    pub fn new(clock: C, speed: u32, frame_rate: u32) -> Self {
+        let now = clock.now();
+        let cycle_duration = Self::period(speed);
+        let frame_duration = Self::period(frame_rate);
+
+        Scheduler {
+            clock,
+            speed,
+            cycle_duration,
+            frame_duration,
+            next_cycle: now + cycle_duration,
+            next_frame: now + frame_duration,
+        }
+    }
+
+    /// Returns the current time of the clock.
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// Returns the time between two cycles.
+    pub fn cycle_duration(&self) -> Duration {
+        self.cycle_duration
+    }
+
+    /// Changes the execution speed, starting with the cycle after the next one.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - Execution speed in cycles per second.
+    pub fn set_speed(&mut self, speed: u32) {
+        if speed != self.speed {
+            self.speed = speed;
+            self.cycle_duration = Self::period(speed);
+        }
+    }
+
+    /// Returns the execution speed in cycles per second.
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /// Returns `true` if a cycle is due, and schedules the next one.
+    pub fn cycle_due(&mut self) -> bool {
+        let now = self.clock.now();
+        Self::advance_deadline(
+            now,
+            &mut self.next_cycle,
+            self.cycle_duration,
+            self.frame_duration,
+        )
+    }
+
+    /// Returns `true` if a frame is due, and schedules the next one.
+    pub fn frame_due(&mut self) -> bool {
+        let now = self.clock.now();
+        Self::advance_deadline(
+            now,
+            &mut self.next_frame,
+            self.frame_duration,
+            self.frame_duration,
+        )
+    }
+
+    /// Returns the duration of one of `rate` periods per second.
+    fn period(rate: u32) -> Duration {
+        Duration::from_secs_f64(1.0 / rate.max(1) as f64)
+    }
+
+    fn advance_deadline(
+        now: Instant,
+        deadline: &mut Instant,
+        period: Duration,
+        max_lag: Duration,
+    ) -> bool {
+        if now < *deadline {
+            return false;
+        }
+
+        *deadline = if now - *deadline > max_lag {
+            now + period
+        } else {
+            *deadline + period
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_advance() {
+        let clock = MockClock::new();
+        let start = clock.now();
+
+        clock.advance(Duration::from_millis(5));
+
+        assert_eq!(clock.now() - start, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_scheduler_counts() {
+        let clock = MockClock::new();
+        let mut scheduler = Scheduler::new(&clock, 1000, 60);
+
+        let mut cycles = 0;
+        let mut frames = 0;
+        for _ in 0..110 {
+            clock.advance(Duration::from_millis(1));
+            while scheduler.cycle_due() {
+                cycles += 1;
+            }
+            while scheduler.frame_due() {
+                frames += 1;
+            }
+        }
+
+        assert_eq!(cycles, 110);
+        assert_eq!(frames, 6);
+    }
+
+    #[test]
+    fn test_scheduler_set_speed() {
+        let clock = MockClock::new();
+        let mut scheduler = Scheduler::new(&clock, 1000, 60);
+        assert_eq!(scheduler.cycle_duration(), Duration::from_millis(1));
+
+        scheduler.set_speed(500);
+        assert_eq!(scheduler.speed(), 500);
+        assert_eq!(scheduler.cycle_duration(), Duration::from_millis(2));
+
+        // The cycle already scheduled at 1 ms still runs, then they are 2 ms apart
+        let mut cycles = 0;
+        for _ in 0..9 {
+            clock.advance(Duration::from_millis(1));
+            while scheduler.cycle_due() {
+                cycles += 1;
+            }
+        }
+        assert_eq!(cycles, 5);
+    }
+
+    #[test]
+    fn test_scheduler_skips_backlog() {
+        let clock = MockClock::new();
+        let mut scheduler = Scheduler::new(&clock, 1000, 60);
+
+        clock.advance(Duration::from_secs(1));
+
+        let mut cycles = 0;
+        while scheduler.cycle_due() {
+            cycles += 1;
+        }
+        assert_eq!(cycles, 1);
+    }
+}
@@ -0,0 +1,118 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::Mode;
+
+/// An error raised while executing an instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// The opcode is not valid in the selected mode.
+    UnknownOpcode(u16),
+    /// The opcode is only valid in a mode with more capabilities than the selected one.
+    OpcodeRequiresMode { opcode: u16, needed: Mode },
+    /// A subroutine call exceeded the stack size.
+    StackOverflow,
+    /// A return was executed with an empty stack.
+    StackUnderflow,
+    /// An instruction accessed memory past its end.
+    MemoryOutOfBounds { address: usize },
+    /// A register index outside of `V0` through `VF` was given.
+    InvalidRegister(usize),
+    /// SDL2 failed to draw to or resize the window.
+    Display(String),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode(opcode) => write!(f, "Unknown opcode: {:04X}", opcode),
+            Chip8Error::OpcodeRequiresMode { opcode, needed } => write!(
+                f,
+                "Opcode {:04X} requires {} mode (try --mode {})",
+                opcode,
+                needed.name(),
+                needed.name()
+            ),
+            Chip8Error::StackOverflow => write!(f, "Stack overflow"),
+            Chip8Error::StackUnderflow => write!(f, "Stack underflow"),
+            Chip8Error::MemoryOutOfBounds { address } => {
+                write!(f, "Memory access out of bounds: 0x{:X}", address)
+            }
+            Chip8Error::InvalidRegister(index) => write!(f, "Invalid register index: {}", index),
+            Chip8Error::Display(message) => write!(f, "Display error: {}", message),
+        }
+    }
+}
+
+impl Error for Chip8Error {}
+
+/// An error raised while parsing a color.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColorError {
+    /// The color does not have 3 or 6 hex digits.
+    Length(String),
+    /// The color contains a character that is not a hex digit.
+    InvalidDigit(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::Length(text) => write!(
+                f,
+                "Invalid color \"{}\": expected #RRGGBB, RRGGBB, or #RGB",
+                text
+            ),
+            ColorError::InvalidDigit(text) => {
+                write!(f, "Invalid color \"{}\": not a hex number", text)
+            }
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+/// An error raised while creating a `Chip8` instance.
+#[derive(Debug)]
+pub enum InitError {
+    /// SDL2 itself failed to initialize.
+    Sdl(String),
+    /// The window or its canvas could not be created.
+    Display(Box<dyn Error>),
+    /// The event pump could not be obtained.
+    Input(Box<dyn Error>),
+    /// The audio device could not be opened. `Chip8::new` only warns about it and
+    /// runs without sound.
+    Audio(Box<dyn Error>),
+    /// The program could not be loaded into memory.
+    Program(io::Error),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InitError::Sdl(e) => write!(f, "Failed to initialize SDL2: {}", e),
+            InitError::Display(e) => write!(f, "Failed to open the display: {}", e),
+            InitError::Input(e) => write!(f, "Failed to set up input: {}", e),
+            InitError::Audio(e) => write!(f, "Failed to open the audio device: {}", e),
+            InitError::Program(e) => write!(f, "Failed to load the program: {}", e),
+        }
+    }
+}
+
+impl Error for InitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InitError::Sdl(_) => None,
+            InitError::Display(e) | InitError::Input(e) | InitError::Audio(e) => Some(e.as_ref()),
+            InitError::Program(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for InitError {
+    fn from(e: io::Error) -> Self {
+        InitError::Program(e)
+    }
+}
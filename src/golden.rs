@@ -0,0 +1,125 @@
+//! Golden snapshot tests for ROMs.
+//!
+//! A ROM runs headless for a number of cycles and the display is compared against a
+//! stored golden file, either as text from `to_ascii` or as a hash of the RGBA pixels.
+//! Set the `UPDATE_GOLDEN` environment variable to write the current output instead.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::Chip8Error;
+use crate::{fnv1a, Chip8, DEFAULT_SPEED, FRAME_RATE};
+
+/// How the display is captured for a snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Snapshot {
+    /// The display as text, with `#` for set and `.` for clear pixels.
+    Ascii,
+    /// A hash of the RGBA pixels, which also covers the palette.
+    RgbaHash,
+}
+
+/// Runs the machine for a number of cycles and captures the display.
+///
+/// The timers tick once every `700 / 60` cycles, like at the default speed.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run, usually created with `Chip8::new_for_test`.
+/// * `cycles` - Number of cycles to run.
+/// * `kind` - How to capture the display.
+///
+/// # Errors
+///
+/// Returns an error if an instruction fails to execute.
+pub fn snapshot(chip8: &mut Chip8, cycles: u64, kind: Snapshot) -> Result<String, Chip8Error> {
+    let cycles_per_frame = (DEFAULT_SPEED / FRAME_RATE) as u64;
+
+    for cycle in 1..=cycles {
+        chip8.emulate_cycle()?;
+        if cycle % cycles_per_frame == 0 {
+            chip8.update_timers();
+        }
+    }
+
+    Ok(match kind {
+        Snapshot::Ascii => chip8.display.to_ascii(),
+        Snapshot::RgbaHash => {
+            let (width, height) = (chip8.display.width(), chip8.display.height());
+            let mut rgba = vec![0; width * height * 4];
+            chip8.display.render_to_buffer(&mut rgba);
+            format!("{}x{} rgba fnv1a {:016x}\n", width, height, fnv1a(&rgba))
+        }
+    })
+}
+
+/// Runs the machine and compares the captured display with a golden file.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `cycles` - Number of cycles to run.
+/// * `kind` - How to capture the display.
+/// * `path` - The golden file.
+///
+/// # Panics
+///
+/// Panics with a line-by-line diff if the display doesn't match the golden file, or
+/// if the ROM fails or the golden file can't be read.
+pub fn assert_golden(chip8: &mut Chip8, cycles: u64, kind: Snapshot, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let actual = snapshot(chip8, cycles, kind)
+        .unwrap_or_else(|e| panic!("ROM failed before the snapshot: {}", e));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(path, &actual)
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    if let Some(diff) = diff(&expected, &actual) {
+        panic!(
+            "Display doesn't match {} (set UPDATE_GOLDEN=1 to accept it):\n{}",
+            path.display(),
+            diff
+        );
+    }
+}
+
+/// Lists the lines that differ between two snapshots, or returns `None` if they match.
+fn diff(expected: &str, actual: &str) -> Option<String> {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+
+    for line in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(line), actual.get(line));
+        if old != new {
+            out.push_str(&format!("line {}:\n", line + 1));
+            if let Some(old) = old {
+                out.push_str(&format!("  - {}\n", old));
+            }
+            if let Some(new) = new {
+                out.push_str(&format!("  + {}\n", new));
+            }
+        }
+    }
+
+    (!out.is_empty()).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("..#\n#..\n", "..#\n#..\n"), None);
+        assert_eq!(
+            diff("..#\n#..\n", "..#\n.#.\n..#\n").unwrap(),
+            "line 2:\n  - #..\n  + .#.\nline 3:\n  + ..#\n"
+        );
+    }
+}
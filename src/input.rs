@@ -1,11 +1,15 @@
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Scancode;
 use sdl2::EventPump;
 use sdl2::IntegerOrSdlError;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 const KEYS_NUM: usize = 16;
 
+const DEFAULT_QUIT_KEY: Scancode = Scancode::Escape;
+
 /// Maps specific Scancodes to corresponding hex values.
 const SCANCODE_TO_HEX_MAP: [(Scancode, u8); KEYS_NUM] = [
     (Scancode::Num1, 0x1),
@@ -26,6 +30,248 @@ const SCANCODE_TO_HEX_MAP: [(Scancode, u8); KEYS_NUM] = [
     (Scancode::V, 0xF),
 ];
 
+/// The QWERTY keys labeled 1234/QWER/ASDF/ZXCV on an AZERTY keyboard.
+const AZERTY_SCANCODE_TO_HEX_MAP: [(Scancode, u8); KEYS_NUM] = [
+    (Scancode::Num1, 0x1),
+    (Scancode::Num2, 0x2),
+    (Scancode::Num3, 0x3),
+    (Scancode::Num4, 0xC),
+    (Scancode::A, 0x4),
+    (Scancode::Z, 0x5),
+    (Scancode::E, 0x6),
+    (Scancode::R, 0xD),
+    (Scancode::Q, 0x7),
+    (Scancode::S, 0x8),
+    (Scancode::D, 0x9),
+    (Scancode::F, 0xE),
+    (Scancode::W, 0xA),
+    (Scancode::X, 0x0),
+    (Scancode::C, 0xB),
+    (Scancode::V, 0xF),
+];
+
+/// The keys labeled 1234/QWER/ASDF/ZXCV on a Dvorak keyboard.
+const DVORAK_SCANCODE_TO_HEX_MAP: [(Scancode, u8); KEYS_NUM] = [
+    (Scancode::Num1, 0x1),
+    (Scancode::Num2, 0x2),
+    (Scancode::Num3, 0x3),
+    (Scancode::Num4, 0xC),
+    (Scancode::X, 0x4),
+    (Scancode::Comma, 0x5),
+    (Scancode::D, 0x6),
+    (Scancode::O, 0xD),
+    (Scancode::A, 0x7),
+    (Scancode::Semicolon, 0x8),
+    (Scancode::H, 0x9),
+    (Scancode::Y, 0xE),
+    (Scancode::Slash, 0xA),
+    (Scancode::B, 0x0),
+    (Scancode::I, 0xB),
+    (Scancode::Period, 0xF),
+];
+
+/// Keyboard layouts that the keypad mapping can follow.
+///
+/// SDL scancodes name physical keys by their position on a US QWERTY keyboard, so the
+/// default mapping is positional: the keypad is the 4x4 block below the 1-4 keys on any
+/// layout. The other presets map by character instead, so the keys labeled 1234, QWER,
+/// ASDF, and ZXCV on that layout form the keypad, as named in the documentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    #[default]
+    Qwerty,
+    Azerty,
+    Dvorak,
+}
+
+impl Layout {
+    /// Returns the scancodes of the keypad keys with their hex values.
+    pub fn scancode_map(&self) -> &'static [(Scancode, u8); KEYS_NUM] {
+        match self {
+            Layout::Qwerty => &SCANCODE_TO_HEX_MAP,
+            Layout::Azerty => &AZERTY_SCANCODE_TO_HEX_MAP,
+            Layout::Dvorak => &DVORAK_SCANCODE_TO_HEX_MAP,
+        }
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "qwerty" => Ok(Layout::Qwerty),
+            "azerty" => Ok(Layout::Azerty),
+            "dvorak" => Ok(Layout::Dvorak),
+            _ => Err(format!(
+                "Unknown layout {} (expected qwerty, azerty, or dvorak)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Layout::Qwerty => "qwerty",
+            Layout::Azerty => "azerty",
+            Layout::Dvorak => "dvorak",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The hex keys of the CHIP-8 keypad, row by row, as laid out on the original hardware.
+pub const KEYPAD_LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+/// Maps specific Scancodes to emulator hotkeys.
+const SCANCODE_TO_HOTKEY_MAP: [(Scancode, Hotkey); 12] = [
+    (Scancode::PageDown, Hotkey::NextRom),
+    (Scancode::PageUp, Hotkey::PreviousRom),
+    (Scancode::M, Hotkey::ToggleMute),
+    (Scancode::F1, Hotkey::ToggleHud),
+    (Scancode::F2, Hotkey::CyclePalette),
+    (Scancode::F3, Hotkey::Reset),
+    (Scancode::F4, Hotkey::ToggleStats),
+    (Scancode::F5, Hotkey::TogglePause),
+    (Scancode::F6, Hotkey::TickCpu),
+    (Scancode::F7, Hotkey::TickTimers),
+    (Scancode::Equals, Hotkey::SpeedUp),
+    (Scancode::Minus, Hotkey::SpeedDown),
+];
+
+/// Emulator functions triggered from the keyboard, outside of the CHIP-8 keypad.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hotkey {
+    NextRom,
+    PreviousRom,
+    ToggleMute,
+    ToggleHud,
+    CyclePalette,
+    /// Restarts the loaded program.
+    Reset,
+    /// Shows or hides the frame rate and instructions per second.
+    ToggleStats,
+    TogglePause,
+    /// Executes one instruction while paused.
+    TickCpu,
+    /// Ticks the timers once while paused.
+    TickTimers,
+    /// Raises the execution speed by 100 Hz.
+    SpeedUp,
+    /// Lowers the execution speed by 100 Hz, down to 100 Hz.
+    SpeedDown,
+}
+
+/// A source of CHIP-8 keypad input.
+pub trait Input {
+    /// Updates the input state.
+    ///
+    /// Called after every cycle by `run`, and once per frame by `run_headless`.
+    fn update(&mut self);
+
+    /// Checks if a specific hex key is currently pressed.
+    ///
+    /// # Arguments
+    ///
+    /// * `hex_key` - The hex value of the key to check.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key is pressed, `false` otherwise.
+    fn is_key_pressed(&self, hex_key: u8) -> bool;
+
+    /// Gets the next key that was released.
+    ///
+    /// # Returns
+    ///
+    /// The hex value of the next released key, or `None` if no key was released.
+    fn get_next_released_key(&mut self) -> Option<u8>;
+
+    /// Marks a hex key as pressed.
+    ///
+    /// # Arguments
+    ///
+    /// * `hex_key` - The hex value of the key.
+    fn press_key(&mut self, hex_key: u8);
+
+    /// Marks a hex key as released, completing a pending wait for a key release.
+    ///
+    /// # Arguments
+    ///
+    /// * `hex_key` - The hex value of the key.
+    fn release_key(&mut self, hex_key: u8);
+
+    /// Replaces the pressed state of every key at once, e.g. to replay recorded input.
+    ///
+    /// Keys that change state go through `press_key` and `release_key`, so a release
+    /// still completes a pending wait for a key release.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The pressed state of each key, indexed by hex value.
+    fn set_key_state(&mut self, state: [bool; KEYS_NUM]) {
+        for (hex_key, pressed) in (0..).zip(state) {
+            if pressed == self.is_key_pressed(hex_key) {
+                continue;
+            }
+
+            if pressed {
+                self.press_key(hex_key);
+            } else {
+                self.release_key(hex_key);
+            }
+        }
+    }
+
+    /// Checks if a quit event has been received.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a quit event has been received, `false` otherwise.
+    fn should_quit(&self) -> bool;
+
+    /// Checks if the window has the keyboard focus.
+    ///
+    /// Input sources without a window are always focused.
+    fn is_focused(&self) -> bool {
+        true
+    }
+
+    /// Takes the hotkeys pressed since the last call.
+    ///
+    /// # Returns
+    ///
+    /// The pressed hotkeys, in the order they were pressed.
+    fn take_hotkeys(&mut self) -> Vec<Hotkey> {
+        Vec::new()
+    }
+
+    /// Sets the key that quits the emulator, in addition to closing the window.
+    ///
+    /// Input sources without a keyboard ignore it.
+    ///
+    /// # Arguments
+    ///
+    /// * `quit_key` - The quit key, or `None` to only quit when the window is closed.
+    fn set_quit_key(&mut self, _quit_key: Option<Scancode>) {}
+
+    /// Selects the keyboard layout that the keypad keys follow.
+    ///
+    /// Input sources without a keyboard ignore it.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The keyboard layout.
+    fn set_layout(&mut self, _layout: Layout) {}
+}
+
 /// Manages input using SDL2.
 pub struct InputManager {
     event_pump: Option<EventPump>,
@@ -33,7 +279,12 @@ pub struct InputManager {
     released_key_queue: Option<u8>,
     waiting_for_key: bool,
     quit: bool,
+    quit_key: Option<Scancode>,
     scancode_to_hex_map: HashMap<Scancode, u8>,
+    hotkeys: Vec<Hotkey>,
+    focused: bool,
+    text_input: bool,
+    text: String,
 }
 
 impl InputManager {
@@ -58,77 +309,248 @@ impl InputManager {
             released_key_queue: None,
             waiting_for_key: false,
             quit: false,
+            quit_key: Some(DEFAULT_QUIT_KEY),
             scancode_to_hex_map,
+            hotkeys: Vec::new(),
+            focused: true,
+            text_input: false,
+            text: String::new(),
         })
     }
 
-    /// Checks if a specific hex key is currently pressed.
+    /// Creates a new `InputManager` instance without an SDL event pump.
     ///
-    /// # Arguments
+    /// Key state can only be changed programmatically.
+    pub fn headless() -> Self {
+        InputManager {
+            event_pump: None,
+            key_state: [false; KEYS_NUM],
+            released_key_queue: None,
+            waiting_for_key: false,
+            quit: false,
+            quit_key: Some(DEFAULT_QUIT_KEY),
+            scancode_to_hex_map: SCANCODE_TO_HEX_MAP.iter().cloned().collect(),
+            hotkeys: Vec::new(),
+            focused: true,
+            text_input: false,
+            text: String::new(),
+        }
+    }
+
+    /// Returns the pressed state of every key, arranged like `KEYPAD_LAYOUT`.
     ///
-    /// * `hex_key` - The hex value of the key to check.
+    /// Useful for drawing an on-screen keypad.
+    pub fn keypad_state(&self) -> [[bool; 4]; 4] {
+        KEYPAD_LAYOUT.map(|row| row.map(|hex_key| self.is_key_pressed(hex_key)))
+    }
+
+    /// Enables or disables collecting typed text, e.g. for entering addresses in a monitor.
     ///
-    /// # Returns
+    /// Text is collected in addition to the keypad mapping. Disabling it discards the
+    /// text that wasn't taken yet.
     ///
-    /// `true` if the key is pressed, `false` otherwise.
-    pub fn is_key_pressed(&self, hex_key: u8) -> bool {
-        self.key_state[hex_key as usize]
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to collect typed text.
+    pub fn set_text_input(&mut self, enabled: bool) {
+        self.text_input = enabled;
+        if !enabled {
+            self.text.clear();
+        }
     }
 
-    /// Gets the next key that was released.
+    /// Returns the text typed since the last call, while text input is enabled.
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// Updates the state of the InputManager from a single SDL event.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// The hex value of the next released key, or `None` if no key was released.
-    pub fn get_next_released_key(&mut self) -> Option<u8> {
-        self.waiting_for_key = true;
-        self.released_key_queue.take()
+    /// * `event` - The event to process.
+    pub fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Quit { .. } => self.quit = true,
+            Event::Window {
+                win_event: WindowEvent::FocusGained,
+                ..
+            } => self.focused = true,
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => self.focused = false,
+            Event::TextInput { text, .. } if self.text_input => self.text.push_str(&text),
+            Event::KeyUp {
+                scancode: Some(scancode),
+                ..
+            } => {
+                if let Some(&hex_key) = self.scancode_to_hex_map.get(&scancode) {
+                    self.release_key(hex_key);
+                }
+            }
+            Event::KeyDown {
+                scancode: Some(scancode),
+                repeat,
+                ..
+            } => {
+                if let Some(&hex_key) = self.scancode_to_hex_map.get(&scancode) {
+                    self.press_key(hex_key);
+                }
+                if !repeat {
+                    if let Some(&(_, hotkey)) = SCANCODE_TO_HOTKEY_MAP
+                        .iter()
+                        .find(|(hotkey_scancode, _)| *hotkey_scancode == scancode)
+                    {
+                        self.hotkeys.push(hotkey);
+                    }
+                }
+                if self.quit_key == Some(scancode) {
+                    self.quit = true;
+                }
+            }
+            _ => {}
+        }
     }
+}
 
+impl Input for InputManager {
     /// Updates the state of the InputManager by processing SDL events.
-    pub fn update(&mut self) {
+    fn update(&mut self) {
         if let Some(event_pump) = self.event_pump.as_mut() {
             event_pump.pump_events();
 
-            for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } => self.quit = true,
-                    Event::KeyUp {
-                        scancode: Some(scancode),
-                        ..
-                    } => {
-                        if let Some(&hex_key) = self.scancode_to_hex_map.get(&scancode) {
-                            self.key_state[hex_key as usize] = false;
-                            if self.waiting_for_key {
-                                self.released_key_queue = Some(hex_key);
-                                self.waiting_for_key = false;
-                            }
-                        }
-                    }
-                    Event::KeyDown {
-                        scancode: Some(scancode),
-                        ..
-                    } => {
-                        if let Some(&hex_key) = self.scancode_to_hex_map.get(&scancode) {
-                            self.key_state[hex_key as usize] = true;
-                        }
-                        if scancode == Scancode::Escape {
-                            self.quit = true;
-                        }
-                    }
-                    _ => {}
-                }
+            let events: Vec<Event> = event_pump.poll_iter().collect();
+            for event in events {
+                self.handle_event(event);
             }
         }
     }
 
-    /// Checks if a quit event has been received.
+    fn is_key_pressed(&self, hex_key: u8) -> bool {
+        self.key_state[hex_key as usize]
+    }
+
+    fn get_next_released_key(&mut self) -> Option<u8> {
+        self.waiting_for_key = true;
+        self.released_key_queue.take()
+    }
+
+    fn press_key(&mut self, hex_key: u8) {
+        self.key_state[hex_key as usize] = true;
+    }
+
+    fn release_key(&mut self, hex_key: u8) {
+        self.key_state[hex_key as usize] = false;
+        if self.waiting_for_key {
+            self.released_key_queue = Some(hex_key);
+            self.waiting_for_key = false;
+        }
+    }
+
+    fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    fn take_hotkeys(&mut self) -> Vec<Hotkey> {
+        std::mem::take(&mut self.hotkeys)
+    }
+
+    fn set_quit_key(&mut self, quit_key: Option<Scancode>) {
+        self.quit_key = quit_key;
+    }
+
+    fn set_layout(&mut self, layout: Layout) {
+        self.scancode_to_hex_map = layout.scancode_map().iter().cloned().collect();
+    }
+}
+
+/// Plays back keypad input from a predefined per-frame schedule, without SDL.
+///
+/// Each call to `update` advances the schedule by one frame, starting at frame 0.
+#[derive(Default)]
+pub struct ScriptedInput {
+    events: Vec<(u64, u8, bool)>,
+    frame: u64,
+    key_state: [bool; KEYS_NUM],
+    released_key_queue: Option<u8>,
+    waiting_for_key: bool,
+}
+
+impl ScriptedInput {
+    /// Creates a new `ScriptedInput` instance with an empty schedule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedules a key press.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// `true` if a quit event has been received, `false` otherwise.
-    pub fn should_quit(&self) -> bool {
-        self.quit
+    /// * `frame` - The frame on which the key is pressed.
+    /// * `hex_key` - The hex value of the key.
+    pub fn press(mut self, frame: u64, hex_key: u8) -> Self {
+        self.events.push((frame, hex_key, true));
+        self
+    }
+
+    /// Schedules a key release.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame on which the key is released.
+    /// * `hex_key` - The hex value of the key.
+    pub fn release(mut self, frame: u64, hex_key: u8) -> Self {
+        self.events.push((frame, hex_key, false));
+        self
+    }
+}
+
+impl Input for ScriptedInput {
+    /// Applies the events scheduled for the current frame and advances to the next one.
+    fn update(&mut self) {
+        for index in 0..self.events.len() {
+            let (frame, hex_key, pressed) = self.events[index];
+            if frame != self.frame {
+                continue;
+            }
+
+            if pressed {
+                self.press_key(hex_key);
+            } else {
+                self.release_key(hex_key);
+            }
+        }
+        self.frame += 1;
+    }
+
+    fn is_key_pressed(&self, hex_key: u8) -> bool {
+        self.key_state[hex_key as usize]
+    }
+
+    fn get_next_released_key(&mut self) -> Option<u8> {
+        self.waiting_for_key = true;
+        self.released_key_queue.take()
+    }
+
+    fn press_key(&mut self, hex_key: u8) {
+        self.key_state[hex_key as usize] = true;
+    }
+
+    fn release_key(&mut self, hex_key: u8) {
+        self.key_state[hex_key as usize] = false;
+        if self.waiting_for_key {
+            self.released_key_queue = Some(hex_key);
+            self.waiting_for_key = false;
+        }
+    }
+
+    fn should_quit(&self) -> bool {
+        false
     }
 }
 
@@ -146,7 +568,12 @@ mod tests {
             released_key_queue: None,
             waiting_for_key: false,
             quit: false,
+            quit_key: Some(DEFAULT_QUIT_KEY),
             scancode_to_hex_map,
+            hotkeys: Vec::new(),
+            focused: true,
+            text_input: false,
+            text: String::new(),
         }
     }
 
@@ -161,6 +588,51 @@ mod tests {
         assert!(!input_manager.is_key_pressed(0x2));
     }
 
+    #[test]
+    fn test_set_key_state() {
+        let mut input_manager = create_test_input_manager();
+        input_manager.press_key(0x2);
+
+        let mut state = [false; KEYS_NUM];
+        state[0x0] = true;
+        state[0x5] = true;
+        state[0xF] = true;
+        input_manager.set_key_state(state);
+
+        assert!(input_manager.is_key_pressed(0x0));
+        assert!(input_manager.is_key_pressed(0x5));
+        assert!(input_manager.is_key_pressed(0xF));
+        assert!(!input_manager.is_key_pressed(0x2));
+        assert!(!input_manager.is_key_pressed(0xA));
+
+        // Releasing through the new state completes a wait for a key release
+        assert_eq!(input_manager.get_next_released_key(), None);
+        input_manager.set_key_state([false; KEYS_NUM]);
+        assert_eq!(input_manager.get_next_released_key(), Some(0x0));
+    }
+
+    #[test]
+    fn test_azerty_layout() {
+        let mut input_manager = create_test_input_manager();
+        input_manager.set_layout(Layout::Azerty);
+
+        // The keys labeled A and Q on AZERTY swap places compared to QWERTY
+        input_manager.handle_event(key_down(Scancode::A));
+        assert!(input_manager.is_key_pressed(0x4));
+        input_manager.handle_event(key_down(Scancode::Q));
+        assert!(input_manager.is_key_pressed(0x7));
+        input_manager.handle_event(key_down(Scancode::W));
+        assert!(input_manager.is_key_pressed(0xA));
+        input_manager.handle_event(key_down(Scancode::Z));
+        assert!(input_manager.is_key_pressed(0x5));
+        input_manager.handle_event(key_down(Scancode::E));
+        assert!(input_manager.is_key_pressed(0x6));
+        assert!(!input_manager.is_key_pressed(0x8));
+
+        assert_eq!("AZERTY".parse(), Ok(Layout::Azerty));
+        assert!("colemak".parse::<Layout>().is_err());
+    }
+
     #[test]
     fn test_get_next_released_key() {
         let mut input_manager = create_test_input_manager();
@@ -172,6 +644,169 @@ mod tests {
         assert_eq!(input_manager.get_next_released_key(), None); // Queue should be empty now
     }
 
+    #[test]
+    fn test_take_hotkeys() {
+        let mut input_manager = create_test_input_manager();
+
+        input_manager.hotkeys.push(Hotkey::NextRom);
+
+        assert_eq!(input_manager.take_hotkeys(), vec![Hotkey::NextRom]);
+        assert!(input_manager.take_hotkeys().is_empty());
+    }
+
+    fn key_down(scancode: Scancode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: None,
+            scancode: Some(scancode),
+            keymod: sdl2::keyboard::Mod::empty(),
+            repeat: false,
+        }
+    }
+
+    fn window_event(win_event: WindowEvent) -> Event {
+        Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event,
+        }
+    }
+
+    #[test]
+    fn test_focus() {
+        let mut input_manager = create_test_input_manager();
+        assert!(input_manager.is_focused());
+
+        input_manager.handle_event(window_event(WindowEvent::FocusLost));
+        assert!(!input_manager.is_focused());
+
+        input_manager.handle_event(window_event(WindowEvent::Exposed));
+        assert!(!input_manager.is_focused());
+
+        input_manager.handle_event(window_event(WindowEvent::FocusGained));
+        assert!(input_manager.is_focused());
+    }
+
+    fn text_input(text: &str) -> Event {
+        Event::TextInput {
+            timestamp: 0,
+            window_id: 0,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_text_input() {
+        let mut input_manager = create_test_input_manager();
+
+        // Text is ignored until text input is enabled
+        input_manager.handle_event(text_input("1"));
+        assert_eq!(input_manager.take_text(), "");
+
+        input_manager.set_text_input(true);
+        input_manager.handle_event(text_input("2"));
+        input_manager.handle_event(text_input("0a"));
+        assert_eq!(input_manager.take_text(), "20a");
+        assert_eq!(input_manager.take_text(), "");
+
+        input_manager.handle_event(text_input("F"));
+        input_manager.set_text_input(false);
+        assert_eq!(input_manager.take_text(), "");
+    }
+
+    #[test]
+    fn test_default_quit_key() {
+        let mut input_manager = create_test_input_manager();
+
+        input_manager.handle_event(key_down(Scancode::Escape));
+        assert!(input_manager.should_quit());
+    }
+
+    #[test]
+    fn test_set_quit_key() {
+        let mut input_manager = create_test_input_manager();
+        input_manager.set_quit_key(Some(Scancode::F10));
+
+        input_manager.handle_event(key_down(Scancode::Escape));
+        assert!(!input_manager.should_quit());
+
+        input_manager.handle_event(key_down(Scancode::F10));
+        assert!(input_manager.should_quit());
+    }
+
+    #[test]
+    fn test_disabled_quit_key() {
+        let mut input_manager = create_test_input_manager();
+        input_manager.set_quit_key(None);
+
+        input_manager.handle_event(key_down(Scancode::Escape));
+        assert!(!input_manager.should_quit());
+
+        input_manager.handle_event(Event::Quit { timestamp: 0 });
+        assert!(input_manager.should_quit());
+    }
+
+    #[test]
+    fn test_scripted_input() {
+        let mut input = ScriptedInput::new().press(1, 0x5).release(2, 0x5);
+
+        input.update();
+        assert!(!input.is_key_pressed(0x5));
+
+        input.update();
+        assert!(input.is_key_pressed(0x5));
+
+        assert_eq!(input.get_next_released_key(), None);
+        input.update();
+        assert!(!input.is_key_pressed(0x5));
+        assert_eq!(input.get_next_released_key(), Some(0x5));
+    }
+
+    #[test]
+    fn test_keypad_layout() {
+        let keys: Vec<u8> = KEYPAD_LAYOUT.iter().flatten().copied().collect();
+        assert_eq!(
+            keys,
+            [0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF]
+        );
+
+        // The layout matches the physical keys the hex keys are mapped to
+        let scancodes = [
+            [
+                Scancode::Num1,
+                Scancode::Num2,
+                Scancode::Num3,
+                Scancode::Num4,
+            ],
+            [Scancode::Q, Scancode::W, Scancode::E, Scancode::R],
+            [Scancode::A, Scancode::S, Scancode::D, Scancode::F],
+            [Scancode::Z, Scancode::X, Scancode::C, Scancode::V],
+        ];
+        let input_manager = create_test_input_manager();
+        for (scancode_row, hex_row) in scancodes.iter().zip(KEYPAD_LAYOUT) {
+            for (scancode, hex_key) in scancode_row.iter().zip(hex_row) {
+                assert_eq!(input_manager.scancode_to_hex_map[scancode], hex_key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_keypad_state() {
+        let mut input_manager = create_test_input_manager();
+        input_manager.press_key(0xC);
+        input_manager.press_key(0x0);
+
+        let state = input_manager.keypad_state();
+
+        assert!(state[0][3]);
+        assert!(state[3][1]);
+        assert_eq!(
+            state.iter().flatten().filter(|&&pressed| pressed).count(),
+            2
+        );
+    }
+
     #[test]
     fn test_should_quit() {
         let mut input_manager = create_test_input_manager();
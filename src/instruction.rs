@@ -0,0 +1,962 @@
+use std::fmt;
+
+use crate::quirks::{LoadStoreIncrement, Quirks};
+use crate::Mode;
+
+/// A decoded CHIP-8, SuperChip, or XO-CHIP instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// 0nnn - SYS addr
+    Sys { nnn: u16 },
+    /// 00Cn - SCD n
+    ScrollDown { n: u8 },
+    /// 00Dn - SCU n
+    ScrollUp { n: u8 },
+    /// 00E0 - CLS
+    Cls,
+    /// 00EE - RET
+    Ret,
+    /// 00FB - SCR
+    ScrollRight,
+    /// 00FC - SCL
+    ScrollLeft,
+    /// 00FD - EXIT
+    Exit,
+    /// 00FE - LORES
+    Lores,
+    /// 00FF - HIRES
+    Hires,
+    /// 1nnn - JP addr
+    Jp { nnn: u16 },
+    /// 2nnn - CALL addr
+    Call { nnn: u16 },
+    /// 3xkk - SE Vx, byte
+    SeVxByte { x: usize, kk: u8 },
+    /// 4xkk - SNE Vx, byte
+    SneVxByte { x: usize, kk: u8 },
+    /// 5xy0 - SE Vx, Vy
+    SeVxVy { x: usize, y: usize },
+    /// 5xy2 - SAVE Vx - Vy
+    SaveRange { x: usize, y: usize },
+    /// 5xy3 - LOAD Vx - Vy
+    LoadRange { x: usize, y: usize },
+    /// 6xkk - LD Vx, byte
+    LdVxByte { x: usize, kk: u8 },
+    /// 7xkk - ADD Vx, byte
+    AddVxByte { x: usize, kk: u8 },
+    /// 8xy0 - LD Vx, Vy
+    LdVxVy { x: usize, y: usize },
+    /// 8xy1 - OR Vx, Vy
+    Or { x: usize, y: usize },
+    /// 8xy2 - AND Vx, Vy
+    And { x: usize, y: usize },
+    /// 8xy3 - XOR Vx, Vy
+    Xor { x: usize, y: usize },
+    /// 8xy4 - ADD Vx, Vy
+    AddVxVy { x: usize, y: usize },
+    /// 8xy5 - SUB Vx, Vy
+    Sub { x: usize, y: usize },
+    /// 8xy6 - SHR Vx {, Vy}
+    Shr { x: usize, y: usize },
+    /// 8xy7 - SUBN Vx, Vy
+    Subn { x: usize, y: usize },
+    /// 8xyE - SHL Vx {, Vy}
+    Shl { x: usize, y: usize },
+    /// 9xy0 - SNE Vx, Vy
+    SneVxVy { x: usize, y: usize },
+    /// Annn - LD I, addr
+    LdI { nnn: u16 },
+    /// Bnnn - JP V0, addr
+    JpV0 { nnn: u16 },
+    /// Bxnn - JP Vx, addr. `nnn` is the whole address, so its high nibble is `x`.
+    JpVx { x: usize, nnn: u16 },
+    /// Cxkk - RND Vx, byte
+    Rnd { x: usize, kk: u8 },
+    /// Dxyn - DRW Vx, Vy, nibble
+    DrawSprite { x: usize, y: usize, n: u8 },
+    /// Ex9E - SKP Vx
+    Skp { x: usize },
+    /// ExA1 - SKNP Vx
+    Sknp { x: usize },
+    /// Fn01 - PLANE n
+    Plane { n: u8 },
+    /// Fx07 - LD Vx, DT
+    LdVxDt { x: usize },
+    /// Fx0A - LD Vx, K
+    LdVxK { x: usize },
+    /// Fx15 - LD DT, Vx
+    LdDtVx { x: usize },
+    /// Fx18 - LD ST, Vx
+    LdStVx { x: usize },
+    /// Fx1E - ADD I, Vx
+    AddIVx { x: usize },
+    /// Fx29 - LD F, Vx
+    LdFVx { x: usize },
+    /// Fx33 - LD B, Vx
+    LdBVx { x: usize },
+    /// Fx55 - LD [I], Vx
+    LdIVx { x: usize },
+    /// Fx65 - LD Vx, [I]
+    LdVxI { x: usize },
+    /// An opcode that is not valid in the selected mode.
+    Unknown { opcode: u16 },
+}
+
+impl Instruction {
+    /// Encodes the instruction into its opcode, the inverse of `decode`.
+    ///
+    /// The opcode of an instruction is the same in every mode; the mode only selects
+    /// which instruction an opcode decodes back to, e.g. `JpV0` or `JpVx` for `Bnnn`.
+    ///
+    /// # Returns
+    ///
+    /// The opcode. Register indices, addresses, and immediates are truncated to their field.
+    pub fn encode(&self) -> u16 {
+        let nnn = |nnn: u16| nnn & 0x0FFF;
+        let x = |x: usize| ((x & 0xF) as u16) << 8;
+        let y = |y: usize| ((y & 0xF) as u16) << 4;
+        let n = |n: u8| (n & 0xF) as u16;
+        let kk = |kk: u8| kk as u16;
+
+        match *self {
+            Instruction::Sys { nnn: a } => nnn(a),
+            Instruction::ScrollDown { n: b } => 0x00C0 | n(b),
+            Instruction::ScrollUp { n: b } => 0x00D0 | n(b),
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Lores => 0x00FE,
+            Instruction::Hires => 0x00FF,
+            Instruction::Jp { nnn: a } => 0x1000 | nnn(a),
+            Instruction::Call { nnn: a } => 0x2000 | nnn(a),
+            Instruction::SeVxByte { x: vx, kk: b } => 0x3000 | x(vx) | kk(b),
+            Instruction::SneVxByte { x: vx, kk: b } => 0x4000 | x(vx) | kk(b),
+            Instruction::SeVxVy { x: vx, y: vy } => 0x5000 | x(vx) | y(vy),
+            Instruction::SaveRange { x: vx, y: vy } => 0x5002 | x(vx) | y(vy),
+            Instruction::LoadRange { x: vx, y: vy } => 0x5003 | x(vx) | y(vy),
+            Instruction::LdVxByte { x: vx, kk: b } => 0x6000 | x(vx) | kk(b),
+            Instruction::AddVxByte { x: vx, kk: b } => 0x7000 | x(vx) | kk(b),
+            Instruction::LdVxVy { x: vx, y: vy } => 0x8000 | x(vx) | y(vy),
+            Instruction::Or { x: vx, y: vy } => 0x8001 | x(vx) | y(vy),
+            Instruction::And { x: vx, y: vy } => 0x8002 | x(vx) | y(vy),
+            Instruction::Xor { x: vx, y: vy } => 0x8003 | x(vx) | y(vy),
+            Instruction::AddVxVy { x: vx, y: vy } => 0x8004 | x(vx) | y(vy),
+            Instruction::Sub { x: vx, y: vy } => 0x8005 | x(vx) | y(vy),
+            Instruction::Shr { x: vx, y: vy } => 0x8006 | x(vx) | y(vy),
+            Instruction::Subn { x: vx, y: vy } => 0x8007 | x(vx) | y(vy),
+            Instruction::Shl { x: vx, y: vy } => 0x800E | x(vx) | y(vy),
+            Instruction::SneVxVy { x: vx, y: vy } => 0x9000 | x(vx) | y(vy),
+            Instruction::LdI { nnn: a } => 0xA000 | nnn(a),
+            Instruction::JpV0 { nnn: a } => 0xB000 | nnn(a),
+            Instruction::JpVx { x: vx, nnn: a } => {
+                debug_assert_eq!(x(vx), a & 0x0F00, "the address of Bxnn must start with x");
+                0xB000 | nnn(a)
+            }
+            Instruction::Rnd { x: vx, kk: b } => 0xC000 | x(vx) | kk(b),
+            Instruction::DrawSprite { x: vx, y: vy, n: b } => 0xD000 | x(vx) | y(vy) | n(b),
+            Instruction::Skp { x: vx } => 0xE09E | x(vx),
+            Instruction::Sknp { x: vx } => 0xE0A1 | x(vx),
+            Instruction::LdVxDt { x: vx } => 0xF007 | x(vx),
+            Instruction::Plane { n: planes } => 0xF001 | ((planes & 0xF) as u16) << 8,
+            Instruction::LdVxK { x: vx } => 0xF00A | x(vx),
+            Instruction::LdDtVx { x: vx } => 0xF015 | x(vx),
+            Instruction::LdStVx { x: vx } => 0xF018 | x(vx),
+            Instruction::AddIVx { x: vx } => 0xF01E | x(vx),
+            Instruction::LdFVx { x: vx } => 0xF029 | x(vx),
+            Instruction::LdBVx { x: vx } => 0xF033 | x(vx),
+            Instruction::LdIVx { x: vx } => 0xF055 | x(vx),
+            Instruction::LdVxI { x: vx } => 0xF065 | x(vx),
+            Instruction::Unknown { opcode } => opcode,
+        }
+    }
+
+    /// Describes what the instruction does and which state it modifies, without executing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `quirks` - The quirks in effect, which select side effects like VF resets and
+    ///   `I` increments.
+    ///
+    /// # Returns
+    ///
+    /// A sentence describing the instruction, followed by the modified registers and flags.
+    pub fn explain(&self, quirks: &Quirks) -> String {
+        let v = |x: usize| format!("V{:X}", x);
+        let resets_vf = quirks.reset_vf_on_logic;
+        let increments_i = quirks.load_store_increment != LoadStoreIncrement::None;
+        let shifts_vy = quirks.shift_uses_vy;
+
+        let (description, modifies): (String, Vec<String>) = match *self {
+            Instruction::Sys { nnn } => (
+                format!(
+                    "Calls the machine code routine at 0x{:03X}, which is ignored",
+                    nnn
+                ),
+                vec![],
+            ),
+            Instruction::ScrollDown { n } => (
+                format!("Scrolls the display down by {} pixels", n),
+                vec!["display".into()],
+            ),
+            Instruction::ScrollUp { n } => (
+                format!("Scrolls the display up by {} pixels", n),
+                vec!["display".into()],
+            ),
+            Instruction::Cls => ("Clears the display".into(), vec!["display".into()]),
+            Instruction::Ret => (
+                "Returns from a subroutine to the address on top of the stack".into(),
+                vec!["PC".into(), "SP".into()],
+            ),
+            Instruction::ScrollRight => (
+                "Scrolls the display right by 4 pixels".into(),
+                vec!["display".into()],
+            ),
+            Instruction::ScrollLeft => (
+                "Scrolls the display left by 4 pixels".into(),
+                vec!["display".into()],
+            ),
+            Instruction::Exit => (
+                "Halts the program until the machine is reset".into(),
+                vec!["PC".into()],
+            ),
+            Instruction::Lores => (
+                "Switches to the low resolution".into(),
+                vec!["display".into()],
+            ),
+            Instruction::Hires => (
+                "Switches to the high resolution".into(),
+                vec!["display".into()],
+            ),
+            Instruction::Jp { nnn } => (format!("Jumps to 0x{:03X}", nnn), vec!["PC".into()]),
+            Instruction::Call { nnn } => (
+                format!(
+                    "Calls the subroutine at 0x{:03X}, pushing the return address",
+                    nnn
+                ),
+                vec!["PC".into(), "SP".into(), "stack".into()],
+            ),
+            Instruction::SeVxByte { x, kk } => (
+                format!("Skips the next instruction if {} equals 0x{:02X}", v(x), kk),
+                vec!["PC".into()],
+            ),
+            Instruction::SneVxByte { x, kk } => (
+                format!("Skips the next instruction if {} is not 0x{:02X}", v(x), kk),
+                vec!["PC".into()],
+            ),
+            Instruction::SeVxVy { x, y } => (
+                format!("Skips the next instruction if {} equals {}", v(x), v(y)),
+                vec!["PC".into()],
+            ),
+            Instruction::SaveRange { x, y } => (
+                format!("Stores {} through {} in memory at I", v(x), v(y)),
+                vec!["memory".into()],
+            ),
+            Instruction::LoadRange { x, y } => {
+                let registers = if x <= y {
+                    (x..=y).map(v).collect::<Vec<_>>()
+                } else {
+                    (y..=x).rev().map(v).collect()
+                };
+                (
+                    format!("Loads {} through {} from memory at I", v(x), v(y)),
+                    vec![registers.join(", ")],
+                )
+            }
+            Instruction::LdVxByte { x, kk } => {
+                (format!("Sets {} to 0x{:02X}", v(x), kk), vec![v(x)])
+            }
+            Instruction::AddVxByte { x, kk } => (
+                format!("Adds 0x{:02X} to {} without affecting VF", kk, v(x)),
+                vec![v(x)],
+            ),
+            Instruction::LdVxVy { x, y } => (format!("Sets {} to {}", v(x), v(y)), vec![v(x)]),
+            Instruction::Or { x, y } | Instruction::And { x, y } | Instruction::Xor { x, y } => {
+                let operation = match self {
+                    Instruction::Or { .. } => "OR",
+                    Instruction::And { .. } => "AND",
+                    _ => "XOR",
+                };
+                let description = format!("Sets {} to {} {} {}", v(x), v(x), operation, v(y));
+                if resets_vf {
+                    (description + " and resets VF to 0", vec![v(x), "VF".into()])
+                } else {
+                    (description, vec![v(x)])
+                }
+            }
+            Instruction::AddVxVy { x, y } => (
+                format!(
+                    "Adds {} to {}, setting VF to 1 on carry and 0 otherwise",
+                    v(y),
+                    v(x)
+                ),
+                vec![v(x), "VF".into()],
+            ),
+            Instruction::Sub { x, y } => (
+                format!(
+                    "Subtracts {} from {}, setting VF to 0 on borrow and 1 otherwise",
+                    v(y),
+                    v(x)
+                ),
+                vec![v(x), "VF".into()],
+            ),
+            Instruction::Subn { x, y } => (
+                format!(
+                    "Sets {} to {} minus {}, setting VF to 0 on borrow and 1 otherwise",
+                    v(x),
+                    v(y),
+                    v(x)
+                ),
+                vec![v(x), "VF".into()],
+            ),
+            Instruction::Shr { x, y } | Instruction::Shl { x, y } => {
+                let (direction, bit) = match self {
+                    Instruction::Shr { .. } => ("right", "lowest"),
+                    _ => ("left", "highest"),
+                };
+                let source = if shifts_vy { v(y) } else { v(x) };
+                (
+                    format!(
+                        "Sets {} to {} shifted {} by one, setting VF to the {} bit shifted out",
+                        v(x),
+                        source,
+                        direction,
+                        bit
+                    ),
+                    vec![v(x), "VF".into()],
+                )
+            }
+            Instruction::SneVxVy { x, y } => (
+                format!("Skips the next instruction if {} is not {}", v(x), v(y)),
+                vec!["PC".into()],
+            ),
+            Instruction::LdI { nnn } => (format!("Sets I to 0x{:03X}", nnn), vec!["I".into()]),
+            Instruction::JpV0 { nnn } => {
+                (format!("Jumps to 0x{:03X} plus V0", nnn), vec!["PC".into()])
+            }
+            Instruction::JpVx { x, nnn } => (
+                format!("Jumps to 0x{:03X} plus {}", nnn, v(x)),
+                vec!["PC".into()],
+            ),
+            Instruction::Rnd { x, kk } => (
+                format!("Sets {} to a random byte AND 0x{:02X}", v(x), kk),
+                vec![v(x)],
+            ),
+            Instruction::DrawSprite { x, y, n } => (
+                format!(
+                    "Draws the {}-byte sprite at I at ({}, {}) with XOR, setting VF to 1 on \
+                     collision and 0 otherwise",
+                    n,
+                    v(x),
+                    v(y)
+                ),
+                vec!["display".into(), "VF".into()],
+            ),
+            Instruction::Skp { x } => (
+                format!(
+                    "Skips the next instruction if the key in {} is pressed",
+                    v(x)
+                ),
+                vec!["PC".into()],
+            ),
+            Instruction::Sknp { x } => (
+                format!(
+                    "Skips the next instruction if the key in {} is not pressed",
+                    v(x)
+                ),
+                vec!["PC".into()],
+            ),
+            Instruction::Plane { n } => (
+                format!("Selects the bitplanes 0b{:02b} for drawing", n & 0x3),
+                vec!["planes".into()],
+            ),
+            Instruction::LdVxDt { x } => (format!("Sets {} to the delay timer", v(x)), vec![v(x)]),
+            Instruction::LdVxK { x } => (
+                format!("Waits for a key release and stores the key in {}", v(x)),
+                vec![v(x)],
+            ),
+            Instruction::LdDtVx { x } => (
+                format!("Sets the delay timer to {}", v(x)),
+                vec!["DT".into()],
+            ),
+            Instruction::LdStVx { x } => (
+                format!("Sets the sound timer to {}", v(x)),
+                vec!["ST".into()],
+            ),
+            Instruction::AddIVx { x } => (format!("Adds {} to I", v(x)), vec!["I".into()]),
+            Instruction::LdFVx { x } => (
+                format!("Points I at the font glyph for the digit in {}", v(x)),
+                vec!["I".into()],
+            ),
+            Instruction::LdBVx { x } => (
+                format!("Stores the decimal digits of {} at I, I+1, and I+2", v(x)),
+                vec!["memory".into()],
+            ),
+            Instruction::LdIVx { x } => {
+                let description = format!("Stores V0 through {} in memory at I", v(x));
+                if increments_i {
+                    (
+                        description + " and advances I past them",
+                        vec!["memory".into(), "I".into()],
+                    )
+                } else {
+                    (description, vec!["memory".into()])
+                }
+            }
+            Instruction::LdVxI { x } => {
+                let description = format!("Loads V0 through {} from memory at I", v(x));
+                let registers = (0..=x).map(v).collect::<Vec<_>>().join(", ");
+                if increments_i {
+                    (
+                        description + " and advances I past them",
+                        vec![registers, "I".into()],
+                    )
+                } else {
+                    (description, vec![registers])
+                }
+            }
+            Instruction::Unknown { opcode } => (
+                format!("Unknown opcode 0x{:04X}, which stops execution", opcode),
+                vec![],
+            ),
+        };
+
+        if modifies.is_empty() {
+            format!("{}. Modifies nothing.", description)
+        } else {
+            format!("{}. Modifies {}.", description, modifies.join(", "))
+        }
+    }
+
+    /// Returns the mnemonic of the instruction, e.g. `LD` for every load or `DW` for
+    /// unknown opcodes, as used by the `Display` implementation.
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Sys { .. } => "SYS",
+            Instruction::ScrollDown { .. } => "SCD",
+            Instruction::ScrollUp { .. } => "SCU",
+            Instruction::Cls => "CLS",
+            Instruction::Ret => "RET",
+            Instruction::ScrollRight => "SCR",
+            Instruction::ScrollLeft => "SCL",
+            Instruction::Exit => "EXIT",
+            Instruction::Lores => "LORES",
+            Instruction::Hires => "HIRES",
+            Instruction::Jp { .. } | Instruction::JpV0 { .. } | Instruction::JpVx { .. } => "JP",
+            Instruction::Call { .. } => "CALL",
+            Instruction::SeVxByte { .. } | Instruction::SeVxVy { .. } => "SE",
+            Instruction::SneVxByte { .. } | Instruction::SneVxVy { .. } => "SNE",
+            Instruction::SaveRange { .. } => "SAVE",
+            Instruction::LoadRange { .. } => "LOAD",
+            Instruction::LdVxByte { .. }
+            | Instruction::LdVxVy { .. }
+            | Instruction::LdI { .. }
+            | Instruction::LdVxDt { .. }
+            | Instruction::LdVxK { .. }
+            | Instruction::LdDtVx { .. }
+            | Instruction::LdStVx { .. }
+            | Instruction::LdFVx { .. }
+            | Instruction::LdBVx { .. }
+            | Instruction::LdIVx { .. }
+            | Instruction::LdVxI { .. } => "LD",
+            Instruction::AddVxByte { .. }
+            | Instruction::AddVxVy { .. }
+            | Instruction::AddIVx { .. } => "ADD",
+            Instruction::Or { .. } => "OR",
+            Instruction::And { .. } => "AND",
+            Instruction::Xor { .. } => "XOR",
+            Instruction::Sub { .. } => "SUB",
+            Instruction::Shr { .. } => "SHR",
+            Instruction::Subn { .. } => "SUBN",
+            Instruction::Shl { .. } => "SHL",
+            Instruction::Rnd { .. } => "RND",
+            Instruction::DrawSprite { .. } => "DRW",
+            Instruction::Skp { .. } => "SKP",
+            Instruction::Sknp { .. } => "SKNP",
+            Instruction::Plane { .. } => "PLANE",
+            Instruction::Unknown { .. } => "DW",
+        }
+    }
+
+    /// Returns the `x` and `y` register indices the instruction names, e.g. to check
+    /// them before executing a hand-built instruction.
+    pub fn registers(&self) -> (Option<usize>, Option<usize>) {
+        match *self {
+            Instruction::SeVxVy { x, y }
+            | Instruction::SaveRange { x, y }
+            | Instruction::LoadRange { x, y }
+            | Instruction::LdVxVy { x, y }
+            | Instruction::Or { x, y }
+            | Instruction::And { x, y }
+            | Instruction::Xor { x, y }
+            | Instruction::AddVxVy { x, y }
+            | Instruction::Sub { x, y }
+            | Instruction::Shr { x, y }
+            | Instruction::Subn { x, y }
+            | Instruction::Shl { x, y }
+            | Instruction::SneVxVy { x, y }
+            | Instruction::DrawSprite { x, y, .. } => (Some(x), Some(y)),
+            Instruction::SeVxByte { x, .. }
+            | Instruction::SneVxByte { x, .. }
+            | Instruction::LdVxByte { x, .. }
+            | Instruction::AddVxByte { x, .. }
+            | Instruction::JpVx { x, .. }
+            | Instruction::Rnd { x, .. }
+            | Instruction::Skp { x }
+            | Instruction::Sknp { x }
+            | Instruction::LdVxDt { x }
+            | Instruction::LdVxK { x }
+            | Instruction::LdDtVx { x }
+            | Instruction::LdStVx { x }
+            | Instruction::AddIVx { x }
+            | Instruction::LdFVx { x }
+            | Instruction::LdBVx { x }
+            | Instruction::LdIVx { x }
+            | Instruction::LdVxI { x } => (Some(x), None),
+            _ => (None, None),
+        }
+    }
+}
+
+/// Formats the instruction in assembly syntax, e.g. `LD V1, 0x2A` or `DRW V0, V1, 5`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mnemonic())?;
+        match *self {
+            Instruction::Cls
+            | Instruction::Ret
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::Exit
+            | Instruction::Lores
+            | Instruction::Hires => Ok(()),
+            Instruction::Sys { nnn } | Instruction::Jp { nnn } | Instruction::Call { nnn } => {
+                write!(f, " 0x{:03X}", nnn)
+            }
+            Instruction::ScrollDown { n }
+            | Instruction::ScrollUp { n }
+            | Instruction::Plane { n } => write!(f, " {}", n),
+            Instruction::SeVxByte { x, kk }
+            | Instruction::SneVxByte { x, kk }
+            | Instruction::LdVxByte { x, kk }
+            | Instruction::AddVxByte { x, kk }
+            | Instruction::Rnd { x, kk } => write!(f, " V{:X}, 0x{:02X}", x, kk),
+            Instruction::SeVxVy { x, y }
+            | Instruction::SneVxVy { x, y }
+            | Instruction::LdVxVy { x, y }
+            | Instruction::Or { x, y }
+            | Instruction::And { x, y }
+            | Instruction::Xor { x, y }
+            | Instruction::AddVxVy { x, y }
+            | Instruction::Sub { x, y }
+            | Instruction::Shr { x, y }
+            | Instruction::Subn { x, y }
+            | Instruction::Shl { x, y } => write!(f, " V{:X}, V{:X}", x, y),
+            Instruction::SaveRange { x, y } | Instruction::LoadRange { x, y } => {
+                write!(f, " V{:X} - V{:X}", x, y)
+            }
+            Instruction::LdI { nnn } => write!(f, " I, 0x{:03X}", nnn),
+            Instruction::JpV0 { nnn } => write!(f, " V0, 0x{:03X}", nnn),
+            Instruction::JpVx { x, nnn } => write!(f, " V{:X}, 0x{:03X}", x, nnn),
+            Instruction::DrawSprite { x, y, n } => write!(f, " V{:X}, V{:X}, {}", x, y, n),
+            Instruction::Skp { x } | Instruction::Sknp { x } => write!(f, " V{:X}", x),
+            Instruction::LdVxDt { x } => write!(f, " V{:X}, DT", x),
+            Instruction::LdVxK { x } => write!(f, " V{:X}, K", x),
+            Instruction::LdDtVx { x } => write!(f, " DT, V{:X}", x),
+            Instruction::LdStVx { x } => write!(f, " ST, V{:X}", x),
+            Instruction::AddIVx { x } => write!(f, " I, V{:X}", x),
+            Instruction::LdFVx { x } => write!(f, " F, V{:X}", x),
+            Instruction::LdBVx { x } => write!(f, " B, V{:X}", x),
+            Instruction::LdIVx { x } => write!(f, " [I], V{:X}", x),
+            Instruction::LdVxI { x } => write!(f, " V{:X}, [I]", x),
+            Instruction::Unknown { opcode } => write!(f, " 0x{:04X}", opcode),
+        }
+    }
+}
+
+/// Returns the mnemonic of an opcode in the given mode, e.g. `CLS`, `JP`, or `DRW`.
+///
+/// # Arguments
+///
+/// * `opcode` - The opcode to name.
+/// * `mode` - The emulation mode, which selects the available instructions.
+pub fn mnemonic(opcode: u16, mode: &Mode) -> &'static str {
+    decode(opcode, mode).mnemonic()
+}
+
+/// Decodes an opcode into an instruction without executing it.
+///
+/// # Arguments
+///
+/// * `opcode` - The opcode to decode.
+/// * `mode` - The emulation mode, which selects the available and quirky instructions.
+///
+/// # Returns
+///
+/// The decoded instruction, or `Instruction::Unknown` if the opcode is not valid in `mode`.
+pub fn decode(opcode: u16, mode: &Mode) -> Instruction {
+    decode_with_quirks(opcode, mode, &Quirks::for_mode(mode))
+}
+
+/// Decodes an opcode into an instruction, with quirks that differ from the mode defaults.
+///
+/// # Arguments
+///
+/// * `opcode` - The opcode to decode.
+/// * `mode` - The emulation mode, which selects the available instructions.
+/// * `quirks` - The quirks that select between ambiguous instructions.
+///
+/// # Returns
+///
+/// The decoded instruction, or `Instruction::Unknown` if the opcode is not valid in `mode`.
+pub fn decode_with_quirks(opcode: u16, mode: &Mode, quirks: &Quirks) -> Instruction {
+    let kk = (opcode & 0x00FF) as u8;
+    let nnn = opcode & 0x0FFF;
+
+    let x = ((opcode & 0x0F00) >> 8) as usize;
+    let y = ((opcode & 0x00F0) >> 4) as usize;
+    let n = (opcode & 0x000F) as u8;
+
+    let unknown = Instruction::Unknown { opcode };
+
+    match opcode & 0xF000 {
+        0x0000 => match opcode & 0x0F00 {
+            0x0000 => match opcode & 0x00F0 {
+                0x00C0 if *mode == Mode::SuperChip || *mode == Mode::XOChip => {
+                    Instruction::ScrollDown { n }
+                }
+                0x00D0 if *mode == Mode::XOChip => Instruction::ScrollUp { n },
+                0x00E0 => match opcode & 0x000F {
+                    0x0000 => Instruction::Cls,
+                    0x000E => Instruction::Ret,
+                    _ => unknown,
+                },
+                0x00F0 => match opcode & 0x000F {
+                    _ if *mode == Mode::Chip8 => unknown,
+                    0x000B => Instruction::ScrollRight,
+                    0x000C => Instruction::ScrollLeft,
+                    0x000D => Instruction::Exit,
+                    0x000E => Instruction::Lores,
+                    0x000F => Instruction::Hires,
+                    _ => unknown,
+                },
+                _ => unknown,
+            },
+            _ => Instruction::Sys { nnn },
+        },
+        0x1000 => Instruction::Jp { nnn },
+        0x2000 => Instruction::Call { nnn },
+        0x3000 => Instruction::SeVxByte { x, kk },
+        0x4000 => Instruction::SneVxByte { x, kk },
+        0x5000 => match opcode & 0x000F {
+            0x0000 => Instruction::SeVxVy { x, y },
+            0x0002 if *mode == Mode::XOChip => Instruction::SaveRange { x, y },
+            0x0003 if *mode == Mode::XOChip => Instruction::LoadRange { x, y },
+            _ => unknown,
+        },
+        0x6000 => Instruction::LdVxByte { x, kk },
+        0x7000 => Instruction::AddVxByte { x, kk },
+        0x8000 => match opcode & 0xF00F {
+            0x8000 => Instruction::LdVxVy { x, y },
+            0x8001 => Instruction::Or { x, y },
+            0x8002 => Instruction::And { x, y },
+            0x8003 => Instruction::Xor { x, y },
+            0x8004 => Instruction::AddVxVy { x, y },
+            0x8005 => Instruction::Sub { x, y },
+            0x8006 => Instruction::Shr { x, y },
+            0x8007 => Instruction::Subn { x, y },
+            0x800E => Instruction::Shl { x, y },
+            _ => unknown,
+        },
+        0x9000 => Instruction::SneVxVy { x, y },
+        0xA000 => Instruction::LdI { nnn },
+        0xB000 if quirks.jump_v0_uses_vx => Instruction::JpVx { x, nnn },
+        0xB000 => Instruction::JpV0 { nnn },
+        0xC000 => Instruction::Rnd { x, kk },
+        0xD000 => Instruction::DrawSprite { x, y, n },
+        0xE000 => match opcode & 0x00FF {
+            0x009E => Instruction::Skp { x },
+            0x00A1 => Instruction::Sknp { x },
+            _ => unknown,
+        },
+        0xF000 => match opcode & 0x00FF {
+            0x0001 if *mode == Mode::XOChip => Instruction::Plane { n: x as u8 },
+            0x0007 => Instruction::LdVxDt { x },
+            0x000A => Instruction::LdVxK { x },
+            0x0015 => Instruction::LdDtVx { x },
+            0x0018 => Instruction::LdStVx { x },
+            0x001E => Instruction::AddIVx { x },
+            0x0029 => Instruction::LdFVx { x },
+            0x0033 => Instruction::LdBVx { x },
+            0x0055 => Instruction::LdIVx { x },
+            0x0065 => Instruction::LdVxI { x },
+            _ => unknown,
+        },
+        _ => unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_system_instructions() {
+        assert_eq!(decode(0x00E0, &Mode::Chip8), Instruction::Cls);
+        assert_eq!(decode(0x00EE, &Mode::Chip8), Instruction::Ret);
+        assert_eq!(
+            decode(0x0123, &Mode::Chip8),
+            Instruction::Sys { nnn: 0x123 }
+        );
+        assert_eq!(decode(0x00FF, &Mode::SuperChip), Instruction::Hires);
+        assert_eq!(decode(0x00FD, &Mode::SuperChip), Instruction::Exit);
+        assert_eq!(decode(0x00FE, &Mode::SuperChip), Instruction::Lores);
+        assert_eq!(decode(0x00FB, &Mode::XOChip), Instruction::ScrollRight);
+        assert_eq!(decode(0x00FC, &Mode::XOChip), Instruction::ScrollLeft);
+        assert_eq!(
+            decode(0x00FF, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0x00FF }
+        );
+        assert_eq!(
+            decode(0x00FD, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0x00FD }
+        );
+    }
+
+    #[test]
+    fn test_decode_mode_specific_scrolls() {
+        assert_eq!(
+            decode(0x00C4, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0x00C4 }
+        );
+        assert_eq!(
+            decode(0x00C4, &Mode::SuperChip),
+            Instruction::ScrollDown { n: 4 }
+        );
+        assert_eq!(
+            decode(0x00D2, &Mode::SuperChip),
+            Instruction::Unknown { opcode: 0x00D2 }
+        );
+        assert_eq!(
+            decode(0x00D2, &Mode::XOChip),
+            Instruction::ScrollUp { n: 2 }
+        );
+    }
+
+    #[test]
+    fn test_decode_register_instructions() {
+        assert_eq!(decode(0x1ABC, &Mode::Chip8), Instruction::Jp { nnn: 0xABC });
+        assert_eq!(
+            decode(0x2ABC, &Mode::Chip8),
+            Instruction::Call { nnn: 0xABC }
+        );
+        assert_eq!(
+            decode(0x3A12, &Mode::Chip8),
+            Instruction::SeVxByte { x: 0xA, kk: 0x12 }
+        );
+        assert_eq!(
+            decode(0x6A12, &Mode::Chip8),
+            Instruction::LdVxByte { x: 0xA, kk: 0x12 }
+        );
+        assert_eq!(
+            decode(0x7A12, &Mode::Chip8),
+            Instruction::AddVxByte { x: 0xA, kk: 0x12 }
+        );
+        assert_eq!(
+            decode(0x8AB4, &Mode::Chip8),
+            Instruction::AddVxVy { x: 0xA, y: 0xB }
+        );
+        assert_eq!(
+            decode(0x8ABE, &Mode::Chip8),
+            Instruction::Shl { x: 0xA, y: 0xB }
+        );
+        assert_eq!(
+            decode(0x8AB8, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0x8AB8 }
+        );
+        assert_eq!(
+            decode(0x9AB0, &Mode::Chip8),
+            Instruction::SneVxVy { x: 0xA, y: 0xB }
+        );
+        assert_eq!(
+            decode(0xDAB5, &Mode::Chip8),
+            Instruction::DrawSprite {
+                x: 0xA,
+                y: 0xB,
+                n: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_jump_with_offset() {
+        assert_eq!(
+            decode(0xB123, &Mode::Chip8),
+            Instruction::JpV0 { nnn: 0x123 }
+        );
+        assert_eq!(
+            decode(0xB123, &Mode::SuperChip),
+            Instruction::JpVx { x: 1, nnn: 0x123 }
+        );
+    }
+
+    #[test]
+    fn test_decode_jump_with_offset_quirk() {
+        let quirks = Quirks {
+            jump_v0_uses_vx: true,
+            ..Quirks::for_mode(&Mode::Chip8)
+        };
+        assert_eq!(
+            decode_with_quirks(0xB123, &Mode::Chip8, &quirks),
+            Instruction::JpVx { x: 1, nnn: 0x123 }
+        );
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let instructions = [
+            (Instruction::Sys { nnn: 0x123 }, Mode::Chip8),
+            (Instruction::ScrollDown { n: 4 }, Mode::SuperChip),
+            (Instruction::ScrollUp { n: 2 }, Mode::XOChip),
+            (Instruction::Cls, Mode::Chip8),
+            (Instruction::Ret, Mode::Chip8),
+            (Instruction::ScrollRight, Mode::SuperChip),
+            (Instruction::ScrollLeft, Mode::SuperChip),
+            (Instruction::Exit, Mode::SuperChip),
+            (Instruction::Lores, Mode::SuperChip),
+            (Instruction::Hires, Mode::SuperChip),
+            (Instruction::Jp { nnn: 0xABC }, Mode::Chip8),
+            (Instruction::Call { nnn: 0xABC }, Mode::Chip8),
+            (Instruction::SeVxByte { x: 0xA, kk: 0x12 }, Mode::Chip8),
+            (Instruction::SneVxByte { x: 0xA, kk: 0x12 }, Mode::Chip8),
+            (Instruction::SeVxVy { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::SaveRange { x: 0xA, y: 0xB }, Mode::XOChip),
+            (Instruction::LoadRange { x: 0xB, y: 0xA }, Mode::XOChip),
+            (Instruction::LdVxByte { x: 0xA, kk: 0x12 }, Mode::Chip8),
+            (Instruction::AddVxByte { x: 0xA, kk: 0x12 }, Mode::Chip8),
+            (Instruction::LdVxVy { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::Or { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::And { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::Xor { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::AddVxVy { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::Sub { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::Shr { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::Subn { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::Shl { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::SneVxVy { x: 0xA, y: 0xB }, Mode::Chip8),
+            (Instruction::LdI { nnn: 0xABC }, Mode::Chip8),
+            (Instruction::JpV0 { nnn: 0xABC }, Mode::Chip8),
+            (Instruction::JpVx { x: 0xA, nnn: 0xABC }, Mode::SuperChip),
+            (Instruction::Rnd { x: 0xA, kk: 0x12 }, Mode::Chip8),
+            (
+                Instruction::DrawSprite {
+                    x: 0xA,
+                    y: 0xB,
+                    n: 5,
+                },
+                Mode::Chip8,
+            ),
+            (Instruction::Skp { x: 3 }, Mode::Chip8),
+            (Instruction::Sknp { x: 3 }, Mode::Chip8),
+            (Instruction::Plane { n: 2 }, Mode::XOChip),
+            (Instruction::LdVxDt { x: 3 }, Mode::Chip8),
+            (Instruction::LdVxK { x: 3 }, Mode::Chip8),
+            (Instruction::LdDtVx { x: 3 }, Mode::Chip8),
+            (Instruction::LdStVx { x: 3 }, Mode::Chip8),
+            (Instruction::AddIVx { x: 3 }, Mode::Chip8),
+            (Instruction::LdFVx { x: 3 }, Mode::Chip8),
+            (Instruction::LdBVx { x: 3 }, Mode::Chip8),
+            (Instruction::LdIVx { x: 3 }, Mode::Chip8),
+            (Instruction::LdVxI { x: 3 }, Mode::Chip8),
+            (Instruction::Unknown { opcode: 0xF3FF }, Mode::Chip8),
+        ];
+
+        for (instruction, mode) in instructions {
+            let opcode = instruction.encode();
+            assert_eq!(
+                decode(opcode, &mode),
+                instruction,
+                "opcode 0x{:04X}",
+                opcode
+            );
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(decode(0x00E0, &Mode::Chip8).to_string(), "CLS");
+        assert_eq!(decode(0x612A, &Mode::Chip8).to_string(), "LD V1, 0x2A");
+        assert_eq!(decode(0xD015, &Mode::Chip8).to_string(), "DRW V0, V1, 5");
+        assert_eq!(decode(0xA2F0, &Mode::Chip8).to_string(), "LD I, 0x2F0");
+        assert_eq!(decode(0xF3FF, &Mode::Chip8).to_string(), "DW 0xF3FF");
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        let mnemonics = [
+            (0x00E0, Mode::Chip8, "CLS"),
+            (0x00EE, Mode::Chip8, "RET"),
+            (0x1234, Mode::Chip8, "JP"),
+            (0xB234, Mode::SuperChip, "JP"),
+            (0x2234, Mode::Chip8, "CALL"),
+            (0x612A, Mode::Chip8, "LD"),
+            (0xA2F0, Mode::Chip8, "LD"),
+            (0xF355, Mode::Chip8, "LD"),
+            (0x7101, Mode::Chip8, "ADD"),
+            (0x8124, Mode::Chip8, "ADD"),
+            (0x812E, Mode::Chip8, "SHL"),
+            (0xD015, Mode::Chip8, "DRW"),
+            (0xE39E, Mode::Chip8, "SKP"),
+            (0x00FF, Mode::SuperChip, "HIRES"),
+            (0xF201, Mode::XOChip, "PLANE"),
+            (0xF201, Mode::Chip8, "DW"),
+        ];
+
+        for (opcode, mode, expected) in mnemonics {
+            assert_eq!(mnemonic(opcode, &mode), expected, "opcode 0x{:04X}", opcode);
+        }
+    }
+
+    #[test]
+    fn test_encode_matches_opcodes() {
+        for opcode in [
+            0x00E0, 0x00C4, 0x1ABC, 0x8AB5, 0xB123, 0xDAB5, 0xE39E, 0xF365,
+        ] {
+            for mode in [Mode::Chip8, Mode::SuperChip, Mode::XOChip] {
+                assert_eq!(decode(opcode, &mode).encode(), opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_key_and_memory_instructions() {
+        assert_eq!(decode(0xF201, &Mode::XOChip), Instruction::Plane { n: 2 });
+        assert_eq!(
+            decode(0xF201, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0xF201 }
+        );
+        assert_eq!(decode(0xE39E, &Mode::Chip8), Instruction::Skp { x: 3 });
+        assert_eq!(decode(0xE3A1, &Mode::Chip8), Instruction::Sknp { x: 3 });
+        assert_eq!(decode(0xF30A, &Mode::Chip8), Instruction::LdVxK { x: 3 });
+        assert_eq!(decode(0xF333, &Mode::Chip8), Instruction::LdBVx { x: 3 });
+        assert_eq!(decode(0xF355, &Mode::Chip8), Instruction::LdIVx { x: 3 });
+        assert_eq!(decode(0xF365, &Mode::Chip8), Instruction::LdVxI { x: 3 });
+        assert_eq!(
+            decode(0x5132, &Mode::XOChip),
+            Instruction::SaveRange { x: 1, y: 3 }
+        );
+        assert_eq!(
+            decode(0x5313, &Mode::XOChip),
+            Instruction::LoadRange { x: 3, y: 1 }
+        );
+        assert_eq!(
+            decode(0x5132, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0x5132 }
+        );
+        assert_eq!(
+            decode(0xF3FF, &Mode::Chip8),
+            Instruction::Unknown { opcode: 0xF3FF }
+        );
+    }
+}
@@ -1,24 +1,69 @@
 pub mod audio;
+pub mod builder;
+pub mod clock;
+pub mod error;
+#[cfg(any(test, feature = "test-util"))]
+pub mod golden;
 pub mod input;
+pub mod instruction;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod options;
+pub mod png;
+pub mod quirks;
+#[cfg(feature = "ctrlc")]
+pub mod signal;
+pub mod state;
+pub mod trace;
 pub mod video;
 
-use std::fs::File;
-use std::io::{self, Read};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use rand::Rng;
+use sdl2::audio::AudioStatus;
+use sdl2::keyboard::Scancode;
+use sdl2::pixels::Color;
 
 use audio::AudioManager;
-use input::InputManager;
-use video::{DisplayManager, Resolution};
+use builder::Chip8Builder;
+use clock::{Clock, Scheduler, SystemClock};
+use error::{Chip8Error, InitError};
+#[cfg(any(test, feature = "test-util"))]
+use input::ScriptedInput;
+use input::{Hotkey, Input, InputManager, Layout};
+use instruction::{decode_with_quirks, Instruction};
+use options::Options;
+use quirks::{LoadStoreIncrement, Quirk, Quirks};
+use state::{Chip8State, CpuState};
+use trace::TraceEvent;
+use video::{DisplayManager, Filter, Palette, Resolution};
 
 const MEMORY_SIZE: usize = 4096;
 const V_COUNT: usize = 16;
 const ROM_START_ADDRESS: usize = 0x200;
 const SPRITE_WIDTH: usize = 8;
 const MAX_STACK_LEVELS: usize = 16;
+const STEP_OVER_MAX_CYCLES: u64 = 1_000_000;
+const VIP_HIRES_TRIGGER: u16 = 0x260;
+const VIP_HIRES_START_ADDRESS: usize = 0x2C0;
+const VIP_HIRES_CLS: u16 = 0x230;
 
 const FRAME_RATE: u32 = 60;
+/// Default execution speed in instructions per second.
+pub const DEFAULT_SPEED: u32 = 700;
+const SPEED_STEP: u32 = 100;
+
+const ROM_EXTENSIONS: [&str; 3] = ["ch8", "c8", "xo"];
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01B3;
 
 const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -39,19 +84,224 @@ const CHIP8_FONTSET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
 pub enum Mode {
+    #[default]
     Chip8,
     SuperChip,
     XOChip,
 }
 
+impl Mode {
+    /// Every emulation mode, in order of increasing capabilities.
+    pub const ALL: [Mode; 3] = [Mode::Chip8, Mode::SuperChip, Mode::XOChip];
+
+    /// Returns the name of the mode, as accepted by `--mode`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Chip8 => "Chip8",
+            Mode::SuperChip => "SuperChip",
+            Mode::XOChip => "XOChip",
+        }
+    }
+
+    /// Returns a short description of the machine the mode emulates.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Mode::Chip8 => "The original CHIP-8 of the COSMAC VIP, with a 64x32 display",
+            Mode::SuperChip => "SUPER-CHIP 1.1, adding a 128x64 hires display and scrolling",
+            Mode::XOChip => "XO-CHIP, adding bitplanes and wrapping sprites to SUPER-CHIP",
+        }
+    }
+}
+
+/// Guesses the emulation mode of a ROM from the opcodes it contains.
+///
+/// XO-CHIP-only opcodes (`F000`, `Fx01`, `00Dn`) select XO-CHIP. Otherwise,
+/// SuperChip-only opcodes (`00Cn`, `00FB` to `00FF`, `Fx30`, `Fx75`, `Fx85`) select
+/// SuperChip. Since sprite data is scanned like code, this is only a heuristic.
+///
+/// # Arguments
+///
+/// * `bytes` - The ROM contents, starting at the first instruction.
+///
+/// # Returns
+///
+/// The guessed mode, or `Mode::Chip8` if the ROM uses no mode-specific opcodes.
+pub fn detect_mode(bytes: &[u8]) -> Mode {
+    let mut mode = Mode::Chip8;
+    for pair in bytes.chunks_exact(2) {
+        let opcode = u16::from_be_bytes([pair[0], pair[1]]);
+
+        let xo_chip = opcode == 0xF000 || opcode & 0xF0FF == 0xF001 || opcode & 0xFFF0 == 0x00D0;
+        if xo_chip {
+            return Mode::XOChip;
+        }
+
+        let super_chip = matches!(opcode, 0x00FB..=0x00FF)
+            || opcode & 0xFFF0 == 0x00C0
+            || matches!(opcode & 0xF0FF, 0xF030 | 0xF075 | 0xF085);
+        if super_chip {
+            mode = Mode::SuperChip;
+        }
+    }
+    mode
+}
+
+/// Lists the emulation modes, one per line with a description.
+pub fn list_modes() -> String {
+    Mode::ALL
+        .iter()
+        .map(|mode| format!("{:<10} {}\n", mode.name(), mode.description()))
+        .collect()
+}
+
+/// How a run of the emulator ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RunOutcome {
+    /// The input requested to quit, or the stop flag was set.
+    Quit,
+    /// All requested frames were run.
+    Completed,
+    /// The watchdog stopped a program that made no progress.
+    Watchdog,
+    /// An instruction failed to execute.
+    Error(Chip8Error),
+}
+
+/// Performance measured by `bench`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchReport {
+    /// Number of executed instructions.
+    pub instructions: u64,
+    /// Number of rendered frames.
+    pub frames: u64,
+    /// Wall-clock time the run took.
+    pub elapsed: Duration,
+}
+
+impl BenchReport {
+    /// Returns the achieved instructions per second.
+    pub fn instructions_per_second(&self) -> f64 {
+        self.instructions as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} instructions and {} frames in {:.3} s ({:.0} IPS)",
+            self.instructions,
+            self.frames,
+            self.elapsed.as_secs_f64(),
+            self.instructions_per_second()
+        )
+    }
+}
+
+/// Delay timer accesses counted since the last reset, returned by `Chip8::timer_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimerStats {
+    /// Number of `Fx07` reads of the delay timer.
+    pub delay_reads: u64,
+    /// Number of `Fx15` writes of the delay timer.
+    pub delay_writes: u64,
+}
+
+/// Measures the frames presented and the instructions executed per second, for the
+/// speed overlay.
+struct RateCounter {
+    started: Instant,
+    frames: u32,
+    cycles: u32,
+    fps: u32,
+    ips: u32,
+}
+
+impl RateCounter {
+    /// Creates a counter that starts measuring at `now`.
+    fn new(now: Instant) -> Self {
+        RateCounter {
+            started: now,
+            frames: 0,
+            cycles: 0,
+            fps: 0,
+            ips: 0,
+        }
+    }
+
+    /// Counts a presented frame.
+    fn frame(&mut self) {
+        self.frames += 1;
+    }
+
+    /// Counts an executed instruction.
+    fn cycle(&mut self) {
+        self.cycles += 1;
+    }
+
+    /// Recomputes the rates once at least a second has passed since the last update.
+    fn update(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed < Duration::from_secs(1) {
+            return;
+        }
+
+        let seconds = elapsed.as_secs_f64();
+        self.fps = (self.frames as f64 / seconds).round() as u32;
+        self.ips = (self.cycles as f64 / seconds).round() as u32;
+        self.started = now;
+        self.frames = 0;
+        self.cycles = 0;
+    }
+}
+
+/// Decides which frames with display changes are presented, skipping the others.
+struct FrameSkip {
+    every: u32,
+    pending: u32,
+}
+
+impl FrameSkip {
+    /// Creates a frame skip that presents every `every`th frame with changes.
+    fn new(every: u32) -> Self {
+        FrameSkip {
+            every: every.max(1),
+            pending: 0,
+        }
+    }
+
+    /// Returns `true` if the frame should be skipped. Frames without changes have
+    /// nothing to present and are never skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `dirty` - Whether the display changed since the last presented frame.
+    fn skip(&mut self, dirty: bool) -> bool {
+        if !dirty {
+            return false;
+        }
+        self.pending += 1;
+        if self.pending < self.every {
+            return true;
+        }
+        self.pending = 0;
+        false
+    }
+}
+
+/// A callback receiving the RGBA buffer, width, and height of a rendered frame.
+pub type FrameCallback = dyn FnMut(&[u8], usize, usize);
+
 #[allow(non_snake_case)]
 pub struct Chip8 {
     mode: Mode,
-    scale: usize,
+    quirks: Quirks,
+    strict: bool,
 
     memory: [u8; MEMORY_SIZE],
+    start_address: usize,
     V: [u8; V_COUNT],
     I: u16,
     PC: usize,
@@ -63,22 +313,169 @@ pub struct Chip8 {
     sound_timer: u8,
 
     display: DisplayManager,
-    input: InputManager,
+    input: Box<dyn Input>,
     audio: AudioManager,
-    sdl_context: sdl2::Sdl,
+    audio_channels: u8,
+    audio_samples: Option<u16>,
+    sdl_context: Option<sdl2::Sdl>,
 
     release_key_register: Option<usize>,
+    held_keys_at_wait: u16,
+    halted: bool,
+
+    max_cycles: Option<u64>,
+    frame_skip: u32,
+    idle_cycles: u64,
+    last_display_changes: u64,
+    last_keys: u16,
+
+    lores: bool,
+    planes: u8,
+    collision_planes: u8,
+    sound_active: bool,
+    timer_stats: TimerStats,
+    stop_flag: Option<Arc<AtomicBool>>,
+    speed: Arc<AtomicU32>,
+
+    collision_callback: Option<Box<dyn FnMut()>>,
+    frame_callback: Option<Box<FrameCallback>>,
+    trace_callback: Option<Box<dyn FnMut(TraceEvent)>>,
+    sound_start_callback: Option<Box<dyn FnMut()>>,
+    sound_stop_callback: Option<Box<dyn FnMut()>>,
 }
 
 impl Chip8 {
-    #[allow(non_snake_case)]
-    pub fn new(mode: Mode, scale: usize, memory: [u8; MEMORY_SIZE]) -> Self {
-        let sdl_context = sdl2::init().expect("Failed to initialize SDL2");
+    /// Creates a new `Chip8` instance with an SDL2 window, input, and sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode.
+    /// * `scale` - The display scaling factor.
+    /// * `memory` - The initial memory contents, usually from `load_program_rom`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2, the window, or the event pump fail to initialize. Without
+    /// an audio device the machine runs silently.
+    pub fn new(mode: Mode, scale: usize, memory: [u8; MEMORY_SIZE]) -> Result<Self, InitError> {
+        let sdl_context = sdl2::init().map_err(InitError::Sdl)?;
+        Self::with_context(&sdl_context, mode, scale, memory)
+    }
+
+    /// Creates a new `Chip8` instance from an SDL context the host application already owns.
+    ///
+    /// The context is shared rather than initialized again, so the emulator can run next
+    /// to other SDL code. Only one event pump can exist at a time, so the host must not
+    /// hold its own while the emulator is alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdl_context` - A reference to an initialized SDL context.
+    /// * `mode` - The emulation mode.
+    /// * `scale` - The display scaling factor.
+    /// * `memory` - The initial memory contents, usually from `load_program_rom`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window or the event pump fail to initialize. Without an
+    /// audio device the machine runs silently.
+    pub fn with_context(
+        sdl_context: &sdl2::Sdl,
+        mode: Mode,
+        scale: usize,
+        memory: [u8; MEMORY_SIZE],
+    ) -> Result<Self, InitError> {
+        let mut chip8 = Self::from_managers(
+            mode,
+            memory,
+            DisplayManager::new(sdl_context, Resolution::Low, scale),
+            InputManager::new(sdl_context)
+                .map(|input| Box::new(input) as Box<dyn Input>)
+                .map_err(Into::into),
+            AudioManager::new(sdl_context),
+        )?;
+        chip8.sdl_context = Some(sdl_context.clone());
+        Ok(chip8)
+    }
+
+    /// Creates a new `Chip8` instance that runs without a window, input, or sound.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode.
+    /// * `memory` - The initial memory contents, usually from `load_program_rom`.
+    pub fn headless(mode: Mode, memory: [u8; MEMORY_SIZE]) -> Self {
+        Self::with_managers(
+            mode,
+            memory,
+            DisplayManager::headless(Resolution::Low),
+            Box::new(InputManager::headless()),
+            AudioManager::headless(),
+        )
+    }
 
+    /// Creates a new `Chip8` instance for tests, without initializing SDL at all.
+    ///
+    /// Input comes from an empty `ScriptedInput`, so no key is ever pressed unless
+    /// another source is installed with `set_input`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode.
+    /// * `memory` - The initial memory contents, e.g. from `assemble_program`.
+    #[cfg(any(test, feature = "test-util"))]
+    pub fn new_for_test(mode: Mode, memory: [u8; MEMORY_SIZE]) -> Self {
+        Self::with_managers(
+            mode,
+            memory,
+            DisplayManager::headless(Resolution::Low),
+            Box::new(ScriptedInput::new()),
+            AudioManager::headless(),
+        )
+    }
+
+    /// Returns a builder for a `Chip8` instance with more options than `new`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode.
+    pub fn builder(mode: Mode) -> Chip8Builder {
+        Chip8Builder::new(mode)
+    }
+
+    /// Builds the struct from the results of creating each manager, reporting the first failure.
+    ///
+    /// Sound is optional, so an audio device that fails to open is replaced by a silent one
+    /// with a warning instead.
+    fn from_managers(
+        mode: Mode,
+        memory: [u8; MEMORY_SIZE],
+        display: Result<DisplayManager, Box<dyn Error>>,
+        input: Result<Box<dyn Input>, Box<dyn Error>>,
+        audio: Result<AudioManager, Box<dyn Error>>,
+    ) -> Result<Self, InitError> {
+        let display = display.map_err(InitError::Display)?;
+        let input = input.map_err(InitError::Input)?;
+        let audio = audio.unwrap_or_else(|e| {
+            eprintln!("{}; continuing without sound", InitError::Audio(e));
+            AudioManager::headless()
+        });
+        Ok(Self::with_managers(mode, memory, display, input, audio))
+    }
+
+    fn with_managers(
+        mode: Mode,
+        memory: [u8; MEMORY_SIZE],
+        display: DisplayManager,
+        input: Box<dyn Input>,
+        audio: AudioManager,
+    ) -> Self {
         let mut chip8 = Chip8 {
+            quirks: Quirks::for_mode(&mode),
+            strict: false,
             mode,
-            scale,
             memory,
+            start_address: ROM_START_ADDRESS,
             V: [0; V_COUNT],
             I: 0,
             PC: ROM_START_ADDRESS,
@@ -86,11 +483,32 @@ impl Chip8 {
             SP: 0,
             delay_timer: 0,
             sound_timer: 0,
-            display: DisplayManager::new(&sdl_context, Resolution::Low, scale).unwrap(),
-            input: InputManager::new(&sdl_context).unwrap(),
-            audio: AudioManager::new(&sdl_context).unwrap(),
-            sdl_context,
+            display,
+            input,
+            audio,
+            audio_channels: 1,
+            audio_samples: None,
+            sdl_context: None,
             release_key_register: None,
+            held_keys_at_wait: 0,
+            halted: false,
+            max_cycles: None,
+            frame_skip: 1,
+            idle_cycles: 0,
+            last_display_changes: 0,
+            last_keys: 0,
+            lores: true,
+            planes: 1,
+            collision_planes: 0,
+            sound_active: false,
+            timer_stats: TimerStats::default(),
+            stop_flag: None,
+            speed: Arc::new(AtomicU32::new(DEFAULT_SPEED)),
+            collision_callback: None,
+            frame_callback: None,
+            trace_callback: None,
+            sound_start_callback: None,
+            sound_stop_callback: None,
         };
 
         // Load fontset into memory
@@ -98,449 +516,3541 @@ impl Chip8 {
         chip8
     }
 
-    fn emulate_cycle(&mut self) {
-        if let Some(register) = self.release_key_register {
-            self.wait_for_next_key(register);
-        } else {
-            let opcode = self.fetch_opcode();
-            self.execute_opcode(opcode);
-        }
+    fn set_start_address(&mut self, start_address: usize) {
+        self.start_address = start_address;
+        self.PC = start_address;
     }
 
-    fn update_timers(&mut self) {
-        if self.delay_timer > 0 {
-            self.delay_timer -= 1;
-        }
+    /// Returns the emulation mode.
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
 
-        if self.sound_timer > 0 {
-            self.audio.start();
-            self.sound_timer -= 1;
-        } else {
-            self.audio.stop()
-        }
+    /// Returns the active quirks.
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
     }
 
-    fn wait_for_next_key(&mut self, register: usize) {
-        if let Some(val) = self.input.get_next_released_key() {
-            self.V[register] = val;
-            self.release_key_register = None;
+    /// Overrides the quirks derived from the emulation mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window when the quirks change the
+    /// display resolution.
+    pub fn set_quirks(&mut self, quirks: Quirks) -> Result<(), Box<dyn Error>> {
+        self.quirks = quirks;
+        if self.display.resolution() != self.display_resolution() {
+            self.display.set_resolution(self.display_resolution())?;
         }
+        Ok(())
     }
 
-    fn fetch_opcode(&mut self) -> u16 {
-        let opcode = (self.memory[self.PC] as u16) << 8 | self.memory[self.PC + 1] as u16;
-        self.PC += 2;
-        opcode
+    /// Enables or disables strict mode, which reports every executed instruction whose
+    /// behavior depends on a quirk to the trace hook, to help validate portability.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
     }
 
-    fn execute_opcode(&mut self, opcode: u16) {
-        let kk = (opcode & 0x00FF) as u8;
-        let nnn = opcode & 0x0FFF;
-
-        let x = ((opcode & 0x0F00) >> 8) as usize;
-        let y = ((opcode & 0x00F0) >> 4) as usize;
-        let n = (opcode & 0x000F) as u8;
-
-        match opcode & 0xF000 {
-            0x0000 => match opcode & 0x0F00 {
-                0x0000 => match opcode & 0x00F0 {
-                    0x00C0 if self.mode == Mode::SuperChip || self.mode == Mode::XOChip => {
-                        self.op_00cn(n)
-                    }
-                    0x00D0 if self.mode == Mode::XOChip => self.op_00dn(n),
-                    0x00E0 => match opcode & 0x000F {
-                        0x0000 => self.op_00e0(),
-                        0x000E => self.op_00ee(),
-                        _ => Self::unknown_opcode(opcode),
-                    },
-                    0x00F0 => match opcode & 0x000F {
-                        0x000B => self.op_00fb(),
-                        0x000C => self.op_00fc(),
-                        // 0x000D => self.op_00fd(),
-                        0x000E => self.op_00fe(),
-                        0x000F => self.op_00ff(),
-                        _ => Self::unknown_opcode(opcode),
-                    },
-                    _ => Self::unknown_opcode(opcode),
-                },
-                _ => self.op_0nnn(nnn),
-            },
-            0x1000 => self.op_1nnn(nnn),
-            0x2000 => self.op_2nnn(nnn),
-            0x3000 => self.op_3xkk(x, kk),
-            0x4000 => self.op_4xkk(x, kk),
-            0x5000 => self.op_5xy0(x, y),
-            0x6000 => self.op_6xkk(x, kk),
-            0x7000 => self.op_7xkk(x, kk),
-            0x8000 => match opcode & 0xF00F {
-                0x8000 => self.op_8xy0(x, y),
-                0x8001 => self.op_8xy1(x, y),
-                0x8002 => self.op_8xy2(x, y),
-                0x8003 => self.op_8xy3(x, y),
-                0x8004 => self.op_8xy4(x, y),
-                0x8005 => self.op_8xy5(x, y),
-                0x8006 => self.op_8xy6(x, y),
-                0x8007 => self.op_8xy7(x, y),
-                0x800E => self.op_8xye(x, y),
-                _ => Self::unknown_opcode(opcode),
-            },
-            0x9000 => self.op_9xy0(x, y),
-            0xA000 => self.op_annn(nnn),
-            0xB000 if self.mode != Mode::SuperChip => self.op_bnnn(nnn),
-            0xB000 if self.mode == Mode::SuperChip => self.op_bxnn(x, nnn),
-            0xC000 => self.op_cxkk(x, kk),
-            0xD000 => self.op_dxyn(x, y, n),
-            0xE000 => match opcode & 0x00FF {
-                0x009E => self.op_ex9e(x),
-                0x00A1 => self.op_exa1(x),
-                _ => Self::unknown_opcode(opcode),
-            },
-            0xF000 => match opcode & 0x00FF {
-                0x0007 => self.op_fx07(x),
-                0x000A => self.op_fx0a(x),
-                0x0015 => self.op_fx15(x),
-                0x0018 => self.op_fx18(x),
-                0x001E => self.op_fx1e(x),
-                0x0029 => self.op_fx29(x),
-                0x0033 => self.op_fx33(x),
-                0x0055 => self.op_fx55(x),
-                0x0065 => self.op_fx65(x),
-                _ => Self::unknown_opcode(opcode),
-            },
-            _ => Self::unknown_opcode(opcode),
-        }
+    /// Changes the horizontal and vertical display scaling factors, resizing the window.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window.
+    pub fn set_scale(&mut self, scale_x: usize, scale_y: usize) -> Result<(), Box<dyn Error>> {
+        self.display.set_scale(scale_x, scale_y)
     }
 
-    fn unknown_opcode(opcode: u16) {
-        panic!("Unknown opcode: {:X}", opcode);
+    /// Surrounds the display with a solid border, resizing the window to fit it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window.
+    pub fn set_border(&mut self, thickness: usize, color: Color) -> Result<(), Box<dyn Error>> {
+        self.display.set_border(thickness, color)
     }
 
-    // 0nnn - SYS addr: Jump to a machine code routine at nnn.
-    fn op_0nnn(&mut self, _nnn: u16) {
-        // This instruction is only used on the old computers on which Chip-8
-        // was originally implemented. It is ignored by modern interpreters.
+    /// Switches the audio output between mono and stereo playback.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to reopen the playback device.
+    pub fn set_stereo(&mut self, stereo: bool) -> Result<(), Box<dyn Error>> {
+        self.audio_channels = if stereo { 2 } else { 1 };
+        self.reopen_audio()
     }
 
-    fn op_00cn(&mut self, n: u8) {
-        self.display.scroll_down(n as usize)
+    /// Sets the audio buffer size in samples, trading robustness for latency.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size is not a power of two, or SDL2 fails to reopen
+    /// the playback device.
+    pub fn set_audio_buffer(&mut self, samples: u16) -> Result<(), Box<dyn Error>> {
+        audio::validate_buffer_size(samples)?;
+        self.audio_samples = Some(samples);
+        self.reopen_audio()
     }
 
-    fn op_00dn(&mut self, n: u8) {
-        self.display.scroll_up(n as usize)
+    fn reopen_audio(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(sdl_context) = self.sdl_context.as_ref() {
+            self.audio.reopen(
+                sdl_context,
+                audio::DEFAULT_FREQUENCY,
+                self.audio_channels,
+                self.audio_samples,
+            )?;
+        }
+        Ok(())
     }
 
-    // 00E0 - CLS: Clear the display.
-    fn op_00e0(&mut self) {
-        self.display.clear();
+    /// Sets the stereo panning of the audio output, from -1.0 (left) to 1.0 (right).
+    pub fn set_pan(&mut self, pan: f32) {
+        self.audio.set_pan(pan);
     }
 
-    // 00EE - RET: Return from a subroutine.
-    fn op_00ee(&mut self) {
-        if self.SP == 0 {
-            panic!("Stack underflow!");
-        }
-        self.SP -= 1;
-        self.PC = self.stack[self.SP];
+    /// Sets the minimum duration of a beep, so very short sound timer values stay audible.
+    pub fn set_min_beep(&mut self, min_beep: Duration) {
+        self.audio.set_min_beep(min_beep);
     }
 
-    fn op_00fb(&mut self) {
-        self.display.scroll_right()
+    /// Mutes or unmutes the audio output. The sound timer keeps running while muted.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.audio.set_muted(muted);
     }
 
-    fn op_00fc(&mut self) {
-        self.display.scroll_left()
+    /// Returns `true` if the audio output is muted.
+    pub fn is_muted(&self) -> bool {
+        self.audio.is_muted()
     }
 
-    // 00FE - LORES: Switch to lores mode.
-    fn op_00fe(&mut self) {
-        self.display = DisplayManager::new(&self.sdl_context, Resolution::Low, self.scale).unwrap();
+    /// Replaces the source of keypad input, e.g. with a `ScriptedInput`.
+    pub fn set_input(&mut self, input: Box<dyn Input>) {
+        self.input = input;
     }
 
-    // 00FF - HIRES: Switch to hires mode.
-    fn op_00ff(&mut self) {
-        self.display =
-            DisplayManager::new(&self.sdl_context, Resolution::High, self.scale).unwrap();
+    /// Sets the key that quits the emulator, or `None` to only quit when the window is closed.
+    pub fn set_quit_key(&mut self, quit_key: Option<Scancode>) {
+        self.input.set_quit_key(quit_key);
     }
 
-    // 1nnn - JP addr: Jump to location nnn.
-    fn op_1nnn(&mut self, addr: u16) {
-        self.PC = addr as usize;
+    /// Selects the keyboard layout that the keypad keys follow.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.input.set_layout(layout);
     }
 
-    // 2nnn - CALL addr: Call subroutine at nnn.
-    fn op_2nnn(&mut self, addr: u16) {
-        if self.SP >= MAX_STACK_LEVELS {
-            panic!("Stack overflow!");
-        }
-        self.stack[self.SP] = self.PC;
-        self.SP += 1;
-        self.PC = addr as usize;
+    /// Enables or disables the watchdog.
+    ///
+    /// When enabled, `run` and `run_headless` stop with `RunOutcome::Watchdog` after the given
+    /// number of cycles without a display or input change, or right away when an instruction
+    /// jumps to itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_cycles` - The number of cycles without progress, or `None` to disable it.
+    pub fn set_max_cycles(&mut self, max_cycles: Option<u64>) {
+        self.max_cycles = max_cycles;
+        self.idle_cycles = 0;
     }
 
-    // 3xkk - SE Vx, byte: Skip next instruction if Vx = kk.
-    fn op_3xkk(&mut self, x: usize, kk: u8) {
-        if self.V[x] == kk {
-            self.PC += 2;
+    /// Writes single bytes into memory, like cheat codes that poke a value into the program.
+    ///
+    /// All addresses are checked first, so either every patch is applied or none.
+    ///
+    /// # Arguments
+    ///
+    /// * `patches` - Pairs of an address and the byte to store there.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MemoryOutOfBounds` for the first address past the end of memory.
+    pub fn apply_patches(&mut self, patches: &[(usize, u8)]) -> Result<(), Chip8Error> {
+        if let Some(&(address, _)) = patches.iter().find(|(address, _)| *address >= MEMORY_SIZE) {
+            return Err(Chip8Error::MemoryOutOfBounds { address });
         }
+        for &(address, value) in patches {
+            self.memory[address] = value;
+        }
+        Ok(())
     }
 
-    // 4xkk - SNE Vx, byte: Skip next instruction if Vx != kk.
-    fn op_4xkk(&mut self, x: usize, kk: u8) {
-        if self.V[x] != kk {
-            self.PC += 2;
+    /// Fills `V0` to `VF` and `I` with a random pattern, for ROMs that assume the registers
+    /// start out nonzero. `reset` sets them back to zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed of the pattern; the same seed always gives the same registers,
+    ///   on every platform and release.
+    pub fn randomize_registers(&mut self, seed: u64) {
+        let mut state = seed;
+        for registers in self.V.chunks_mut(8) {
+            registers.copy_from_slice(&splitmix64(&mut state).to_le_bytes());
         }
+        self.I = (splitmix64(&mut state) & 0x0FFF) as u16;
     }
 
-    // 5xy0 - SE Vx, Vy: Skip next instruction if Vx = Vy.
-    fn op_5xy0(&mut self, x: usize, y: usize) {
-        if self.V[x] == self.V[y] {
-            self.PC += 2;
-        }
+    /// Installs a flag that stops `run` and `run_headless` with `RunOutcome::Quit` once
+    /// it is set, e.g. from another thread or a signal handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `stop_flag` - The flag shared with the host.
+    pub fn set_stop_handle(&mut self, stop_flag: Arc<AtomicBool>) {
+        self.stop_flag = Some(stop_flag);
     }
 
-    // 6xkk - LD Vx, byte: Set Vx = kk.
-    fn op_6xkk(&mut self, x: usize, kk: u8) {
-        self.V[x] = kk;
+    /// Sets the execution speed, which `run` picks up on its next cycle.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - Execution speed in instructions per second.
+    pub fn set_speed(&self, speed: u32) {
+        self.speed.store(speed.max(1), Ordering::Relaxed);
     }
 
-    // 7xkk - ADD Vx, byte: Set Vx = Vx + kk.
-    fn op_7xkk(&mut self, x: usize, kk: u8) {
-        self.V[x] = self.V[x].wrapping_add(kk);
+    /// Returns the execution speed in instructions per second.
+    pub fn speed(&self) -> u32 {
+        self.speed.load(Ordering::Relaxed)
     }
 
-    // 8xy0 - LD Vx, Vy: Set Vx = Vy.
-    fn op_8xy0(&mut self, x: usize, y: usize) {
-        self.V[x] = self.V[y];
+    /// Returns the cell holding the execution speed, so the host can change it while
+    /// `run` owns the machine, e.g. from another thread.
+    pub fn speed_handle(&self) -> Arc<AtomicU32> {
+        Arc::clone(&self.speed)
     }
 
-    // 8xy1 - OR Vx, Vy: Set Vx = Vx OR Vy.
-    // SuperChip and XOChip modes don't reset VF.
-    fn op_8xy1(&mut self, x: usize, y: usize) {
-        self.V[x] |= self.V[y];
-        if self.mode == Mode::Chip8 {
-            self.V[0xF] = 0;
-        }
+    /// Returns `true` if the run loop should stop, because the input requested to quit
+    /// or the stop flag is set.
+    fn should_stop(&self) -> bool {
+        self.input.should_quit()
+            || self
+                .stop_flag
+                .as_ref()
+                .is_some_and(|stop_flag| stop_flag.load(Ordering::Relaxed))
     }
 
-    // 8xy2 - AND Vx, Vy: Set Vx = Vx AND Vy.
-    // SuperChip and XOChip modes don't reset VF.
-    fn op_8xy2(&mut self, x: usize, y: usize) {
-        self.V[x] &= self.V[y];
-        if self.mode == Mode::Chip8 {
-            self.V[0xF] = 0;
-        }
+    /// Makes `run` present at most every `every`th frame with display changes, to favor
+    /// CPU throughput at high speeds. The VRAM is still updated every cycle.
+    ///
+    /// # Arguments
+    ///
+    /// * `every` - Present one of this many frames with changes; 0 and 1 present all.
+    pub fn set_frame_skip(&mut self, every: u32) {
+        self.frame_skip = every;
     }
 
-    // 8xy3 - XOR Vx, Vy: Set Vx = Vx XOR Vy.
-    // SuperChip and XOChip modes don't reset VF.
-    fn op_8xy3(&mut self, x: usize, y: usize) {
-        self.V[x] ^= self.V[y];
-        if self.mode == Mode::Chip8 {
-            self.V[0xF] = 0;
-        }
+    /// Replaces the pressed state of every keypad key at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The pressed state of each key, indexed by hex value.
+    pub fn set_keys(&mut self, state: [bool; 16]) {
+        self.input.set_key_state(state);
     }
 
-    // 8xy4 - ADD Vx, Vy: Set Vx = Vx + Vy, set VF = carry.
-    fn op_8xy4(&mut self, x: usize, y: usize) {
-        let (result, carry) = self.V[x].overflowing_add(self.V[y]);
-        self.V[x] = result;
-        self.V[0xF] = if carry { 1 } else { 0 }
+    /// Installs a callback invoked whenever a sprite draw collides with set pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The callback, called once per colliding draw.
+    pub fn on_collision(&mut self, f: impl FnMut() + 'static) {
+        self.collision_callback = Some(Box::new(f));
     }
 
-    // 8xy5 - SUB Vx, Vy: Set Vx = Vx - Vy, set VF = NOT borrow.
-    fn op_8xy5(&mut self, x: usize, y: usize) {
-        let (result, borrow) = self.V[x].overflowing_sub(self.V[y]);
-        self.V[x] = result;
-        self.V[0xF] = !borrow as u8;
+    /// Returns the planes that collided during the last sprite draw.
+    ///
+    /// # Returns
+    ///
+    /// A bitmask with bit 0 set for plane 1 and bit 1 set for plane 2. Outside of XO-CHIP
+    /// mode only plane 1 is ever drawn to.
+    pub fn last_collision_planes(&self) -> u8 {
+        self.collision_planes
     }
 
-    // 8xy6 - SHR Vx {, Vy}: Set Vx = Vx SHR 1.
-    // SuperChip doesn't set vX to vY.
-    fn op_8xy6(&mut self, x: usize, y: usize) {
-        if self.mode != Mode::SuperChip {
-            self.V[x] = self.V[y];
-        }
-        let bit = self.V[x] & 0x1;
-        self.V[x] >>= 1;
-        self.V[0xF] = bit;
+    /// Installs a callback invoked with every rendered frame, e.g. to composite it elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The callback, called with the RGBA buffer, width, and height of the frame.
+    pub fn on_frame(&mut self, f: impl FnMut(&[u8], usize, usize) + 'static) {
+        self.frame_callback = Some(Box::new(f));
     }
 
-    // 8xy7 - SUBN Vx, Vy: Set Vx = Vy - Vx, set VF = NOT borrow.
-    fn op_8xy7(&mut self, x: usize, y: usize) {
-        let (result, borrow) = self.V[y].overflowing_sub(self.V[x]);
-        self.V[x] = result;
-        self.V[0xF] = !borrow as u8;
+    /// Installs a trace hook, invoked with events that are useful for debugging ROMs.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The callback, called once per event.
+    pub fn on_trace(&mut self, f: impl FnMut(TraceEvent) + 'static) {
+        self.trace_callback = Some(Box::new(f));
     }
 
-    // 8xye - SHL Vx {, Vy}: Set Vx = Vx SHL 1.
-    // SuperChip doesn't set vX to vY.
-    fn op_8xye(&mut self, x: usize, y: usize) {
-        if self.mode != Mode::SuperChip {
-            self.V[x] = self.V[y];
-        }
-        let bit = (self.V[x] >> 7) & 0x1;
-        self.V[x] <<= 1;
-        self.V[0xF] = bit;
+    /// Installs a callback invoked when the sound timer starts running.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The callback, called once per beep.
+    pub fn on_sound_start(&mut self, f: impl FnMut() + 'static) {
+        self.sound_start_callback = Some(Box::new(f));
     }
 
-    // 9xy0 - SNE Vx, Vy: Skip next instruction if Vx != Vy.
-    fn op_9xy0(&mut self, x: usize, y: usize) {
-        if self.V[x] != self.V[y] {
-            self.PC += 2;
-        }
+    /// Installs a callback invoked when the sound timer runs out.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The callback, called once per beep.
+    pub fn on_sound_stop(&mut self, f: impl FnMut() + 'static) {
+        self.sound_stop_callback = Some(Box::new(f));
     }
 
-    // Annn - LD I, addr: Set I = nnn.
-    fn op_annn(&mut self, addr: u16) {
-        self.I = addr;
+    /// Returns the current value of the delay timer.
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
     }
 
-    // Bnnn - JP V0, addr: Jump to location nnn + V0.
-    fn op_bnnn(&mut self, addr: u16) {
-        self.PC = (addr + self.V[0] as u16) as usize;
+    /// Returns the current value of the sound timer.
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
     }
 
-    // Bxnn - JP VX, addr: Jump to location xnn + VX.
-    fn op_bxnn(&mut self, x: usize, xnn: u16) {
-        self.PC = (xnn + self.V[x] as u16) as usize;
+    /// Returns `true` while the sound timer is running and the buzzer is playing.
+    pub fn is_beeping(&self) -> bool {
+        self.sound_timer > 0 && self.audio.status() == AudioStatus::Playing
     }
 
-    // Cxkk - RND Vx, byte: Set Vx = random byte AND kk.
-    fn op_cxkk(&mut self, x: usize, kk: u8) {
-        self.V[x] = rand::thread_rng().gen::<u8>() & kk;
+    /// Selects the filter used when the display is scaled up to the window size.
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.display.set_filter(filter);
     }
 
-    // Dxyn - DRW Vx, Vy, nibble: Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
-    // XO-CHIP wraps pixels instead of clipping them.
-    fn op_dxyn(&mut self, x: usize, y: usize, n: u8) {
-        let x_coord = self.V[x] as usize % self.display.width();
-        let y_coord = self.V[y] as usize % self.display.height();
-
-        self.V[0xF] = 0;
-        for byte_index in 0..n as usize {
-            let y = (y_coord + byte_index) % self.display.height();
-            if self.mode != Mode::XOChip && y_coord + byte_index >= self.display.height() {
-                break;
-            }
-            let byte = self.memory[self.I as usize + byte_index];
-            for bit_index in 0..SPRITE_WIDTH {
-                let x = (x_coord + bit_index) % self.display.width();
-                if self.mode != Mode::XOChip && x_coord + bit_index >= self.display.width() {
-                    break;
-                }
-                let bit = (byte >> (7 - bit_index)) & 1;
-                self.V[0xF] |= self.display.set_pixel(x, y, bit);
-            }
-        }
+    /// Sets the display colors, indexed by pixel value.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.display.set_palette(palette);
     }
 
-    // Ex9E - SKP Vx: Skip next instruction if key with the value of Vx is pressed.
-    fn op_ex9e(&mut self, x: usize) {
-        if self.input.is_key_pressed(self.V[x]) {
-            self.PC += 2;
-        }
+    /// Returns the display colors.
+    pub fn palette(&self) -> Palette {
+        self.display.palette()
     }
 
-    // ExA1 - SKNP Vx: Skip next instruction if key with the value of Vx is not pressed.
-    fn op_exa1(&mut self, x: usize) {
-        if !self.input.is_key_pressed(self.V[x]) {
-            self.PC += 2;
+    /// Applies the colors and quirks of an Octo options object.
+    ///
+    /// The quirks of the current mode are replaced by the ones from the options. A
+    /// `buzzColor` turns on the beep indicator in that color. The speed is passed to
+    /// `run` by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The options, e.g. from `options::load_options_from_json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to update the display.
+    pub fn apply_options(&mut self, options: &Options) -> Result<(), Box<dyn Error>> {
+        self.set_quirks(options.quirks(&self.mode))?;
+        if let Some(palette) = options.palette {
+            self.set_palette(palette);
         }
+        if let Some(buzz_color) = options.buzz_color {
+            self.display.set_beep_color(buzz_color);
+            self.set_beep_indicator(true)?;
+        }
+        Ok(())
     }
 
-    // Fx07 - LD Vx, DT: Set Vx = delay timer value.
-    fn op_fx07(&mut self, x: usize) {
-        self.V[x] = self.delay_timer;
+    /// Enables or disables an on-screen indicator shown while the buzzer is playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to restore the pixels under the indicator.
+    pub fn set_beep_indicator(&mut self, enabled: bool) -> Result<(), Box<dyn Error>> {
+        self.display.set_beep_indicator(enabled)
     }
 
-    // Fx0A - LD Vx, K: Wait for a key press, store the value of the key in Vx.
-    fn op_fx0a(&mut self, x: usize) {
-        self.release_key_register = Some(x);
+    /// Returns the value of the register `V<index>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Chip8Error::InvalidRegister` if `index` is not below 16.
+    pub fn register(&self, index: usize) -> Result<u8, Chip8Error> {
+        self.V
+            .get(index)
+            .copied()
+            .ok_or(Chip8Error::InvalidRegister(index))
     }
 
-    // Fx15 - LD DT, Vx: Set delay timer = Vx.
-    fn op_fx15(&mut self, x: usize) {
-        self.delay_timer = self.V[x];
+    /// Sets the register `V<index>` to `value`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Chip8Error::InvalidRegister` if `index` is not below 16.
+    pub fn set_register(&mut self, index: usize, value: u8) -> Result<(), Chip8Error> {
+        let register = self
+            .V
+            .get_mut(index)
+            .ok_or(Chip8Error::InvalidRegister(index))?;
+        *register = value;
+        Ok(())
     }
 
-    // Fx18 - LD ST, Vx: Set sound timer = Vx.
-    fn op_fx18(&mut self, x: usize) {
-        self.sound_timer = self.V[x];
+    /// Returns how often the delay timer was read and written since the last reset,
+    /// e.g. to find the polling loops of a ROM that runs too fast or too slow.
+    pub fn timer_stats(&self) -> TimerStats {
+        self.timer_stats
     }
 
-    // Fx1E - ADD I, Vx: Set I = I + Vx.
-    fn op_fx1e(&mut self, x: usize) {
-        self.I += self.V[x] as u16;
+    /// Returns a snapshot of the CPU registers, stack, and timers.
+    pub fn cpu_state(&self) -> CpuState {
+        CpuState {
+            V: self.V,
+            I: self.I,
+            PC: self.PC,
+            stack: self.stack,
+            SP: self.SP,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+        }
     }
 
-    // Fx29 - LD F, Vx: Set I = location of sprite for digit Vx.
-    fn op_fx29(&mut self, x: usize) {
-        self.I = self.V[x] as u16 * 5;
+    /// Captures the complete machine state.
+    pub fn save_state(&self) -> Chip8State {
+        let (width, height) = (self.display.width(), self.display.height());
+        Chip8State {
+            cpu: self.cpu_state(),
+            memory: self.memory,
+            release_key_register: self.release_key_register,
+            halted: self.halted,
+            lores: self.lores,
+            planes: self.planes,
+            width,
+            height,
+            pixels: (0..height)
+                .flat_map(|y| (0..width).map(move |x| (x, y)))
+                .map(|(x, y)| self.display.pixel(x, y))
+                .collect(),
+        }
     }
 
-    // Fx33 - LD B, Vx: Store BCD representation of Vx in memory locations I, I+1, and I+2.
-    fn op_fx33(&mut self, x: usize) {
-        self.memory[self.I as usize] = self.V[x] / 100;
-        self.memory[self.I as usize + 1] = (self.V[x] % 100) / 10;
-        self.memory[self.I as usize + 2] = self.V[x] % 10;
+    /// Returns a hash of the complete machine state, including memory, registers, the
+    /// stack, timers, and the display, for comparing runs in regression tests.
+    ///
+    /// The hash is stable across platforms and releases for the same save state format.
+    pub fn state_hash(&self) -> u64 {
+        fnv1a(&self.save_state().to_bytes())
     }
 
-    // Fx55 - LD [I], Vx: Store registers V0 through Vx in memory starting at location I.
-    // SuperChip doesn't increment I.
-    fn op_fx55(&mut self, x: usize) {
-        for offset in 0..=x {
-            self.memory[self.I as usize + offset] = self.V[offset];
+    /// Restores a machine state captured by `save_state`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error if the state fails `Chip8State::validate`, or an
+    /// error if the display can't be switched to the saved resolution. The machine is
+    /// left untouched if the state is invalid.
+    pub fn load_state(&mut self, state: &Chip8State) -> io::Result<()> {
+        state.validate()?;
+        let resolution = Resolution::new(state.width, state.height);
+        self.display
+            .set_resolution(resolution)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        for (index, &pixel) in state.pixels.iter().enumerate() {
+            self.display
+                .set_pixel(index % state.width, index / state.width, pixel);
         }
 
-        if self.mode != Mode::SuperChip {
-            self.I += self.V[x] as u16;
-            self.I += 1;
-        }
+        let cpu = &state.cpu;
+        self.V = cpu.V;
+        self.I = cpu.I;
+        self.PC = cpu.PC;
+        self.stack = cpu.stack;
+        self.SP = cpu.SP;
+        self.delay_timer = cpu.delay_timer;
+        self.sound_timer = cpu.sound_timer;
+        self.memory = state.memory;
+        self.release_key_register = state.release_key_register;
+        self.halted = state.halted;
+        self.lores = state.lores;
+        self.planes = state.planes;
+        self.idle_cycles = 0;
+        Ok(())
     }
 
-    // Fx65 - LD Vx, [I]: Read registers V0 through Vx from memory starting at location I.
-    // SuperChip doesn't increment I.
-    fn op_fx65(&mut self, x: usize) {
-        for offset in 0..=x {
-            self.V[offset] = self.memory[self.I as usize + offset];
-        }
+    /// Writes the machine state to a versioned save state file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be written.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.save_state().to_bytes())
+    }
 
-        if self.mode != Mode::SuperChip {
-            self.I += self.V[x] as u16;
-            self.I += 1;
-        }
+    /// Restores the machine state from a file written by `save_to_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read, or if it is not a save state of the
+    /// current version, in which case the machine is left untouched.
+    pub fn load_from_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let state = Chip8State::from_bytes(&fs::read(path)?)?;
+        self.load_state(&state)
     }
-}
 
-pub fn run(mut chip8: Chip8, speed: u32) {
-    let mut last_frame = Instant::now();
-    let frame_duration: Duration = Duration::from_secs_f64(1.0 / FRAME_RATE as f64);
+    /// Returns the return addresses of the active subroutine calls, oldest first.
+    pub fn call_stack(&self) -> &[usize] {
+        &self.stack[..self.SP.min(MAX_STACK_LEVELS)]
+    }
 
-    let mut last_cycle = Instant::now();
-    let cycle_duration: Duration = Duration::from_secs_f64(1.0 / speed as f64);
+    /// Enables or disables the on-screen register overlay.
+    pub fn set_hud(&mut self, enabled: bool) {
+        self.display.set_hud(enabled);
+    }
 
-    loop {
-        if last_cycle.elapsed() >= cycle_duration {
-            last_cycle = Instant::now();
-            chip8.emulate_cycle();
-            chip8.input.update();
-        }
+    /// Enables or disables the on-screen frame rate and instructions per second.
+    pub fn set_stats(&mut self, enabled: bool) {
+        self.display.set_stats(enabled);
+    }
 
-        if last_frame.elapsed() >= frame_duration {
-            last_frame = Instant::now();
-            chip8.display.render();
-            chip8.update_timers();
-        }
+    /// Resets the machine to its power-on state, keeping the loaded program in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window back to the power-on
+    /// resolution.
+    pub fn reset(&mut self) -> io::Result<()> {
+        self.V = [0; V_COUNT];
+        self.I = 0;
+        self.PC = self.start_address;
+        self.stack = [0; MAX_STACK_LEVELS];
+        self.SP = 0;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.release_key_register = None;
+        self.halted = false;
+        self.idle_cycles = 0;
+        self.last_display_changes = 0;
+        self.last_keys = 0;
+        self.lores = true;
+        self.planes = 1;
+        self.collision_planes = 0;
+        self.sound_active = false;
+        self.timer_stats = TimerStats::default();
+
+        self.audio.silence();
+        self.display
+            .set_resolution(self.display_resolution())
+            .map_err(|e| io::Error::other(e.to_string()))
+    }
 
-        if chip8.input.should_quit() {
-            break;
-        }
+    /// Resets the machine and replaces the loaded program.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Program bytes to load at the start address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program doesn't fit into memory, in which case
+    /// the machine is left untouched.
+    pub fn load_rom(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut memory = [0u8; MEMORY_SIZE];
+        copy_program(&mut memory, bytes, self.start_address)?;
+        memory[..CHIP8_FONTSET.len()].copy_from_slice(&CHIP8_FONTSET);
+
+        self.memory = memory;
+        self.reset()?;
+        Ok(())
     }
-}
 
-pub fn load_program_rom(rom_path: &str) -> io::Result<[u8; MEMORY_SIZE]> {
-    let mut rom = File::open(rom_path)?;
-    let mut buffer = [0u8; MEMORY_SIZE];
-    rom.read(&mut buffer[ROM_START_ADDRESS..])?;
-    Ok(buffer)
+    /// Returns the opcode at `PC` without advancing it.
+    ///
+    /// Like instruction fetches, reads wrap around the end of memory.
+    pub fn peek_opcode(&self) -> u16 {
+        let pc = self.PC % MEMORY_SIZE;
+        (self.memory[pc] as u16) << 8 | self.memory[(pc + 1) % MEMORY_SIZE] as u16
+    }
+
+    /// Returns the instruction at `PC` in assembly syntax, without advancing `PC`.
+    pub fn peek_disassembly(&self) -> String {
+        decode_with_quirks(self.peek_opcode(), &self.mode, &self.quirks).to_string()
+    }
+
+    /// Describes what an opcode does with the current mode and quirks, and which state
+    /// it modifies, without executing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `opcode` - The opcode to describe.
+    pub fn explain(&self, opcode: u16) -> String {
+        decode_with_quirks(opcode, &self.mode, &self.quirks).explain(&self.quirks)
+    }
+
+    /// Executes a single opcode against the current state, without fetching it from memory.
+    ///
+    /// Meant for fuzzing and tooling: any opcode may be passed, and invalid ones are
+    /// reported as errors instead of panicking.
+    ///
+    /// # Arguments
+    ///
+    /// * `opcode` - The opcode to execute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the opcode is unknown in the current mode, overflows or
+    /// underflows the stack, or accesses memory out of bounds.
+    pub fn execute_raw(&mut self, opcode: u16) -> Result<(), Chip8Error> {
+        self.execute_opcode(opcode)
+    }
+
+    /// Executes a single instruction without updating the timers or the display.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the instruction fails, as with `execute_raw`.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        self.emulate_cycle()
+    }
+
+    /// Executes a single instruction while the timers stay frozen, the counterpart of
+    /// `tick_timers_only`. This is the same as `step`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the instruction fails, as with `execute_raw`.
+    pub fn tick_cpu_only(&mut self) -> Result<(), Chip8Error> {
+        self.step()
+    }
+
+    /// Ticks the delay and sound timers once, like at the end of a frame, without
+    /// executing any instruction.
+    pub fn tick_timers_only(&mut self) {
+        self.update_timers();
+    }
+
+    /// Executes the instruction at `PC`, running a `2nnn` subroutine call until it
+    /// returns. Other instructions are executed like `step`.
+    ///
+    /// Stops early after a million cycles if the subroutine never returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any executed instruction fails.
+    pub fn step_over(&mut self) -> Result<(), Chip8Error> {
+        let is_call = self.release_key_register.is_none()
+            && matches!(
+                decode_with_quirks(self.peek_opcode(), &self.mode, &self.quirks),
+                Instruction::Call { .. }
+            );
+        let sp = self.SP;
+        self.emulate_cycle()?;
+
+        if is_call {
+            let mut cycles = 0;
+            while self.SP > sp && cycles < STEP_OVER_MAX_CYCLES {
+                self.emulate_cycle()?;
+                cycles += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn emulate_cycle(&mut self) -> Result<(), Chip8Error> {
+        if self.halted {
+            Ok(())
+        } else if let Some(register) = self.release_key_register {
+            self.wait_for_next_key(register);
+            Ok(())
+        } else {
+            let opcode = self.fetch_opcode();
+            self.execute_opcode(opcode)
+        }
+    }
+
+    /// Executes a cycle, returning `true` if the watchdog tripped.
+    fn watched_cycle(&mut self) -> Result<bool, Chip8Error> {
+        let pc = self.PC;
+        self.emulate_cycle()?;
+
+        let max_cycles = match self.max_cycles {
+            Some(max_cycles) => max_cycles,
+            None => return Ok(false),
+        };
+        if self.release_key_register.is_some() || self.halted {
+            // Waiting for a key or a reset is not a hang
+            self.idle_cycles = 0;
+            return Ok(false);
+        }
+        if self.PC == pc {
+            return Ok(true);
+        }
+
+        let display_changes = self.display.change_count();
+        let keys = self.pressed_keys();
+        if display_changes != self.last_display_changes || keys != self.last_keys {
+            self.last_display_changes = display_changes;
+            self.last_keys = keys;
+            self.idle_cycles = 0;
+            return Ok(false);
+        }
+
+        self.idle_cycles += 1;
+        Ok(self.idle_cycles >= max_cycles)
+    }
+
+    fn trace(&mut self, event: TraceEvent) {
+        if let Some(callback) = self.trace_callback.as_mut() {
+            callback(event);
+        }
+    }
+
+    /// Returns the address of the instruction being executed.
+    fn instruction_address(&self) -> usize {
+        self.PC.wrapping_sub(2) % MEMORY_SIZE
+    }
+
+    fn report_quirk(&mut self, quirk: Quirk) {
+        if self.strict {
+            self.trace(TraceEvent::QuirkDependent {
+                address: self.instruction_address(),
+                quirk,
+            });
+        }
+    }
+
+    fn render(&mut self) -> Result<(), Chip8Error> {
+        self.display.render().map_err(display_error)?;
+
+        if let Some(callback) = self.frame_callback.as_mut() {
+            let (width, height) = (self.display.width(), self.display.height());
+            let mut buffer = vec![0; width * height * 4];
+            self.display.render_to_buffer(&mut buffer);
+            callback(&buffer, width, height);
+        }
+        Ok(())
+    }
+
+    fn update_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+
+        if self.sound_timer > 0 {
+            if !self.sound_active {
+                self.sound_active = true;
+                if let Some(callback) = self.sound_start_callback.as_mut() {
+                    callback();
+                }
+            }
+            self.audio.start();
+            self.sound_timer -= 1;
+        } else {
+            self.audio.stop()
+        }
+
+        if self.sound_active && self.sound_timer == 0 {
+            self.sound_active = false;
+            if let Some(callback) = self.sound_stop_callback.as_mut() {
+                callback();
+            }
+        }
+    }
+
+    fn wait_for_next_key(&mut self, register: usize) {
+        if let Some(val) = self.input.get_next_released_key() {
+            // Keys held since the wait started don't count unless the quirk allows it
+            let held = 1 << val;
+            if !self.quirks.fx0a_accept_held && self.held_keys_at_wait & held != 0 {
+                self.held_keys_at_wait &= !held;
+                return;
+            }
+            self.V[register] = val;
+            self.release_key_register = None;
+        }
+    }
+
+    /// Returns the pressed keys as a bitmask, with bit `n` set for hex key `n`.
+    fn pressed_keys(&self) -> u16 {
+        (0..16).fold(0, |keys, hex_key| {
+            keys | (self.input.is_key_pressed(hex_key) as u16) << hex_key
+        })
+    }
+
+    /// Fetches the opcode at `PC` and advances it, wrapping around the end of memory.
+    fn fetch_opcode(&mut self) -> u16 {
+        let opcode = self.peek_opcode();
+        self.PC = (self.PC % MEMORY_SIZE + 2) % MEMORY_SIZE;
+        opcode
+    }
+
+    /// Skips the next instruction, wrapping around the end of memory like `fetch_opcode`.
+    fn skip_instruction(&mut self) {
+        self.PC = (self.PC + 2) % MEMORY_SIZE;
+    }
+
+    fn execute_opcode(&mut self, opcode: u16) -> Result<(), Chip8Error> {
+        self.apply(decode_with_quirks(opcode, &self.mode, &self.quirks))
+    }
+
+    /// Executes a decoded instruction against the current state, e.g. to test an
+    /// instruction without encoding it.
+    ///
+    /// Like with `execute_raw`, the instruction is not fetched from memory and `PC` is
+    /// only changed by jumps, calls, and skips.
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - The instruction to execute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the instruction is `Unknown`, names a register past `VF`,
+    /// overflows or underflows the stack, or SDL2 fails to update the display.
+    pub fn apply(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
+        let (x, y) = instruction.registers();
+        if let Some(index) = x.into_iter().chain(y).find(|&index| index >= V_COUNT) {
+            return Err(Chip8Error::InvalidRegister(index));
+        }
+
+        match instruction {
+            Instruction::Sys { nnn } => self.op_0nnn(nnn)?,
+            Instruction::ScrollDown { n } => self.op_00cn(n),
+            Instruction::ScrollUp { n } => self.op_00dn(n),
+            Instruction::Cls => self.op_00e0()?,
+            Instruction::Ret => self.op_00ee()?,
+            Instruction::ScrollRight => self.op_00fb(),
+            Instruction::ScrollLeft => self.op_00fc(),
+            Instruction::Exit => self.op_00fd(),
+            Instruction::Lores => self.op_00fe()?,
+            Instruction::Hires => self.op_00ff()?,
+            Instruction::Jp { nnn } => self.op_1nnn(nnn)?,
+            Instruction::Call { nnn } => self.op_2nnn(nnn)?,
+            Instruction::SeVxByte { x, kk } => self.op_3xkk(x, kk),
+            Instruction::SneVxByte { x, kk } => self.op_4xkk(x, kk),
+            Instruction::SeVxVy { x, y } => self.op_5xy0(x, y),
+            Instruction::SaveRange { x, y } => self.op_5xy2(x, y),
+            Instruction::LoadRange { x, y } => self.op_5xy3(x, y),
+            Instruction::LdVxByte { x, kk } => self.op_6xkk(x, kk),
+            Instruction::AddVxByte { x, kk } => self.op_7xkk(x, kk),
+            Instruction::LdVxVy { x, y } => self.op_8xy0(x, y),
+            Instruction::Or { x, y } => self.op_8xy1(x, y),
+            Instruction::And { x, y } => self.op_8xy2(x, y),
+            Instruction::Xor { x, y } => self.op_8xy3(x, y),
+            Instruction::AddVxVy { x, y } => self.op_8xy4(x, y),
+            Instruction::Sub { x, y } => self.op_8xy5(x, y),
+            Instruction::Shr { x, y } => self.op_8xy6(x, y),
+            Instruction::Subn { x, y } => self.op_8xy7(x, y),
+            Instruction::Shl { x, y } => self.op_8xye(x, y),
+            Instruction::SneVxVy { x, y } => self.op_9xy0(x, y),
+            Instruction::LdI { nnn } => self.op_annn(nnn),
+            Instruction::JpV0 { nnn } => self.op_bnnn(nnn),
+            Instruction::JpVx { x, nnn } => self.op_bxnn(x, nnn),
+            Instruction::Rnd { x, kk } => self.op_cxkk(x, kk),
+            Instruction::DrawSprite { x, y, n } => self.op_dxyn(x, y, n),
+            Instruction::Skp { x } => self.op_ex9e(x),
+            Instruction::Sknp { x } => self.op_exa1(x),
+            Instruction::Plane { n } => self.op_fn01(n),
+            Instruction::LdVxDt { x } => self.op_fx07(x),
+            Instruction::LdVxK { x } => self.op_fx0a(x),
+            Instruction::LdDtVx { x } => self.op_fx15(x),
+            Instruction::LdStVx { x } => self.op_fx18(x),
+            Instruction::AddIVx { x } => self.op_fx1e(x),
+            Instruction::LdFVx { x } => self.op_fx29(x),
+            Instruction::LdBVx { x } => self.op_fx33(x),
+            Instruction::LdIVx { x } => self.op_fx55(x),
+            Instruction::LdVxI { x } => self.op_fx65(x),
+            Instruction::Unknown { opcode } => self.unsupported_opcode(opcode)?,
+        }
+        Ok(())
+    }
+
+    /// Reports an opcode that is invalid in the current mode, naming the first mode
+    /// that supports it so the frontend can suggest switching.
+    fn unsupported_opcode(&self, opcode: u16) -> Result<(), Chip8Error> {
+        let needed = Mode::ALL.into_iter().find(|mode| {
+            decode_with_quirks(opcode, mode, &self.quirks) != Instruction::Unknown { opcode }
+        });
+        match needed {
+            Some(needed) => Err(Chip8Error::OpcodeRequiresMode { opcode, needed }),
+            None => Self::unknown_opcode(opcode),
+        }
+    }
+
+    fn unknown_opcode(opcode: u16) -> Result<(), Chip8Error> {
+        Err(Chip8Error::UnknownOpcode(opcode))
+    }
+
+    /// Returns the memory address `offset` bytes after `I`, wrapped or clamped
+    /// to the end of memory depending on the `memory_wrap` quirk.
+    fn memory_address(&self, offset: usize) -> usize {
+        let address = self.I as usize + offset;
+        if address < MEMORY_SIZE {
+            address
+        } else if self.quirks.memory_wrap {
+            address % MEMORY_SIZE
+        } else {
+            MEMORY_SIZE - 1
+        }
+    }
+
+    // 0nnn - SYS addr: Jump to a machine code routine at nnn.
+    fn op_0nnn(&mut self, nnn: u16) -> Result<(), Chip8Error> {
+        // This instruction is only used on the old computers on which Chip-8
+        // was originally implemented. It is ignored by modern interpreters.
+        if self.quirks.vip_hires && nnn == VIP_HIRES_CLS {
+            return self.display.clear().map_err(display_error);
+        }
+        if self.quirks.sys_is_error {
+            return Self::unknown_opcode(nnn);
+        }
+
+        self.trace(TraceEvent::Sys {
+            address: self.instruction_address(),
+            nnn,
+        });
+        Ok(())
+    }
+
+    fn op_00cn(&mut self, n: u8) {
+        self.display.scroll_vertical(n as isize, self.planes)
+    }
+
+    fn op_00dn(&mut self, n: u8) {
+        self.display.scroll_vertical(-(n as isize), self.planes)
+    }
+
+    // 00E0 - CLS: Clear the display.
+    fn op_00e0(&mut self) -> Result<(), Chip8Error> {
+        self.display.clear().map_err(display_error)
+    }
+
+    // 00EE - RET: Return from a subroutine.
+    fn op_00ee(&mut self) -> Result<(), Chip8Error> {
+        if self.SP == 0 {
+            return Err(Chip8Error::StackUnderflow);
+        }
+        self.SP -= 1;
+        self.PC = self.stack[self.SP];
+        Ok(())
+    }
+
+    fn op_00fb(&mut self) {
+        self.display
+            .scroll_horizontal(4, self.planes, self.quirks.wrap_scroll)
+    }
+
+    fn op_00fc(&mut self) {
+        self.display
+            .scroll_horizontal(-4, self.planes, self.quirks.wrap_scroll)
+    }
+
+    // 00FD - EXIT: Halt the program until the machine is reset.
+    fn op_00fd(&mut self) {
+        self.halted = true;
+    }
+
+    // 00FE - LORES: Switch to lores mode.
+    fn op_00fe(&mut self) -> Result<(), Chip8Error> {
+        self.lores = true;
+        self.switch_resolution()
+    }
+
+    // 00FF - HIRES: Switch to hires mode.
+    fn op_00ff(&mut self) -> Result<(), Chip8Error> {
+        self.lores = false;
+        self.switch_resolution()
+    }
+
+    /// Returns the display resolution for the current lores/hires mode.
+    ///
+    /// With doubled lores pixels the display always stays in hires.
+    fn display_resolution(&self) -> Resolution {
+        if self.lores && !self.quirks.lores_doubled_pixels {
+            Resolution::Low
+        } else {
+            Resolution::High
+        }
+    }
+
+    /// Applies a lores/hires mode switch to the display, clearing it unless lores pixels
+    /// are doubled on the hires display.
+    fn switch_resolution(&mut self) -> Result<(), Chip8Error> {
+        if !self.quirks.lores_doubled_pixels {
+            self.display
+                .set_resolution(self.display_resolution())
+                .map_err(display_error)?;
+        }
+        Ok(())
+    }
+
+    // 1nnn - JP addr: Jump to location nnn.
+    fn op_1nnn(&mut self, addr: u16) -> Result<(), Chip8Error> {
+        // The hires programs of the COSMAC VIP start with a jump over the patched
+        // interpreter, which has to be emulated instead
+        if self.quirks.vip_hires
+            && addr == VIP_HIRES_TRIGGER
+            && self.instruction_address() == ROM_START_ADDRESS
+        {
+            self.display
+                .set_resolution(Resolution::VipHires)
+                .map_err(display_error)?;
+            self.PC = VIP_HIRES_START_ADDRESS;
+            return Ok(());
+        }
+        self.PC = addr as usize;
+        Ok(())
+    }
+
+    // 2nnn - CALL addr: Call subroutine at nnn.
+    fn op_2nnn(&mut self, addr: u16) -> Result<(), Chip8Error> {
+        if self.SP >= MAX_STACK_LEVELS {
+            return Err(Chip8Error::StackOverflow);
+        }
+        self.stack[self.SP] = self.PC;
+        self.SP += 1;
+        self.PC = addr as usize;
+        Ok(())
+    }
+
+    // 3xkk - SE Vx, byte: Skip next instruction if Vx = kk.
+    fn op_3xkk(&mut self, x: usize, kk: u8) {
+        if self.V[x] == kk {
+            self.skip_instruction();
+        }
+    }
+
+    // 4xkk - SNE Vx, byte: Skip next instruction if Vx != kk.
+    fn op_4xkk(&mut self, x: usize, kk: u8) {
+        if self.V[x] != kk {
+            self.skip_instruction();
+        }
+    }
+
+    // 5xy0 - SE Vx, Vy: Skip next instruction if Vx = Vy.
+    fn op_5xy0(&mut self, x: usize, y: usize) {
+        if self.V[x] == self.V[y] {
+            self.skip_instruction();
+        }
+    }
+
+    // 5xy2 - SAVE Vx - Vy: Store registers Vx through Vy in memory starting at location I.
+    // XO-CHIP only. The range may be descending, and I is not incremented.
+    fn op_5xy2(&mut self, x: usize, y: usize) {
+        for (offset, register) in Self::register_range(x, y).enumerate() {
+            let address = self.memory_address(offset);
+            self.memory[address] = self.V[register];
+        }
+    }
+
+    // 5xy3 - LOAD Vx - Vy: Read registers Vx through Vy from memory starting at location I.
+    // XO-CHIP only. The range may be descending, and I is not incremented.
+    fn op_5xy3(&mut self, x: usize, y: usize) {
+        for (offset, register) in Self::register_range(x, y).enumerate() {
+            self.V[register] = self.memory[self.memory_address(offset)];
+        }
+    }
+
+    /// Returns the register indices from `x` to `y`, counting down if `y` is below `x`.
+    fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item = usize>> {
+        if x <= y {
+            Box::new(x..=y)
+        } else {
+            Box::new((y..=x).rev())
+        }
+    }
+
+    // 6xkk - LD Vx, byte: Set Vx = kk.
+    fn op_6xkk(&mut self, x: usize, kk: u8) {
+        self.V[x] = kk;
+    }
+
+    // 7xkk - ADD Vx, byte: Set Vx = Vx + kk.
+    fn op_7xkk(&mut self, x: usize, kk: u8) {
+        self.V[x] = self.V[x].wrapping_add(kk);
+    }
+
+    // 8xy0 - LD Vx, Vy: Set Vx = Vy.
+    fn op_8xy0(&mut self, x: usize, y: usize) {
+        self.V[x] = self.V[y];
+    }
+
+    // 8xy1 - OR Vx, Vy: Set Vx = Vx OR Vy.
+    // The reset_vf_on_logic quirk resets VF after the result is written.
+    fn op_8xy1(&mut self, x: usize, y: usize) {
+        self.V[x] |= self.V[y];
+        if self.quirks.reset_vf_on_logic {
+            self.V[0xF] = 0;
+        }
+    }
+
+    // 8xy2 - AND Vx, Vy: Set Vx = Vx AND Vy.
+    // The reset_vf_on_logic quirk resets VF after the result is written.
+    fn op_8xy2(&mut self, x: usize, y: usize) {
+        self.V[x] &= self.V[y];
+        if self.quirks.reset_vf_on_logic {
+            self.V[0xF] = 0;
+        }
+    }
+
+    // 8xy3 - XOR Vx, Vy: Set Vx = Vx XOR Vy.
+    // The reset_vf_on_logic quirk resets VF after the result is written.
+    fn op_8xy3(&mut self, x: usize, y: usize) {
+        self.V[x] ^= self.V[y];
+        if self.quirks.reset_vf_on_logic {
+            self.V[0xF] = 0;
+        }
+    }
+
+    // 8xy4 - ADD Vx, Vy: Set Vx = Vx + Vy, set VF = carry.
+    fn op_8xy4(&mut self, x: usize, y: usize) {
+        let (result, carry) = self.V[x].overflowing_add(self.V[y]);
+        self.V[x] = result;
+        self.V[0xF] = if carry { 1 } else { 0 }
+    }
+
+    // 8xy5 - SUB Vx, Vy: Set Vx = Vx - Vy, set VF = NOT borrow.
+    fn op_8xy5(&mut self, x: usize, y: usize) {
+        let (result, borrow) = self.V[x].overflowing_sub(self.V[y]);
+        self.V[x] = result;
+        self.V[0xF] = !borrow as u8;
+    }
+
+    // 8xy6 - SHR Vx {, Vy}: Set Vx = Vx SHR 1.
+    // The shift_uses_vy quirk shifts Vy instead; VF is written last so it holds the
+    // shifted-out bit even when x is F.
+    fn op_8xy6(&mut self, x: usize, y: usize) {
+        self.report_quirk(Quirk::Shift);
+        let source = self.shift_source(x, y);
+        self.V[x] = source >> 1;
+        self.V[0xF] = source & 0x1;
+    }
+
+    // 8xy7 - SUBN Vx, Vy: Set Vx = Vy - Vx, set VF = NOT borrow.
+    fn op_8xy7(&mut self, x: usize, y: usize) {
+        let (result, borrow) = self.V[y].overflowing_sub(self.V[x]);
+        self.V[x] = result;
+        self.V[0xF] = !borrow as u8;
+    }
+
+    // 8xye - SHL Vx {, Vy}: Set Vx = Vx SHL 1.
+    // The shift_uses_vy quirk shifts Vy instead; VF is written last so it holds the
+    // shifted-out bit even when x is F.
+    fn op_8xye(&mut self, x: usize, y: usize) {
+        self.report_quirk(Quirk::Shift);
+        let source = self.shift_source(x, y);
+        self.V[x] = source << 1;
+        self.V[0xF] = source >> 7;
+    }
+
+    /// Returns the value shifted by `8xy6` and `8xyE`.
+    fn shift_source(&self, x: usize, y: usize) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.V[y]
+        } else {
+            self.V[x]
+        }
+    }
+
+    // 9xy0 - SNE Vx, Vy: Skip next instruction if Vx != Vy.
+    fn op_9xy0(&mut self, x: usize, y: usize) {
+        if self.V[x] != self.V[y] {
+            self.skip_instruction();
+        }
+    }
+
+    // Annn - LD I, addr: Set I = nnn.
+    fn op_annn(&mut self, addr: u16) {
+        self.I = addr;
+    }
+
+    // Bnnn - JP V0, addr: Jump to location nnn + V0.
+    // The target wraps around to stay within memory.
+    fn op_bnnn(&mut self, addr: u16) {
+        self.report_quirk(Quirk::JumpOffset);
+        self.PC = (addr as usize + self.V[0] as usize) % MEMORY_SIZE;
+    }
+
+    // Bxnn - JP VX, addr: Jump to location xnn + VX.
+    // The target wraps around to stay within memory.
+    fn op_bxnn(&mut self, x: usize, xnn: u16) {
+        self.report_quirk(Quirk::JumpOffset);
+        self.PC = (xnn as usize + self.V[x] as usize) % MEMORY_SIZE;
+    }
+
+    // Cxkk - RND Vx, byte: Set Vx = random byte AND kk.
+    fn op_cxkk(&mut self, x: usize, kk: u8) {
+        self.V[x] = rand::thread_rng().gen::<u8>() & kk;
+    }
+
+    // Dxyn - DRW Vx, Vy, nibble: Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.
+    // XO-CHIP wraps pixels instead of clipping them.
+    // Each selected plane gets its own n bytes, the first plane's at I followed by the second plane's.
+    fn op_dxyn(&mut self, x: usize, y: usize, n: u8) {
+        // Lores pixels doubled on the hires display are drawn as 2x2 blocks
+        let pixel_size = if self.lores && self.quirks.lores_doubled_pixels {
+            2
+        } else {
+            1
+        };
+        let width = self.display.width() / pixel_size;
+        let height = self.display.height() / pixel_size;
+
+        let x_coord = self.V[x] as usize % width;
+        let y_coord = self.V[y] as usize % height;
+
+        let selected_planes = self.planes;
+        let planes = [0b01, 0b10]
+            .into_iter()
+            .filter(|plane| selected_planes & plane != 0);
+
+        self.collision_planes = 0;
+        for (plane_index, plane) in planes.enumerate() {
+            let offset = plane_index * n as usize;
+            for byte_index in 0..n as usize {
+                let y = (y_coord + byte_index) % height;
+                if self.quirks.clip_sprites && y_coord + byte_index >= height {
+                    break;
+                }
+                let byte = self.memory[self.memory_address(offset + byte_index)];
+                for bit_index in 0..SPRITE_WIDTH {
+                    let x = (x_coord + bit_index) % width;
+                    if self.quirks.clip_sprites && x_coord + bit_index >= width {
+                        break;
+                    }
+                    let bit = (byte >> (7 - bit_index)) & 1;
+                    // set_pixel reports the collision in the plane's own bit
+                    for block_y in 0..pixel_size {
+                        for block_x in 0..pixel_size {
+                            self.collision_planes |= self.display.set_pixel(
+                                x * pixel_size + block_x,
+                                y * pixel_size + block_y,
+                                bit * plane,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        self.V[0xF] = (self.collision_planes != 0) as u8;
+        if self.V[0xF] == 1 {
+            if let Some(callback) = self.collision_callback.as_mut() {
+                callback();
+            }
+        }
+    }
+
+    // Ex9E - SKP Vx: Skip next instruction if key with the value of Vx is pressed.
+    fn op_ex9e(&mut self, x: usize) {
+        if self.input.is_key_pressed(self.V[x] & 0xF) {
+            self.skip_instruction();
+        }
+    }
+
+    // ExA1 - SKNP Vx: Skip next instruction if key with the value of Vx is not pressed.
+    fn op_exa1(&mut self, x: usize) {
+        if !self.input.is_key_pressed(self.V[x] & 0xF) {
+            self.skip_instruction();
+        }
+    }
+
+    // Fn01 - PLANE n: Select the bitplanes n that sprites are drawn to.
+    fn op_fn01(&mut self, n: u8) {
+        self.planes = n & 0x3;
+    }
+
+    // Fx07 - LD Vx, DT: Set Vx = delay timer value.
+    fn op_fx07(&mut self, x: usize) {
+        self.V[x] = self.delay_timer;
+        self.timer_stats.delay_reads += 1;
+    }
+
+    // Fx0A - LD Vx, K: Wait for a key press, store the value of the key in Vx.
+    fn op_fx0a(&mut self, x: usize) {
+        self.release_key_register = Some(x);
+        self.held_keys_at_wait = self.pressed_keys();
+    }
+
+    // Fx15 - LD DT, Vx: Set delay timer = Vx.
+    fn op_fx15(&mut self, x: usize) {
+        self.delay_timer = self.V[x];
+        self.timer_stats.delay_writes += 1;
+        self.trace(TraceEvent::DelayTimerSet {
+            address: self.instruction_address(),
+            value: self.V[x],
+        });
+    }
+
+    // Fx18 - LD ST, Vx: Set sound timer = Vx.
+    fn op_fx18(&mut self, x: usize) {
+        self.sound_timer = self.V[x];
+    }
+
+    // Fx1E - ADD I, Vx: Set I = I + Vx.
+    fn op_fx1e(&mut self, x: usize) {
+        self.I = self.I.wrapping_add(self.V[x] as u16);
+    }
+
+    // Fx29 - LD F, Vx: Set I = location of sprite for digit Vx.
+    fn op_fx29(&mut self, x: usize) {
+        self.I = self.V[x] as u16 * 5;
+    }
+
+    // Fx33 - LD B, Vx: Store BCD representation of Vx in memory locations I, I+1, and I+2.
+    fn op_fx33(&mut self, x: usize) {
+        let digits = [self.V[x] / 100, (self.V[x] % 100) / 10, self.V[x] % 10];
+        for (offset, digit) in digits.into_iter().enumerate() {
+            let address = self.memory_address(offset);
+            self.memory[address] = digit;
+        }
+    }
+
+    // Fx55 - LD [I], Vx: Store registers V0 through Vx in memory starting at location I.
+    // I advances according to the load_store_increment quirk; SuperChip leaves it unchanged.
+    fn op_fx55(&mut self, x: usize) {
+        self.report_quirk(Quirk::LoadStoreIncrement);
+        for offset in 0..=x {
+            let address = self.memory_address(offset);
+            self.memory[address] = self.V[offset];
+        }
+        self.advance_i_after_load_store(x);
+    }
+
+    // Fx65 - LD Vx, [I]: Read registers V0 through Vx from memory starting at location I.
+    // I advances according to the load_store_increment quirk; SuperChip leaves it unchanged.
+    fn op_fx65(&mut self, x: usize) {
+        self.report_quirk(Quirk::LoadStoreIncrement);
+        for offset in 0..=x {
+            self.V[offset] = self.memory[self.memory_address(offset)];
+        }
+        self.advance_i_after_load_store(x);
+    }
+
+    /// Advances `I` after `Fx55` or `Fx65` according to the `load_store_increment` quirk.
+    fn advance_i_after_load_store(&mut self, x: usize) {
+        let increment = match self.quirks.load_store_increment {
+            LoadStoreIncrement::None => 0,
+            LoadStoreIncrement::X => x as u16,
+            LoadStoreIncrement::XPlusOne => x as u16 + 1,
+        };
+        self.I = self.I.wrapping_add(increment);
+    }
+}
+
+/// Runs the emulator until a quit event is received.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `speed` - Initial execution speed in instructions per second, see `Chip8::set_speed`.
+/// * `rom_paths` - ROMs that can be hot-swapped at runtime; the first one is assumed
+///   to be loaded already.
+///
+/// # Returns
+///
+/// Why the emulator stopped: a quit event, an error, or the watchdog.
+pub fn run(chip8: Chip8, speed: u32, rom_paths: &[String]) -> RunOutcome {
+    run_with_clock(chip8, speed, rom_paths, SystemClock)
+}
+
+/// Runs the emulator until a quit event is received, timed by the given clock.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `speed` - Initial execution speed in instructions per second, see `Chip8::set_speed`.
+/// * `rom_paths` - ROMs that can be hot-swapped at runtime; the first one is assumed
+///   to be loaded already.
+/// * `clock` - The clock that schedules cycles and frames.
+///
+/// # Returns
+///
+/// Why the emulator stopped: a quit event, an error, or the watchdog.
+pub fn run_with_clock(
+    chip8: Chip8,
+    speed: u32,
+    rom_paths: &[String],
+    clock: impl Clock,
+) -> RunOutcome {
+    run_from(chip8, speed, rom_paths, 0, clock)
+}
+
+/// Runs the emulator like `run_with_clock`, with the ROM at `rom_index` loaded.
+fn run_from(
+    mut chip8: Chip8,
+    speed: u32,
+    rom_paths: &[String],
+    mut rom_index: usize,
+    clock: impl Clock,
+) -> RunOutcome {
+    chip8.set_speed(speed);
+    let mut scheduler = Scheduler::new(clock, chip8.speed(), FRAME_RATE);
+    let mut frame_skip = FrameSkip::new(chip8.frame_skip);
+    let mut rates = RateCounter::new(scheduler.now());
+    // Whether the sound was muted because the window lost the focus
+    let mut muted_for_focus = false;
+    // While paused, the CPU and the timers only advance on the debug hotkeys
+    let mut paused = false;
+
+    loop {
+        scheduler.set_speed(chip8.speed());
+        if scheduler.cycle_due() {
+            if !paused {
+                match chip8.watched_cycle() {
+                    Ok(false) => rates.cycle(),
+                    Ok(true) => return RunOutcome::Watchdog,
+                    Err(e) => {
+                        eprintln!("Error at 0x{:03X}: {}", chip8.PC.wrapping_sub(2), e);
+                        return RunOutcome::Error(e);
+                    }
+                }
+            }
+            chip8.input.update();
+
+            let focused = chip8.input.is_focused();
+            if !focused && !chip8.is_muted() {
+                chip8.set_muted(true);
+                muted_for_focus = true;
+            } else if focused && muted_for_focus {
+                chip8.set_muted(false);
+                muted_for_focus = false;
+            }
+
+            for hotkey in chip8.input.take_hotkeys() {
+                match hotkey {
+                    Hotkey::NextRom if !rom_paths.is_empty() => {
+                        let next = (rom_index + 1) % rom_paths.len();
+                        rom_index = swap_rom(&mut chip8, rom_paths, rom_index, next);
+                    }
+                    Hotkey::PreviousRom if !rom_paths.is_empty() => {
+                        let previous = (rom_index + rom_paths.len() - 1) % rom_paths.len();
+                        rom_index = swap_rom(&mut chip8, rom_paths, rom_index, previous);
+                    }
+                    Hotkey::ToggleMute => chip8.set_muted(!chip8.is_muted()),
+                    Hotkey::ToggleHud => chip8.set_hud(!chip8.display.is_hud_enabled()),
+                    Hotkey::CyclePalette => chip8.display.cycle_palette(),
+                    Hotkey::Reset => {
+                        if let Err(e) = chip8.reset() {
+                            eprintln!("Error resetting: {}", e);
+                            return RunOutcome::Error(Chip8Error::Display(e.to_string()));
+                        }
+                    }
+                    Hotkey::ToggleStats => chip8.set_stats(!chip8.display.is_stats_enabled()),
+                    Hotkey::TogglePause => paused = !paused,
+                    Hotkey::TickCpu if paused => {
+                        if let Err(e) = chip8.tick_cpu_only() {
+                            eprintln!("Error at 0x{:03X}: {}", chip8.PC.wrapping_sub(2), e);
+                            return RunOutcome::Error(e);
+                        }
+                    }
+                    Hotkey::TickTimers if paused => chip8.tick_timers_only(),
+                    Hotkey::SpeedUp => chip8.set_speed(chip8.speed().saturating_add(SPEED_STEP)),
+                    Hotkey::SpeedDown => {
+                        chip8.set_speed(chip8.speed().saturating_sub(SPEED_STEP).max(SPEED_STEP))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if scheduler.frame_due() {
+            if let Err(e) = present_frame(&mut chip8, &mut frame_skip, &mut rates, scheduler.now())
+            {
+                eprintln!("Error rendering frame: {}", e);
+                return RunOutcome::Error(e);
+            }
+            if !paused {
+                chip8.update_timers();
+            }
+        }
+
+        if chip8.should_stop() {
+            // Leave the audio device paused, without touching the mute setting, and show
+            // the last state of the display
+            chip8.audio.silence();
+            if let Err(e) = chip8.render() {
+                eprintln!("Error rendering frame: {}", e);
+                return RunOutcome::Error(e);
+            }
+            return RunOutcome::Quit;
+        }
+    }
+}
+
+/// Draws the overlays and presents the frame unless it is skipped, counting it for the
+/// speed overlay if the display contents changed.
+fn present_frame(
+    chip8: &mut Chip8,
+    frame_skip: &mut FrameSkip,
+    rates: &mut RateCounter,
+    now: Instant,
+) -> Result<(), Chip8Error> {
+    chip8.display.set_beeping(chip8.is_beeping());
+    // The overlays mark every frame as changed, so only look at what was drawn before them
+    let changed = chip8.display.is_dirty();
+    chip8
+        .display
+        .render_hud(&chip8.cpu_state())
+        .map_err(display_error)?;
+    rates.update(now);
+    chip8
+        .display
+        .render_stats(rates.fps, rates.ips)
+        .map_err(display_error)?;
+    if !frame_skip.skip(changed) {
+        if changed {
+            rates.frame();
+        }
+        chip8.render()?;
+    }
+    Ok(())
+}
+
+/// Shows a ROM picker until a ROM is selected with the keypad, then runs it like `run`.
+///
+/// Keys 2 and 8 move the selection up and down, and 5 loads the selected ROM. The
+/// window title shows the file name of the selected ROM.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `speed` - Initial execution speed in instructions per second, see `Chip8::set_speed`.
+/// * `rom_paths` - ROMs to pick from; the first one is assumed to be loaded already.
+///
+/// # Returns
+///
+/// Why the emulator stopped: a quit event, an error, or the watchdog. Quits right away
+/// if there are no ROMs to pick from.
+pub fn run_rom_menu(mut chip8: Chip8, speed: u32, rom_paths: &[String]) -> RunOutcome {
+    if rom_paths.is_empty() {
+        return RunOutcome::Quit;
+    }
+    let selected = match pick_rom(&mut chip8, rom_paths) {
+        Ok(Some(selected)) => selected,
+        Ok(None) => return RunOutcome::Quit,
+        Err(e) => {
+            eprintln!("Error rendering frame: {}", e);
+            return RunOutcome::Error(e);
+        }
+    };
+
+    chip8.display.set_subtitle(None);
+    // Clear the menu, even if the selected ROM fails to load
+    if let Err(e) = chip8.reset() {
+        eprintln!("Error resetting: {}", e);
+        return RunOutcome::Error(Chip8Error::Display(e.to_string()));
+    }
+    let rom_index = swap_rom(&mut chip8, rom_paths, 0, selected);
+    run_from(chip8, speed, rom_paths, rom_index, SystemClock)
+}
+
+/// Shows the ROM picker until a ROM is selected, returning its index, or `None` on quit.
+fn pick_rom(chip8: &mut Chip8, rom_paths: &[String]) -> Result<Option<usize>, Chip8Error> {
+    let mut scheduler = Scheduler::new(SystemClock, FRAME_RATE, FRAME_RATE);
+    let mut selected = 0;
+    let mut changed = true;
+
+    loop {
+        if changed {
+            let name = Path::new(&rom_paths[selected])
+                .file_name()
+                .map(|name| name.to_string_lossy());
+            chip8.display.draw_menu(rom_paths.len(), selected);
+            chip8.display.set_subtitle(name.as_deref());
+            chip8.render()?;
+            changed = false;
+        }
+        if !scheduler.frame_due() {
+            continue;
+        }
+
+        chip8.input.update();
+        // Hotkeys are only handled while a ROM is running
+        chip8.input.take_hotkeys();
+        if chip8.should_stop() {
+            return Ok(None);
+        }
+        match chip8.input.get_next_released_key() {
+            Some(0x2) => selected = (selected + rom_paths.len() - 1) % rom_paths.len(),
+            Some(0x8) => selected = (selected + 1) % rom_paths.len(),
+            Some(0x5) => return Ok(Some(selected)),
+            _ => continue,
+        }
+        changed = true;
+    }
+}
+
+/// Runs the emulator for a fixed number of frames as fast as possible, without SDL.
+///
+/// Each frame updates the input once, executes the cycles for that frame, and then
+/// updates the timers. Stops early if the input requests to quit.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `speed` - Execution speed in instructions per second.
+/// * `frames` - Number of 60 Hz frames to run.
+///
+/// # Returns
+///
+/// Why the emulator stopped: all frames were run, a quit request, an error, or the watchdog.
+pub fn run_headless(chip8: &mut Chip8, speed: u32, frames: u64) -> RunOutcome {
+    let cycles_per_frame = (speed / FRAME_RATE).max(1);
+
+    for _ in 0..frames {
+        chip8.input.update();
+        for _ in 0..cycles_per_frame {
+            match chip8.watched_cycle() {
+                Ok(false) => {}
+                Ok(true) => return RunOutcome::Watchdog,
+                Err(e) => {
+                    eprintln!("Error at 0x{:03X}: {}", chip8.PC.wrapping_sub(2), e);
+                    return RunOutcome::Error(e);
+                }
+            }
+        }
+
+        chip8.display.set_beeping(chip8.is_beeping());
+        if let Err(e) = chip8.render() {
+            eprintln!("Error rendering frame: {}", e);
+            return RunOutcome::Error(e);
+        }
+        chip8.update_timers();
+
+        if chip8.should_stop() {
+            return RunOutcome::Quit;
+        }
+    }
+    RunOutcome::Completed
+}
+
+/// Runs the emulator for a fixed number of iterations without any timing, for
+/// deterministic tests.
+///
+/// Each iteration updates the input and executes one cycle, and the timers tick after
+/// every `cycles_per_timer` iterations. Stops early once the program executes `EXIT`.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `iters` - Maximum number of iterations to run.
+/// * `cycles_per_timer` - Number of iterations per timer tick, e.g. 11 for 700 Hz.
+///
+/// # Returns
+///
+/// The number of iterations that were run.
+///
+/// # Errors
+///
+/// Returns an error if an instruction fails to execute.
+pub fn run_fixed(chip8: &mut Chip8, iters: u64, cycles_per_timer: u64) -> Result<u64, Chip8Error> {
+    let cycles_per_timer = cycles_per_timer.max(1);
+
+    for iter in 1..=iters {
+        chip8.input.update();
+        chip8.emulate_cycle()?;
+        if iter % cycles_per_timer == 0 {
+            chip8.update_timers();
+        }
+        if chip8.halted {
+            return Ok(iter);
+        }
+    }
+    Ok(iters)
+}
+
+/// Runs the emulator with the given input for a number of frames at `DEFAULT_SPEED`,
+/// without SDL or sleeping, e.g. to test many ROMs on a server.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run, usually created with `Chip8::headless`.
+/// * `input` - The input to use from now on, e.g. a `ScriptedInput`.
+/// * `frames` - Number of 60 Hz frames to run.
+///
+/// # Returns
+///
+/// The CPU state after the last frame, or after the emulator stopped early, along with
+/// the reason it stopped, as returned by `run_headless`.
+pub fn run_headless_with_input(
+    chip8: &mut Chip8,
+    input: impl Input + 'static,
+    frames: u64,
+) -> (CpuState, RunOutcome) {
+    chip8.set_input(Box::new(input));
+    let outcome = run_headless(chip8, DEFAULT_SPEED, frames);
+    (chip8.cpu_state(), outcome)
+}
+
+/// Runs the emulator for a number of cycles without SDL and saves the display as a PNG.
+///
+/// The timers tick once every `speed / 60` cycles, like in `run_headless`.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `speed` - Execution speed in instructions per second.
+/// * `cycles` - Number of cycles to run before the screenshot is taken.
+/// * `path` - The PNG file to write, with one image pixel per display pixel.
+///
+/// # Errors
+///
+/// Returns an error if an instruction fails to execute or the file can't be written.
+pub fn render_frame(
+    chip8: &mut Chip8,
+    speed: u32,
+    cycles: u64,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let cycles_per_frame = (speed / FRAME_RATE).max(1) as u64;
+
+    for cycle in 1..=cycles {
+        if let Err(e) = chip8.emulate_cycle() {
+            return Err(io::Error::other(format!(
+                "Error at 0x{:03X}: {}",
+                chip8.PC.wrapping_sub(2),
+                e
+            )));
+        }
+        if cycle % cycles_per_frame == 0 {
+            chip8.update_timers();
+        }
+    }
+
+    let (width, height) = (chip8.display.width(), chip8.display.height());
+    let mut rgba = vec![0; width * height * 4];
+    chip8.display.render_to_buffer(&mut rgba);
+    fs::write(path, png::encode_rgba(&rgba, width, height))
+}
+
+/// Runs the emulator for a number of cycles as fast as possible, without SDL, and
+/// measures the achieved speed.
+///
+/// A frame is rendered and the timers tick once every `speed / 60` cycles, like in
+/// `run_headless`.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to run.
+/// * `speed` - Nominal execution speed in instructions per second, which sets the frame rate.
+/// * `cycles` - Number of cycles to run.
+///
+/// # Errors
+///
+/// Returns an error if an instruction fails to execute.
+pub fn bench(chip8: &mut Chip8, speed: u32, cycles: u64) -> Result<BenchReport, Chip8Error> {
+    let cycles_per_frame = (speed / FRAME_RATE).max(1) as u64;
+    let mut frames = 0;
+
+    let start = Instant::now();
+    for cycle in 1..=cycles {
+        chip8.emulate_cycle()?;
+        if cycle % cycles_per_frame == 0 {
+            chip8.render()?;
+            chip8.update_timers();
+            frames += 1;
+        }
+    }
+
+    Ok(BenchReport {
+        instructions: cycles,
+        frames,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// Loads the ROM at index `next`, returning the index of the ROM that is loaded afterwards.
+fn swap_rom(chip8: &mut Chip8, rom_paths: &[String], current: usize, next: usize) -> usize {
+    match fs::read(&rom_paths[next]).and_then(|bytes| chip8.load_rom(&bytes)) {
+        Ok(()) => next,
+        Err(e) => {
+            eprintln!("Error loading ROM {}: {}", rom_paths[next], e);
+            current
+        }
+    }
+}
+
+/// Lists the CHIP-8 ROMs in a directory, sorted by path.
+///
+/// # Arguments
+///
+/// * `path` - The directory to scan. Subdirectories are not searched.
+///
+/// # Returns
+///
+/// The files with a `.ch8`, `.c8`, or `.xo` extension, in any letter case.
+///
+/// # Errors
+///
+/// Returns an error if the directory can't be read.
+pub fn scan_rom_dir(path: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut roms = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        let is_rom = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                ROM_EXTENSIONS
+                    .iter()
+                    .any(|rom_extension| extension.eq_ignore_ascii_case(rom_extension))
+            });
+        if is_rom && path.is_file() {
+            roms.push(path);
+        }
+    }
+    roms.sort();
+    Ok(roms)
+}
+
+/// Parses a memory patch written as `ADDR=VAL`, with both numbers in hex, e.g. `2A4=FF`
+/// or `0x2A4=0xFF`.
+///
+/// # Arguments
+///
+/// * `text` - The patch to parse.
+pub fn parse_patch(text: &str) -> Result<(usize, u8), String> {
+    let hex = |part: &str| {
+        let digits = part.trim();
+        let digits = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+            .unwrap_or(digits);
+        u32::from_str_radix(digits, 16).map_err(|_| format!("Invalid hex number {:?}", part))
+    };
+
+    let (address, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("Invalid patch {:?} (expected ADDR=VAL)", text))?;
+    let address = hex(address)?;
+    if address as usize >= MEMORY_SIZE {
+        return Err(format!("Address {:X} is past the end of memory", address));
+    }
+    let value = u8::try_from(hex(value)?)
+        .map_err(|_| format!("Value {:?} doesn't fit in a byte", value.trim()))?;
+    Ok((address as usize, value))
+}
+
+pub fn load_program_rom(rom_path: &str) -> io::Result<[u8; MEMORY_SIZE]> {
+    load_program_rom_at(rom_path, ROM_START_ADDRESS)
+}
+
+/// Reads a ROM file into a memory image, starting at a custom address.
+///
+/// # Arguments
+///
+/// * `rom_path` - Path to the ROM file.
+/// * `start_address` - The address the ROM is loaded at, see `Chip8Builder::start_address`.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read, the start address is invalid, or the
+/// ROM doesn't fit into memory.
+pub fn load_program_rom_at(rom_path: &str, start_address: usize) -> io::Result<[u8; MEMORY_SIZE]> {
+    validate_start_address(start_address)?;
+    let mut buffer = [0u8; MEMORY_SIZE];
+    copy_program(&mut buffer, &fs::read(rom_path)?, start_address)?;
+    Ok(buffer)
+}
+
+/// Wraps an SDL2 drawing or window error, so it can be returned from the interpreter.
+fn display_error(error: Box<dyn Error>) -> Chip8Error {
+    Chip8Error::Display(error.to_string())
+}
+
+/// Hashes bytes with 64-bit FNV-1a, which is stable across platforms and releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Advances a SplitMix64 generator and returns its next value.
+///
+/// Unlike the generators of the rand crate, the sequence is stable across platforms
+/// and releases.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Lays out instructions as a program image, starting at the ROM start address.
+///
+/// The result can be passed to `Chip8::new` or `Chip8::headless` like a loaded ROM.
+///
+/// # Arguments
+///
+/// * `instructions` - The instructions of the program, in order.
+///
+/// # Panics
+///
+/// Panics if the program does not fit into memory.
+pub fn assemble_program(instructions: &[Instruction]) -> [u8; MEMORY_SIZE] {
+    let bytes: Vec<u8> = instructions
+        .iter()
+        .flat_map(|instruction| instruction.encode().to_be_bytes())
+        .collect();
+
+    let mut buffer = [0u8; MEMORY_SIZE];
+    copy_program(&mut buffer, &bytes, ROM_START_ADDRESS).expect("Program is too large");
+    buffer
+}
+
+/// Checks that programs can start at `start_address`: inside memory and even-aligned.
+fn validate_start_address(start_address: usize) -> io::Result<()> {
+    if start_address >= MEMORY_SIZE || !start_address.is_multiple_of(2) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid start address 0x{:X}", start_address),
+        ));
+    }
+    Ok(())
+}
+
+/// Copies program bytes into memory at the given start address.
+fn copy_program(
+    memory: &mut [u8; MEMORY_SIZE],
+    bytes: &[u8],
+    start_address: usize,
+) -> io::Result<()> {
+    let end = start_address + bytes.len();
+    if end > MEMORY_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("ROM is too large ({} bytes)", bytes.len()),
+        ));
+    }
+
+    memory[start_address..end].copy_from_slice(bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use instruction::decode;
+
+    fn create_test_chip8(mode: Mode) -> Chip8 {
+        Chip8::headless(mode, [0; MEMORY_SIZE])
+    }
+
+    #[test]
+    fn test_assemble_program_round_trip() {
+        let program = [
+            Instruction::LdVxByte { x: 0, kk: 0x05 },
+            Instruction::LdI { nnn: 0x000 },
+            Instruction::DrawSprite { x: 0, y: 1, n: 5 },
+            Instruction::AddVxByte { x: 0, kk: 0xFF },
+            Instruction::SeVxByte { x: 0, kk: 0x00 },
+            Instruction::Jp { nnn: 0x204 },
+            Instruction::Jp { nnn: 0x20C },
+        ];
+
+        let memory = assemble_program(&program);
+
+        let disassembled: Vec<Instruction> = memory[ROM_START_ADDRESS..]
+            .chunks_exact(2)
+            .take(program.len())
+            .map(|word| decode(u16::from_be_bytes([word[0], word[1]]), &Mode::Chip8))
+            .collect();
+        assert_eq!(disassembled, program);
+        assert!(memory[ROM_START_ADDRESS + program.len() * 2..]
+            .iter()
+            .all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_assemble_program_runs() {
+        let program = [
+            Instruction::LdVxByte { x: 1, kk: 0x20 },
+            Instruction::AddVxByte { x: 1, kk: 0x03 },
+        ];
+        let mut chip8 = Chip8::headless(Mode::Chip8, assemble_program(&program));
+
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+
+        assert_eq!(chip8.V[1], 0x23);
+    }
+
+    #[test]
+    fn test_fetch_opcode_wraps_pc() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.memory[MEMORY_SIZE - 1] = 0x12;
+        chip8.memory[0] = 0x34;
+        chip8.PC = MEMORY_SIZE - 1;
+
+        assert_eq!(chip8.fetch_opcode(), 0x1234);
+        assert_eq!(chip8.PC, 1);
+    }
+
+    #[test]
+    fn test_fetch_opcode_wraps_skipped_pc() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.PC = MEMORY_SIZE;
+
+        chip8.fetch_opcode();
+
+        assert_eq!(chip8.PC, 2);
+    }
+
+    #[test]
+    fn test_skip_wraps_pc() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.memory[MEMORY_SIZE - 4..MEMORY_SIZE - 2].copy_from_slice(&[0x30, 0x00]);
+        chip8.PC = MEMORY_SIZE - 4;
+
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.PC, 0);
+        // The wrapped PC survives a save state round trip
+        Chip8State::from_bytes(&chip8.save_state().to_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_sys_is_traced() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&events);
+        chip8.on_trace(move |event| log.borrow_mut().push(event));
+        chip8.memory[0x200..0x202].copy_from_slice(&[0x01, 0x23]);
+        let state = chip8.cpu_state();
+
+        chip8.emulate_cycle().unwrap();
+
+        // Only the program counter moves on to the next instruction
+        assert_eq!(chip8.cpu_state(), CpuState { PC: 0x202, ..state });
+        assert_eq!(
+            *events.borrow(),
+            [TraceEvent::Sys {
+                address: 0x200,
+                nnn: 0x123
+            }]
+        );
+    }
+
+    #[test]
+    fn test_timer_getters() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.V[3] = 42;
+
+        chip8.execute_raw(0xF315).unwrap();
+        chip8.execute_raw(0xF318).unwrap();
+        assert_eq!(chip8.delay_timer(), 42);
+        assert_eq!(chip8.sound_timer(), 42);
+
+        chip8.update_timers();
+        assert_eq!(chip8.delay_timer(), 41);
+        assert_eq!(chip8.sound_timer(), 41);
+    }
+
+    #[test]
+    fn test_timer_stats() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&events);
+        chip8.on_trace(move |event| log.borrow_mut().push(event));
+        // 0x200: LD V0, 3; 0x202: LD DT, V0
+        // 0x204: LD V1, DT; 0x206: SE V1, 0; 0x208: JP 0x204
+        chip8
+            .load_rom(&[0x60, 0x03, 0xF0, 0x15, 0xF1, 0x07, 0x31, 0x00, 0x12, 0x04])
+            .unwrap();
+
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+        for _ in 0..3 {
+            // Each frame polls the delay timer twice before it ticks
+            for _ in 0..6 {
+                chip8.emulate_cycle().unwrap();
+            }
+            chip8.update_timers();
+        }
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+
+        assert_eq!(chip8.PC, 0x20A);
+        assert_eq!(
+            chip8.timer_stats(),
+            TimerStats {
+                delay_reads: 7,
+                delay_writes: 1
+            }
+        );
+        assert_eq!(
+            *events.borrow(),
+            [TraceEvent::DelayTimerSet {
+                address: 0x202,
+                value: 3
+            }]
+        );
+
+        chip8.reset().unwrap();
+        assert_eq!(chip8.timer_stats(), TimerStats::default());
+    }
+
+    #[test]
+    fn test_exit_halts_until_reset() {
+        let mut chip8 = create_test_chip8(Mode::SuperChip);
+        // 0x200: EXIT, 0x202: LD V0, K
+        chip8.load_rom(&[0x00, 0xFD, 0xF0, 0x0A]).unwrap();
+
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+        assert!(chip8.halted);
+        assert_eq!(chip8.PC, 0x202);
+
+        chip8.halted = false;
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.release_key_register, Some(0));
+
+        chip8.halted = true;
+        chip8.reset().unwrap();
+        assert!(!chip8.halted);
+        assert_eq!(chip8.release_key_register, None);
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+    }
+
+    #[test]
+    fn test_save_state_keeps_halted() {
+        let mut chip8 = create_test_chip8(Mode::SuperChip);
+        chip8.load_rom(&[0x00, 0xFD]).unwrap();
+        chip8.emulate_cycle().unwrap();
+        let state = Chip8State::from_bytes(&chip8.save_state().to_bytes()).unwrap();
+        assert!(state.halted);
+
+        let mut restored = create_test_chip8(Mode::SuperChip);
+        restored.load_state(&state).unwrap();
+        assert!(restored.halted);
+
+        chip8.reset().unwrap();
+        restored.load_state(&chip8.save_state()).unwrap();
+        assert!(!restored.halted);
+    }
+
+    #[test]
+    fn test_strict_mode_reports_shift() {
+        let program = assemble_program(&[
+            Instruction::LdVxByte { x: 1, kk: 0x03 },
+            Instruction::Shr { x: 1, y: 1 },
+        ]);
+        let mut chip8 = Chip8::headless(Mode::Chip8, program);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&events);
+        chip8.on_trace(move |event| log.borrow_mut().push(event));
+
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+        assert!(events.borrow().is_empty());
+
+        chip8.set_strict(true);
+        chip8.reset().unwrap();
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(
+            *events.borrow(),
+            [TraceEvent::QuirkDependent {
+                address: 0x202,
+                quirk: Quirk::Shift
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sys_is_error_quirk() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8
+            .set_quirks(Quirks {
+                sys_is_error: true,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+
+        assert_eq!(
+            chip8.execute_raw(0x0123),
+            Err(Chip8Error::UnknownOpcode(0x0123))
+        );
+    }
+
+    #[test]
+    fn test_execute_raw_never_panics() {
+        for mode in [Mode::Chip8, Mode::SuperChip, Mode::XOChip] {
+            let mut chip8 = create_test_chip8(mode);
+            for opcode in 0..=u16::MAX {
+                let _ = chip8.execute_raw(opcode);
+            }
+
+            // Again from the state the first pass left behind
+            for opcode in (0..=u16::MAX).rev() {
+                let _ = chip8.execute_raw(opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_opcode_requires_mode() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        assert_eq!(
+            chip8.execute_raw(0x00FF),
+            Err(Chip8Error::OpcodeRequiresMode {
+                opcode: 0x00FF,
+                needed: Mode::SuperChip
+            })
+        );
+        assert_eq!(chip8.display.resolution(), Resolution::Low);
+        assert_eq!(
+            chip8.execute_raw(0x00FD),
+            Err(Chip8Error::OpcodeRequiresMode {
+                opcode: 0x00FD,
+                needed: Mode::SuperChip
+            })
+        );
+        assert!(!chip8.halted);
+
+        let mut chip8 = create_test_chip8(Mode::SuperChip);
+        assert_eq!(
+            chip8.execute_raw(0xF001),
+            Err(Chip8Error::OpcodeRequiresMode {
+                opcode: 0xF001,
+                needed: Mode::XOChip
+            })
+        );
+        assert_eq!(
+            chip8.execute_raw(0xF0FF),
+            Err(Chip8Error::UnknownOpcode(0xF0FF))
+        );
+    }
+
+    #[test]
+    fn test_execute_raw_errors() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        assert_eq!(chip8.execute_raw(0x00EE), Err(Chip8Error::StackUnderflow));
+        assert_eq!(
+            chip8.execute_raw(0x8008),
+            Err(Chip8Error::UnknownOpcode(0x8008))
+        );
+
+        for _ in 0..MAX_STACK_LEVELS {
+            chip8.execute_raw(0x2300).unwrap();
+        }
+        assert_eq!(chip8.execute_raw(0x2300), Err(Chip8Error::StackOverflow));
+    }
+
+    #[test]
+    fn test_memory_clamp_quirk() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.V[..3].copy_from_slice(&[0x11, 0x22, 0x33]);
+        chip8.I = 0xFFE;
+
+        chip8.execute_raw(0xF255).unwrap();
+        assert_eq!(chip8.memory[0xFFE..], [0x11, 0x33]);
+        assert_eq!(chip8.memory[0], CHIP8_FONTSET[0]);
+
+        chip8.I = 0xFFE;
+        chip8.execute_raw(0xF265).unwrap();
+        assert_eq!(chip8.V[..3], [0x11, 0x33, 0x33]);
+    }
+
+    #[test]
+    fn test_memory_wrap_quirk() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8
+            .set_quirks(Quirks {
+                memory_wrap: true,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        chip8.V[..3].copy_from_slice(&[0x11, 0x22, 0x33]);
+        chip8.I = 0xFFE;
+
+        chip8.execute_raw(0xF255).unwrap();
+        assert_eq!(chip8.memory[0xFFE..], [0x11, 0x22]);
+        assert_eq!(chip8.memory[0], 0x33);
+
+        chip8.I = 0xFFF;
+        chip8.V[0] = 123;
+        chip8.execute_raw(0xF033).unwrap();
+        assert_eq!(chip8.memory[0xFFF], 1);
+        assert_eq!(chip8.memory[..2], [2, 3]);
+    }
+
+    #[test]
+    fn test_vip_hires_quirk() {
+        let mut memory = [0; MEMORY_SIZE];
+        memory[ROM_START_ADDRESS..ROM_START_ADDRESS + 2].copy_from_slice(&[0x12, 0x60]);
+        memory[VIP_HIRES_START_ADDRESS..VIP_HIRES_START_ADDRESS + 2].copy_from_slice(&[0x02, 0x30]);
+
+        let mut chip8 = Chip8::headless(Mode::Chip8, memory);
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.display.resolution(), Resolution::Low);
+        assert_eq!(chip8.PC, 0x260);
+
+        let mut chip8 = Chip8::headless(Mode::Chip8, memory);
+        chip8
+            .set_quirks(Quirks {
+                vip_hires: true,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.display.resolution(), Resolution::VipHires);
+        assert_eq!((chip8.display.width(), chip8.display.height()), (64, 64));
+        assert_eq!(chip8.PC, VIP_HIRES_START_ADDRESS);
+
+        chip8.display.set_pixel(10, 60, 1);
+        chip8.emulate_cycle().unwrap();
+        assert!(!chip8.display.to_ascii().contains('#'));
+    }
+
+    #[test]
+    fn test_draw_sprite_past_end_of_memory() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.memory[0xFFE..].copy_from_slice(&[0x80, 0x40]);
+        chip8.I = 0xFFE;
+
+        let sprite_rows = |chip8: &Chip8| -> Vec<u8> {
+            (0..4)
+                .map(|y| (0..8).fold(0, |row, x| row << 1 | chip8.display.pixel(x, y)))
+                .collect()
+        };
+
+        // The rows past the end repeat the last byte
+        chip8.execute_raw(0xD004).unwrap();
+        assert_eq!(sprite_rows(&chip8), [0x80, 0x40, 0x40, 0x40]);
+
+        chip8
+            .set_quirks(Quirks {
+                memory_wrap: true,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        chip8.display.clear().unwrap();
+
+        // The rows past the end come from the font at address 0
+        chip8.execute_raw(0xD004).unwrap();
+        assert_eq!(
+            sprite_rows(&chip8),
+            [0x80, 0x40, CHIP8_FONTSET[0], CHIP8_FONTSET[1]]
+        );
+    }
+
+    #[test]
+    fn test_audio_init_failure() {
+        let program = assemble_program(&[
+            Instruction::LdVxByte { x: 0, kk: 30 },
+            Instruction::LdStVx { x: 0 },
+            Instruction::Jp { nnn: 0x204 },
+        ]);
+        let mut chip8 = Chip8::from_managers(
+            Mode::Chip8,
+            program,
+            Ok(DisplayManager::headless(Resolution::Low)),
+            Ok(Box::new(InputManager::headless())),
+            Err("No audio device".into()),
+        )
+        .expect("A missing audio device should fall back to silence");
+
+        // The sound timer still runs, the beep just isn't audible
+        assert_eq!(
+            run_headless(&mut chip8, DEFAULT_SPEED, 20),
+            RunOutcome::Completed
+        );
+        assert!(chip8.is_beeping());
+        assert_eq!(chip8.sound_timer, 10);
+
+        assert_eq!(
+            InitError::Audio("No audio device".into()).to_string(),
+            "Failed to open the audio device: No audio device"
+        );
+    }
+
+    #[test]
+    fn test_is_beeping() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.V[0] = 2;
+        chip8.op_fx18(0);
+
+        // The buzzer only starts on the next timer update
+        assert!(!chip8.is_beeping());
+
+        chip8.update_timers();
+        assert!(chip8.is_beeping());
+
+        chip8.update_timers();
+        assert!(!chip8.is_beeping());
+
+        chip8.update_timers();
+        assert!(!chip8.is_beeping());
+    }
+
+    #[test]
+    fn test_logic_vf_reset_quirk() {
+        // OR, AND, and XOR of 0b1100 and 0b1010
+        let results = [(0x8121, 0b1110), (0x8122, 0b1000), (0x8123, 0b0110)];
+
+        for reset_vf_on_logic in [false, true] {
+            for (opcode, result) in results {
+                let mut chip8 = create_test_chip8(Mode::Chip8);
+                chip8
+                    .set_quirks(Quirks {
+                        reset_vf_on_logic,
+                        ..Quirks::for_mode(&Mode::Chip8)
+                    })
+                    .unwrap();
+                chip8.V[0x1] = 0b1100;
+                chip8.V[0x2] = 0b1010;
+                chip8.V[0xF] = 0x55;
+
+                chip8.execute_opcode(opcode).unwrap();
+                assert_eq!(chip8.V[0x1], result, "opcode 0x{:04X}", opcode);
+                let vf = if reset_vf_on_logic { 0 } else { 0x55 };
+                assert_eq!(chip8.V[0xF], vf, "opcode 0x{:04X}", opcode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_logic_into_vf() {
+        for reset_vf_on_logic in [false, true] {
+            let mut chip8 = create_test_chip8(Mode::Chip8);
+            chip8
+                .set_quirks(Quirks {
+                    reset_vf_on_logic,
+                    ..Quirks::for_mode(&Mode::Chip8)
+                })
+                .unwrap();
+            chip8.V[0xF] = 0b1100;
+            chip8.V[0x2] = 0b1010;
+
+            // The reset happens after the result is written to VF
+            chip8.execute_opcode(0x8F21).unwrap();
+            let vf = if reset_vf_on_logic { 0 } else { 0b1110 };
+            assert_eq!(chip8.V[0xF], vf);
+        }
+    }
+
+    #[test]
+    fn test_shift_quirk() {
+        for shift_uses_vy in [false, true] {
+            let mut chip8 = create_test_chip8(Mode::Chip8);
+            chip8
+                .set_quirks(Quirks {
+                    shift_uses_vy,
+                    ..Quirks::for_mode(&Mode::Chip8)
+                })
+                .unwrap();
+
+            chip8.V[0x1] = 0b0000_0011;
+            chip8.V[0x2] = 0b1000_0100;
+            chip8.execute_opcode(0x8126).unwrap();
+            if shift_uses_vy {
+                assert_eq!((chip8.V[0x1], chip8.V[0xF]), (0b0100_0010, 0));
+            } else {
+                assert_eq!((chip8.V[0x1], chip8.V[0xF]), (0b0000_0001, 1));
+            }
+
+            chip8.V[0x1] = 0b0000_0011;
+            chip8.execute_opcode(0x812E).unwrap();
+            if shift_uses_vy {
+                assert_eq!((chip8.V[0x1], chip8.V[0xF]), (0b0000_1000, 1));
+            } else {
+                assert_eq!((chip8.V[0x1], chip8.V[0xF]), (0b0000_0110, 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_shift_into_vf() {
+        for shift_uses_vy in [false, true] {
+            let mut chip8 = create_test_chip8(Mode::Chip8);
+            chip8
+                .set_quirks(Quirks {
+                    shift_uses_vy,
+                    ..Quirks::for_mode(&Mode::Chip8)
+                })
+                .unwrap();
+
+            // The result would be 1, but VF must hold the shifted-out 0
+            chip8.V[0xF] = 0b10;
+            chip8.V[0xE] = 0b10;
+            chip8.execute_opcode(0x8FE6).unwrap();
+            assert_eq!(chip8.V[0xF], 0);
+
+            // The result would be 0, but VF must hold the shifted-out 1
+            chip8.V[0xF] = 0x80;
+            chip8.V[0xE] = 0x80;
+            chip8.execute_opcode(0x8FEE).unwrap();
+            assert_eq!(chip8.V[0xF], 1);
+        }
+    }
+
+    #[test]
+    fn test_compare_and_copy_with_vf() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.V[0x3] = 0x42;
+        chip8.V[0xF] = 0x42;
+
+        // 5xy0 and 9xy0 compare VF like any other register, as x or y
+        for (opcode, skips) in [
+            (0x5F30, true),
+            (0x53F0, true),
+            (0x5FF0, true),
+            (0x9F30, false),
+            (0x93F0, false),
+            (0x9FF0, false),
+        ] {
+            chip8.PC = 0x200;
+            chip8.execute_opcode(opcode).unwrap();
+            assert_eq!(
+                chip8.PC,
+                if skips { 0x202 } else { 0x200 },
+                "{:04X}",
+                opcode
+            );
+        }
+
+        chip8.V[0xF] = 0x24;
+        for opcode in [0x5F30, 0x53F0, 0x9F30, 0x93F0] {
+            chip8.PC = 0x200;
+            chip8.execute_opcode(opcode).unwrap();
+            let skips = opcode & 0xF000 == 0x9000;
+            assert_eq!(
+                chip8.PC,
+                if skips { 0x202 } else { 0x200 },
+                "{:04X}",
+                opcode
+            );
+        }
+
+        // 8xy0 copies into and out of VF
+        chip8.execute_opcode(0x83F0).unwrap();
+        assert_eq!(chip8.V[0x3], 0x24);
+        chip8.V[0x4] = 0x99;
+        chip8.execute_opcode(0x8F40).unwrap();
+        assert_eq!(chip8.V[0xF], 0x99);
+        chip8.execute_opcode(0x8FF0).unwrap();
+        assert_eq!(chip8.V[0xF], 0x99);
+    }
+
+    #[test]
+    fn test_arithmetic_with_vf() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        // VF as an operand is read before the flag overwrites it
+        chip8.V[0xF] = 0xFF;
+        chip8.V[0x1] = 0x01;
+        chip8.execute_opcode(0x81F4).unwrap();
+        assert_eq!((chip8.V[0x1], chip8.V[0xF]), (0x00, 1));
+
+        chip8.V[0xF] = 0x10;
+        chip8.V[0x1] = 0x20;
+        chip8.execute_opcode(0x81F5).unwrap();
+        assert_eq!((chip8.V[0x1], chip8.V[0xF]), (0x10, 1));
+
+        // With VF as the destination, the flag wins over the result
+        chip8.V[0xF] = 0x01;
+        chip8.V[0x1] = 0x02;
+        chip8.execute_opcode(0x8F14).unwrap();
+        assert_eq!(chip8.V[0xF], 0);
+
+        chip8.V[0xF] = 0x01;
+        chip8.execute_opcode(0x8F15).unwrap();
+        assert_eq!(chip8.V[0xF], 0);
+
+        chip8.V[0xF] = 0x01;
+        chip8.execute_opcode(0x8F17).unwrap();
+        assert_eq!(chip8.V[0xF], 1);
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        let add = chip8.explain(0x8AB4);
+        assert!(add.contains("carry"), "{}", add);
+        assert!(add.contains("Modifies VA, VF."), "{}", add);
+
+        let draw = chip8.explain(0xD015);
+        assert!(draw.contains("collision"), "{}", draw);
+        assert!(draw.contains("VF"), "{}", draw);
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+
+        // VF is only reset by logic instructions with the reset_vf_on_logic quirk
+        assert!(chip8.explain(0x8121).contains("resets VF"));
+        chip8
+            .set_quirks(Quirks {
+                reset_vf_on_logic: false,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        assert!(!chip8.explain(0x8121).contains("VF"));
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("emul8tor-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_detect_mode() {
+        // CLS, LD V0, 0x05, DRW V0, V0, 5, JP 0x206
+        let chip8 = [0x00, 0xE0, 0x60, 0x05, 0xD0, 0x05, 0x12, 0x06];
+        assert!(detect_mode(&chip8) == Mode::Chip8);
+
+        // HIRES, LD HF, V0
+        let super_chip = [0x00, 0xFF, 0xF0, 0x30, 0x12, 0x04];
+        assert!(detect_mode(&super_chip) == Mode::SuperChip);
+        // LD R, V3
+        assert!(detect_mode(&[0xF3, 0x75]) == Mode::SuperChip);
+
+        // HIRES, PLANE 3
+        let xo_chip = [0x00, 0xFF, 0xF3, 0x01, 0x12, 0x04];
+        assert!(detect_mode(&xo_chip) == Mode::XOChip);
+        // LD I, long 0x1234
+        assert!(detect_mode(&[0xF0, 0x00, 0x12, 0x34]) == Mode::XOChip);
+        // SCU 4
+        assert!(detect_mode(&[0x00, 0xD4]) == Mode::XOChip);
+
+        assert!(detect_mode(&[]) == Mode::Chip8);
+    }
+
+    #[test]
+    fn test_scan_rom_dir() {
+        let dir = temp_path("roms");
+        fs::create_dir_all(dir.join("nested.ch8")).unwrap();
+        for name in ["b.ch8", "a.C8", "c.xo", "notes.txt", "noext"] {
+            fs::write(dir.join(name), [0x00, 0xE0]).unwrap();
+        }
+
+        let roms = scan_rom_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            roms.unwrap(),
+            [dir.join("a.C8"), dir.join("b.ch8"), dir.join("c.xo")]
+        );
+        assert!(scan_rom_dir(&dir).is_err());
+    }
+
+    #[test]
+    fn test_step_over() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        // 0x200: CALL 0x300, 0x202: LD V0, 1
+        // 0x300: CALL 0x400, 0x302: RET, 0x400: LD V1, 2, 0x402: RET
+        chip8.load_rom(&[0x23, 0x00, 0x60, 0x01]).unwrap();
+        chip8.memory[0x300..0x304].copy_from_slice(&[0x24, 0x00, 0x00, 0xEE]);
+        chip8.memory[0x400..0x404].copy_from_slice(&[0x61, 0x02, 0x00, 0xEE]);
+
+        chip8.step_over().unwrap();
+        assert_eq!(chip8.PC, 0x202);
+        assert_eq!(chip8.V[1], 2);
+        assert!(chip8.call_stack().is_empty());
+
+        chip8.step_over().unwrap();
+        assert_eq!(chip8.PC, 0x204);
+        assert_eq!(chip8.V[0], 1);
+    }
+
+    #[test]
+    fn test_step_over_runaway_call() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        // 0x200: CALL 0x300, 0x300: JP 0x300
+        chip8.load_rom(&[0x23, 0x00]).unwrap();
+        chip8.memory[0x300..0x302].copy_from_slice(&[0x13, 0x00]);
+
+        chip8.step_over().unwrap();
+        assert_eq!(chip8.PC, 0x300);
+        assert_eq!(chip8.call_stack(), [0x202]);
+    }
+
+    #[test]
+    fn test_tick_cpu_and_timers_only() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        // 0x200: LD V0, 1
+        chip8.load_rom(&[0x60, 0x01]).unwrap();
+        chip8.delay_timer = 10;
+
+        chip8.tick_timers_only();
+        assert_eq!(chip8.delay_timer(), 9);
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+
+        chip8.tick_cpu_only().unwrap();
+        assert_eq!(chip8.delay_timer(), 9);
+        assert_eq!(chip8.PC, 0x202);
+        assert_eq!(chip8.V[0], 1);
+    }
+
+    #[test]
+    fn test_speed() {
+        let chip8 = create_test_chip8(Mode::Chip8);
+        assert_eq!(chip8.speed(), DEFAULT_SPEED);
+
+        chip8.set_speed(1500);
+        assert_eq!(chip8.speed(), 1500);
+
+        let handle = chip8.speed_handle();
+        handle.store(300, Ordering::Relaxed);
+        assert_eq!(chip8.speed(), 300);
+
+        let mut scheduler = Scheduler::new(clock::MockClock::new(), DEFAULT_SPEED, FRAME_RATE);
+        scheduler.set_speed(chip8.speed());
+        assert_eq!(
+            scheduler.cycle_duration(),
+            Duration::from_secs_f64(1.0 / 300.0)
+        );
+    }
+
+    #[test]
+    fn test_stop_handle() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        // 0x200: JP 0x200
+        chip8.load_rom(&[0x12, 0x00]).unwrap();
+        assert!(!chip8.should_stop());
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        chip8.set_stop_handle(Arc::clone(&stop_flag));
+        assert!(!chip8.should_stop());
+
+        stop_flag.store(true, Ordering::Relaxed);
+        assert!(chip8.should_stop());
+        assert!(matches!(
+            run_headless(&mut chip8, 700, 10),
+            RunOutcome::Quit
+        ));
+    }
+
+    #[test]
+    fn test_scroll_selected_plane() {
+        let mut chip8 = create_test_chip8(Mode::XOChip);
+        chip8.display.set_pixel(0, 4, 0b11);
+        // Only the second plane is scrolled
+        chip8.execute_raw(0xF201).unwrap();
+
+        chip8.execute_raw(0x00C3).unwrap();
+        assert_eq!(chip8.display.pixel(0, 4), 0b01);
+        assert_eq!(chip8.display.pixel(0, 7), 0b10);
+
+        chip8.execute_raw(0x00D5).unwrap();
+        assert_eq!(chip8.display.pixel(0, 4), 0b01);
+        assert_eq!(chip8.display.pixel(0, 2), 0b10);
+        assert_eq!(chip8.display.pixel(0, 7), 0);
+
+        // Scrolling by 0 rows changes nothing
+        chip8.display.render().unwrap();
+        chip8.execute_raw(0x00C0).unwrap();
+        chip8.execute_raw(0x00D0).unwrap();
+        assert!(!chip8.display.is_dirty());
+        assert_eq!(chip8.display.pixel(0, 2), 0b10);
+    }
+
+    #[test]
+    fn test_frame_skip() {
+        let mut frame_skip = FrameSkip::new(3);
+        let skipped: Vec<bool> = [true, false, true, true, true, true, true]
+            .into_iter()
+            .map(|dirty| frame_skip.skip(dirty))
+            .collect();
+        assert_eq!(skipped, [true, false, true, false, true, true, false]);
+
+        let mut frame_skip = FrameSkip::new(0);
+        assert!(!frame_skip.skip(true));
+        assert!(!frame_skip.skip(false));
+        assert!(!frame_skip.skip(true));
+    }
+
+    #[test]
+    fn test_rate_counter() {
+        let clock = clock::MockClock::new();
+        let mut rates = RateCounter::new(clock.now());
+        for _ in 0..30 {
+            rates.frame();
+        }
+        for _ in 0..350 {
+            rates.cycle();
+        }
+
+        // The rates only change once a second has passed
+        clock.advance(Duration::from_millis(500));
+        rates.update(clock.now());
+        assert_eq!((rates.fps, rates.ips), (0, 0));
+
+        clock.advance(Duration::from_millis(500));
+        rates.update(clock.now());
+        assert_eq!((rates.fps, rates.ips), (30, 350));
+
+        rates.frame();
+        clock.advance(Duration::from_secs(2));
+        rates.update(clock.now());
+        assert_eq!((rates.fps, rates.ips), (1, 0));
+    }
+
+    #[test]
+    fn test_apply_patches() {
+        let program = assemble_program(&[Instruction::LdVxByte { x: 0, kk: 0x05 }]);
+        let mut chip8 = Chip8::headless(Mode::Chip8, program);
+
+        chip8
+            .apply_patches(&[(0x201, 0x63), (0x300, 0xAB)])
+            .unwrap();
+        assert_eq!(chip8.memory[0x201], 0x63);
+        assert_eq!(chip8.memory[0x300], 0xAB);
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.V[0], 0x63);
+
+        // Nothing is written if any address is out of bounds
+        assert_eq!(
+            chip8.apply_patches(&[(0x202, 0x12), (MEMORY_SIZE, 0)]),
+            Err(Chip8Error::MemoryOutOfBounds {
+                address: MEMORY_SIZE
+            })
+        );
+        assert_eq!(chip8.memory[0x202], 0);
+    }
+
+    #[test]
+    fn test_parse_patch() {
+        assert_eq!(parse_patch("2A4=FF"), Ok((0x2A4, 0xFF)));
+        assert_eq!(parse_patch("0x2a4 = 0x0f"), Ok((0x2A4, 0x0F)));
+        assert!(parse_patch("2A4").is_err());
+        assert!(parse_patch("XYZ=1").is_err());
+        assert!(parse_patch("1000=1").is_err());
+        assert!(parse_patch("200=100").is_err());
+    }
+
+    #[test]
+    fn test_rate_counter_ignores_overlays() {
+        let clock = clock::MockClock::new();
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.set_hud(true);
+        chip8.set_stats(true);
+        let mut frame_skip = FrameSkip::new(1);
+        let mut rates = RateCounter::new(clock.now());
+
+        // Only the frames in which the program drew count, not the overlay redraws
+        for frame in 0..60 {
+            if frame % 4 == 0 {
+                chip8.display.set_pixel(0, 0, 1);
+            }
+            clock.advance(Duration::from_micros(16_667));
+            present_frame(&mut chip8, &mut frame_skip, &mut rates, clock.now()).unwrap();
+        }
+        clock.advance(Duration::from_millis(1));
+        rates.update(clock.now());
+        assert_eq!(rates.fps, 15);
+    }
+
+    #[test]
+    fn test_state_hash() {
+        let program = assemble_program(&[
+            Instruction::LdVxByte { x: 0, kk: 0x05 },
+            Instruction::LdFVx { x: 0 },
+            Instruction::DrawSprite { x: 1, y: 1, n: 5 },
+            Instruction::Call { nnn: 0x208 },
+            Instruction::LdDtVx { x: 0 },
+        ]);
+        let mut first = Chip8::headless(Mode::Chip8, program);
+        let mut second = Chip8::headless(Mode::Chip8, program);
+        for _ in 0..5 {
+            first.emulate_cycle().unwrap();
+            second.emulate_cycle().unwrap();
+        }
+        assert_eq!(first.state_hash(), second.state_hash());
+
+        second.set_register(3, 1).unwrap();
+        assert_ne!(first.state_hash(), second.state_hash());
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), FNV_OFFSET_BASIS);
+        assert_eq!(fnv1a(b"a"), 0xAF63_DC4C_8601_EC8C);
+    }
+
+    #[test]
+    fn test_register() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        chip8.set_register(10, 0x42).unwrap();
+        assert_eq!(chip8.register(10), Ok(0x42));
+        assert_eq!(chip8.V[0xA], 0x42);
+
+        assert_eq!(
+            chip8.set_register(16, 0x42),
+            Err(Chip8Error::InvalidRegister(16))
+        );
+        assert_eq!(chip8.register(16), Err(Chip8Error::InvalidRegister(16)));
+    }
+
+    #[test]
+    fn test_call_stack() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        // 0x200: CALL 0x300, 0x300: CALL 0x400
+        chip8.load_rom(&[0x23, 0x00]).unwrap();
+        chip8.memory[0x300..0x302].copy_from_slice(&[0x24, 0x00]);
+        assert!(chip8.call_stack().is_empty());
+
+        chip8.emulate_cycle().unwrap();
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.call_stack(), [0x202, 0x302]);
+        assert_eq!(chip8.PC, 0x400);
+    }
+
+    #[test]
+    fn test_peek_opcode() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.load_rom(&[0x61, 0x2A, 0x00, 0xE0]).unwrap();
+
+        assert_eq!(chip8.peek_opcode(), 0x612A);
+        assert_eq!(chip8.peek_disassembly(), "LD V1, 0x2A");
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.peek_opcode(), 0x00E0);
+
+        // Reads wrap around the end of memory
+        chip8.PC = MEMORY_SIZE - 1;
+        chip8.memory[MEMORY_SIZE - 1] = 0x12;
+        chip8.memory[0] = 0x34;
+        assert_eq!(chip8.peek_opcode(), 0x1234);
+    }
+
+    #[test]
+    fn test_list_modes() {
+        let modes = list_modes();
+
+        for name in ["Chip8", "SuperChip", "XOChip"] {
+            assert!(modes.contains(name), "{}", modes);
+        }
+        assert_eq!(modes.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_bench() {
+        let mut chip8 = Chip8::builder(Mode::Chip8)
+            .program(&[0x70, 0x01, 0x12, 0x00])
+            .build_headless()
+            .unwrap();
+
+        let report = bench(&mut chip8, 600, 1000).unwrap();
+        assert_eq!(report.instructions, 1000);
+        assert_eq!(report.frames, 100);
+        assert_eq!(chip8.V[0], (500 % 256) as u8);
+    }
+
+    #[test]
+    fn test_run_fixed() {
+        let program = assemble_program(&[
+            Instruction::LdVxByte { x: 0, kk: 5 },
+            Instruction::LdDtVx { x: 0 },
+            Instruction::LdVxDt { x: 1 },
+            Instruction::SeVxByte { x: 1, kk: 0 },
+            Instruction::Jp { nnn: 0x204 },
+            Instruction::Exit,
+        ]);
+
+        // 2 setup instructions, 6 passes of the 3 instruction loop until the delay
+        // timer reads 0, then the skip and EXIT
+        let mut chip8 = Chip8::headless(Mode::SuperChip, program);
+        assert_eq!(run_fixed(&mut chip8, 1000, 3), Ok(20));
+        assert_eq!(chip8.delay_timer, 0);
+        assert_eq!(chip8.PC, 0x20C);
+
+        // Stopping early leaves the delay loop running
+        let mut chip8 = Chip8::headless(Mode::SuperChip, program);
+        assert_eq!(run_fixed(&mut chip8, 10, 3), Ok(10));
+        assert_eq!(chip8.delay_timer, 2);
+    }
+
+    #[test]
+    fn test_render_frame() {
+        let program = assemble_program(&[
+            Instruction::Hires,
+            Instruction::DrawSprite { x: 0, y: 0, n: 5 },
+            Instruction::Jp { nnn: 0x204 },
+        ]);
+        let mut chip8 = Chip8::headless(Mode::SuperChip, program);
+
+        let path = temp_path("frame.png");
+        render_frame(&mut chip8, 600, 10, &path).unwrap();
+        let png = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(png[1..4], *b"PNG");
+        assert_eq!(png[16..20], 128u32.to_be_bytes());
+        assert_eq!(png[20..24], 64u32.to_be_bytes());
+    }
+
+    #[test]
+    fn test_save_state_round_trip() {
+        let mut chip8 = create_test_chip8(Mode::SuperChip);
+        chip8.V[0x3] = 0x42;
+        chip8.I = 0x123;
+        chip8.op_2nnn(0x400).unwrap();
+        chip8.delay_timer = 30;
+        chip8.memory[0x300] = 0xAB;
+        chip8.op_00ff().unwrap();
+        chip8.op_dxyn(0, 1, 5);
+
+        let path = temp_path("round-trip.sav");
+        chip8.save_to_file(&path).unwrap();
+
+        let mut restored = create_test_chip8(Mode::SuperChip);
+        restored.load_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(restored.save_state() == chip8.save_state());
+        assert_eq!(restored.display.resolution(), Resolution::High);
+        assert_eq!(restored.display.to_ascii(), chip8.display.to_ascii());
+    }
+
+    #[test]
+    fn test_load_state_rejects_bad_header() {
+        let chip8 = create_test_chip8(Mode::Chip8);
+        let mut bytes = chip8.save_state().to_bytes();
+
+        let path = temp_path("bad-magic.sav");
+        bytes[0] = b'X';
+        fs::write(&path, &bytes).unwrap();
+        let mut target = create_test_chip8(Mode::Chip8);
+        target.V[0] = 7;
+        let error = target.load_from_file(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("Not a save state"), "{}", error);
+        assert_eq!(target.V[0], 7);
+
+        bytes[0] = b'E';
+        bytes[5] += 1;
+        fs::write(&path, &bytes).unwrap();
+        let error = target.load_from_file(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(error.to_string().contains("version 3"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_state_rejects_invalid_state() {
+        let chip8 = create_test_chip8(Mode::Chip8);
+        let mut target = create_test_chip8(Mode::Chip8);
+        target.V[0] = 7;
+
+        let corruptions: [fn(&mut Chip8State); 5] = [
+            |state| state.width = 0,
+            |state| {
+                state.pixels.pop();
+            },
+            |state| state.cpu.PC = MEMORY_SIZE,
+            |state| state.cpu.SP = MAX_STACK_LEVELS + 1,
+            |state| state.release_key_register = Some(V_COUNT),
+        ];
+        for corrupt in corruptions {
+            let mut state = chip8.save_state();
+            corrupt(&mut state);
+            let error = target.load_state(&state).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(target.V[0], 7);
+        }
+    }
+
+    #[test]
+    fn test_rom_menu_without_roms() {
+        let chip8 = create_test_chip8(Mode::Chip8);
+        assert_eq!(run_rom_menu(chip8, DEFAULT_SPEED, &[]), RunOutcome::Quit);
+    }
+
+    #[test]
+    fn test_watchdog_self_jump() {
+        let mut chip8 = Chip8::builder(Mode::Chip8)
+            .program(&[0x12, 0x00])
+            .max_cycles(1000)
+            .build_headless()
+            .unwrap();
+
+        assert_eq!(run_headless(&mut chip8, 600, 60), RunOutcome::Watchdog);
+        assert_eq!(chip8.PC, 0x200);
+    }
+
+    #[test]
+    fn test_watchdog_idle_loop() {
+        // Jumps back and forth between 0x200 and 0x202 without drawing
+        let mut chip8 = Chip8::builder(Mode::Chip8)
+            .program(&[0x12, 0x02, 0x12, 0x00])
+            .max_cycles(50)
+            .build_headless()
+            .unwrap();
+        assert_eq!(run_headless(&mut chip8, 600, 60), RunOutcome::Watchdog);
+
+        // Drawing counts as progress
+        let program = assemble_program(&[Instruction::Cls, Instruction::Jp { nnn: 0x200 }]);
+        let mut chip8 = Chip8::headless(Mode::Chip8, program);
+        chip8.set_max_cycles(Some(50));
+        assert_eq!(run_headless(&mut chip8, 600, 60), RunOutcome::Completed);
+
+        // Resetting clears the progress seen before
+        chip8.reset().unwrap();
+        assert_eq!((chip8.last_display_changes, chip8.last_keys), (0, 0));
+    }
+
+    #[test]
+    fn test_lores_doubled_pixels() {
+        let mut chip8 = create_test_chip8(Mode::SuperChip);
+        chip8
+            .set_quirks(Quirks {
+                lores_doubled_pixels: true,
+                ..Quirks::for_mode(&Mode::SuperChip)
+            })
+            .unwrap();
+        assert_eq!(chip8.display.resolution(), Resolution::High);
+
+        // Draw the top row of the "0" glyph at lores (1, 1)
+        chip8.I = 0;
+        chip8.V[0] = 1;
+        chip8.V[1] = 1;
+        chip8.op_dxyn(0, 1, 1);
+
+        for (x, y) in [(2, 2), (3, 2), (2, 3), (3, 3), (9, 3)] {
+            assert_eq!(chip8.display.pixel(x, y), 1, "pixel ({}, {})", x, y);
+        }
+        for (x, y) in [(1, 1), (1, 2), (10, 2), (2, 4)] {
+            assert_eq!(chip8.display.pixel(x, y), 0, "pixel ({}, {})", x, y);
+        }
+
+        // Switching to hires keeps the display contents
+        chip8.execute_opcode(0x00FF).unwrap();
+        assert_eq!(chip8.display.pixel(2, 2), 1);
+    }
+
+    #[test]
+    fn test_collision_planes() {
+        let mut chip8 = create_test_chip8(Mode::XOChip);
+        chip8.I = 0;
+
+        chip8.execute_opcode(0xF201).unwrap();
+        chip8.op_dxyn(0, 1, 5);
+        assert_eq!(chip8.V[0xF], 0);
+        assert_eq!(chip8.last_collision_planes(), 0);
+
+        chip8.op_dxyn(0, 1, 5);
+        assert_eq!(chip8.V[0xF], 1);
+        assert_eq!(chip8.last_collision_planes() & 0b10, 0b10);
+        assert_eq!(chip8.last_collision_planes() & 0b01, 0);
+    }
+
+    #[test]
+    fn test_draw_to_both_planes() {
+        let mut chip8 = create_test_chip8(Mode::XOChip);
+        // One byte for the first plane, followed by one byte for the second plane
+        chip8.memory[0x300..0x302].copy_from_slice(&[0b1100_0000, 0b1010_0000]);
+        chip8.I = 0x300;
+
+        chip8.execute_opcode(0xF301).unwrap();
+        chip8.op_dxyn(0, 1, 1);
+        let row = |chip8: &Chip8| {
+            (0..4)
+                .map(|x| chip8.display.pixel(x, 0))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(row(&chip8), [0b11, 0b01, 0b10, 0]);
+        assert_eq!(chip8.V[0xF], 0);
+        assert_eq!(chip8.I, 0x300);
+
+        // Drawing to the first plane alone reads only the first byte
+        chip8.execute_opcode(0xF101).unwrap();
+        chip8.op_dxyn(0, 1, 1);
+        assert_eq!(row(&chip8), [0b10, 0, 0b10, 0]);
+        assert_eq!(chip8.last_collision_planes(), 0b01);
+
+        // Drawing to both planes again collides only where the second plane is set
+        chip8.execute_opcode(0xF301).unwrap();
+        chip8.op_dxyn(0, 1, 1);
+        assert_eq!(row(&chip8), [0b01, 0b01, 0, 0]);
+        assert_eq!(chip8.last_collision_planes(), 0b10);
+        assert_eq!(chip8.V[0xF], 1);
+    }
+
+    #[test]
+    fn test_on_collision() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        let collisions = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&collisions);
+        chip8.on_collision(move || counter.set(counter.get() + 1));
+
+        // Draw the "0" glyph three times at the same position
+        chip8.I = 0;
+        chip8.op_dxyn(0, 1, 5);
+        assert_eq!(collisions.get(), 0);
+
+        chip8.op_dxyn(0, 1, 5);
+        assert_eq!(chip8.V[0xF], 1);
+        assert_eq!(collisions.get(), 1);
+
+        chip8.op_dxyn(0, 1, 5);
+        assert_eq!(chip8.V[0xF], 0);
+        assert_eq!(collisions.get(), 1);
+    }
+
+    #[test]
+    fn test_on_sound_start_and_stop() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        let starts = Rc::new(Cell::new(0));
+        let stops = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&starts);
+        chip8.on_sound_start(move || counter.set(counter.get() + 1));
+        let counter = Rc::clone(&stops);
+        chip8.on_sound_stop(move || counter.set(counter.get() + 1));
+
+        chip8.sound_timer = 2;
+        chip8.update_timers();
+        assert_eq!((starts.get(), stops.get()), (1, 0));
+
+        chip8.update_timers();
+        assert_eq!((starts.get(), stops.get()), (1, 1));
+
+        chip8.update_timers();
+        assert_eq!((starts.get(), stops.get()), (1, 1));
+    }
+
+    #[test]
+    fn test_on_frame() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        let frame = Rc::new(RefCell::new(None));
+        let last_frame = Rc::clone(&frame);
+        chip8.on_frame(move |buffer, width, height| {
+            *last_frame.borrow_mut() = Some((buffer.to_vec(), width, height));
+        });
+
+        chip8.I = 0;
+        chip8.op_dxyn(0, 1, 5);
+        chip8.render().unwrap();
+
+        let (buffer, width, height) = frame.borrow_mut().take().unwrap();
+        assert_eq!((width, height), (64, 32));
+        assert_eq!(buffer.len(), 64 * 32 * 4);
+        assert_eq!(buffer[..4], [0xFF, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_5xy2_5xy3_ascending_range() {
+        let mut chip8 = create_test_chip8(Mode::XOChip);
+        chip8.V[1..4].copy_from_slice(&[0x11, 0x22, 0x33]);
+        chip8.I = 0x300;
+
+        chip8.execute_raw(0x5132).unwrap();
+        assert_eq!(chip8.memory[0x300..0x304], [0x11, 0x22, 0x33, 0x00]);
+        assert_eq!(chip8.I, 0x300);
+
+        chip8.I = 0x301;
+        chip8.execute_raw(0x5463).unwrap();
+        assert_eq!(chip8.V[4..7], [0x22, 0x33, 0x00]);
+        assert_eq!(chip8.I, 0x301);
+    }
+
+    #[test]
+    fn test_5xy2_5xy3_descending_range() {
+        let mut chip8 = create_test_chip8(Mode::XOChip);
+        chip8.V[1..4].copy_from_slice(&[0x11, 0x22, 0x33]);
+        chip8.I = 0x300;
+
+        chip8.execute_raw(0x5312).unwrap();
+        assert_eq!(chip8.memory[0x300..0x303], [0x33, 0x22, 0x11]);
+
+        chip8.execute_raw(0x5973).unwrap();
+        assert_eq!(chip8.V[7..10], [0x11, 0x22, 0x33]);
+        assert_eq!(chip8.I, 0x300);
+    }
+
+    #[test]
+    fn test_fx55_fx65_increment_by_mode() {
+        let expected = [
+            (Mode::Chip8, 0x304),
+            (Mode::SuperChip, 0x300),
+            (Mode::XOChip, 0x304),
+        ];
+        for (mode, i) in expected {
+            let mut chip8 = create_test_chip8(mode);
+            // The increment depends on x, not on the value of Vx
+            chip8.V[..4].copy_from_slice(&[0x11, 0x22, 0x33, 200]);
+            chip8.I = 0x300;
+            chip8.execute_opcode(0xF355).unwrap();
+            assert_eq!(
+                chip8.memory[0x300..0x304],
+                [0x11, 0x22, 0x33, 200],
+                "{:?}",
+                mode
+            );
+            assert_eq!(chip8.I, i, "{:?}", mode);
+
+            chip8.V = [0; V_COUNT];
+            chip8.I = 0x300;
+            chip8.execute_opcode(0xF365).unwrap();
+            assert_eq!(chip8.V[..4], [0x11, 0x22, 0x33, 200], "{:?}", mode);
+            assert_eq!(chip8.I, i, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_load_store_increment_quirk() {
+        let expected = [
+            (LoadStoreIncrement::None, 0x300),
+            (LoadStoreIncrement::X, 0x303),
+            (LoadStoreIncrement::XPlusOne, 0x304),
+        ];
+        for (load_store_increment, i) in expected {
+            let mut chip8 = create_test_chip8(Mode::Chip8);
+            chip8
+                .set_quirks(Quirks {
+                    load_store_increment,
+                    ..Quirks::for_mode(&Mode::Chip8)
+                })
+                .unwrap();
+
+            chip8.I = 0x300;
+            chip8.op_fx55(3);
+            assert_eq!(chip8.I, i, "{:?}", load_store_increment);
+
+            chip8.I = 0x300;
+            chip8.op_fx65(3);
+            assert_eq!(chip8.I, i, "{:?}", load_store_increment);
+        }
+    }
+
+    #[test]
+    fn test_run_headless_scripted_input() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        #[rustfmt::skip]
+        chip8.load_rom(&[
+            0x60, 0x05, // LD V0, 0x05
+            0xE0, 0x9E, // SKP V0
+            0x12, 0x02, // JP 0x202
+        ]).unwrap();
+        chip8.set_input(Box::new(ScriptedInput::new().press(2, 0x5)));
+
+        // Two cycles per frame, so each frame ends right after SKP
+        run_headless(&mut chip8, 2 * FRAME_RATE, 2);
+        assert_eq!(chip8.PC, 0x204);
+
+        run_headless(&mut chip8, 2 * FRAME_RATE, 1);
+        assert_eq!(chip8.PC, 0x206);
+    }
+
+    #[test]
+    fn test_run_headless_with_input() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        #[rustfmt::skip]
+        chip8.load_rom(&[
+            0xF0, 0x0A, // LD V0, K
+            0x71, 0x01, // ADD V1, 0x01
+            0x12, 0x04, // JP 0x204
+        ]).unwrap();
+        let input = ScriptedInput::new().press(3, 0xA).release(5, 0xA);
+
+        let (state, outcome) = run_headless_with_input(&mut chip8, input, 10);
+
+        assert_eq!(outcome, RunOutcome::Completed);
+        assert_eq!(state.V[0], 0xA);
+        assert_eq!(state.V[1], 1);
+        assert_eq!(state.PC, 0x204);
+    }
+
+    #[test]
+    fn test_fx0a_accept_held_quirk() {
+        // The key is held from frame 0, before LD V0, K runs, and released in frame 3
+        let rom = [0xF0, 0x0A, 0x12, 0x02];
+        let input = || ScriptedInput::new().press(0, 0x7).release(3, 0x7);
+
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.load_rom(&rom).unwrap();
+        let (state, _) = run_headless_with_input(&mut chip8, input(), 6);
+        assert_eq!(state.V[0], 0x7);
+        assert_eq!(chip8.release_key_register, None);
+
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8
+            .set_quirks(Quirks {
+                fx0a_accept_held: false,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        chip8.load_rom(&rom).unwrap();
+        let (state, _) = run_headless_with_input(&mut chip8, input(), 6);
+        assert_eq!(state.V[0], 0);
+        assert_eq!(chip8.release_key_register, Some(0));
+    }
+
+    #[test]
+    fn test_mode() {
+        let chip8 = create_test_chip8(Mode::XOChip);
+        assert_eq!(*chip8.mode(), Mode::XOChip);
+    }
+
+    #[test]
+    fn test_mode_traits() {
+        let mut speeds = std::collections::HashMap::new();
+        speeds.insert(Mode::Chip8, 700);
+        speeds.insert(Mode::XOChip, 1000);
+
+        assert_eq!(Mode::default(), Mode::Chip8);
+        assert_eq!(speeds[&Mode::default()], 700);
+        assert_eq!(speeds.get(&Mode::SuperChip), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        chip8.apply(Instruction::AddVxByte { x: 0, kk: 5 }).unwrap();
+        assert_eq!(chip8.V[0], 5);
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+
+        chip8.apply(Instruction::Call { nnn: 0x300 }).unwrap();
+        assert_eq!(chip8.PC, 0x300);
+        assert_eq!(
+            chip8.apply(Instruction::Unknown { opcode: 0xFFFF }),
+            Err(Chip8Error::UnknownOpcode(0xFFFF))
+        );
+    }
+
+    #[test]
+    fn test_apply_invalid_register() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        assert_eq!(
+            chip8.apply(Instruction::AddVxByte { x: 16, kk: 1 }),
+            Err(Chip8Error::InvalidRegister(16))
+        );
+        assert_eq!(
+            chip8.apply(Instruction::LdVxVy { x: 0, y: 20 }),
+            Err(Chip8Error::InvalidRegister(20))
+        );
+        assert_eq!(chip8.V, [0; V_COUNT]);
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+    }
+
+    /// Draws a solid 8x4 sprite with its origin at the given register values and
+    /// returns the coordinates of the set pixels, row by row.
+    fn draw_solid_sprite(clip_sprites: bool, vx: u8, vy: u8) -> Vec<(usize, usize)> {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8
+            .set_quirks(Quirks {
+                clip_sprites,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        chip8.memory[0x300..0x304].fill(0xFF);
+        chip8.I = 0x300;
+        chip8.V[0] = vx;
+        chip8.V[1] = vy;
+        chip8.execute_raw(0xD014).unwrap();
+
+        let (width, height) = (chip8.display.width(), chip8.display.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| chip8.display.pixel(x, y) != 0)
+            .collect()
+    }
+
+    #[test]
+    fn test_clip_sprites_quirk() {
+        let block = |xs: std::ops::Range<usize>, ys: std::ops::Range<usize>| {
+            ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+                .collect::<Vec<_>>()
+        };
+
+        // Right edge: only the 2 columns left of the edge are drawn
+        assert_eq!(draw_solid_sprite(true, 62, 10), block(62..64, 10..14));
+        // Bottom edge: only the last row is drawn
+        assert_eq!(draw_solid_sprite(true, 10, 31), block(10..18, 31..32));
+        // Left and top edges: the origin wraps around before clipping, so 126 and 63
+        // are the same as 62 and 31
+        assert_eq!(draw_solid_sprite(true, 126, 63), block(62..64, 31..32));
+        // A sprite at the top left corner is drawn completely
+        assert_eq!(draw_solid_sprite(true, 0, 0), block(0..8, 0..4));
+
+        // Without clipping, the parts past the edges reappear on the opposite side
+        let mut wrapped = block(0..6, 0..3);
+        wrapped.extend(block(62..64, 0..3));
+        wrapped.extend(block(0..6, 31..32));
+        wrapped.extend(block(62..64, 31..32));
+        wrapped.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(draw_solid_sprite(false, 62, 31), wrapped);
+    }
+
+    #[test]
+    fn test_jump_with_offset_uses_v0() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.V[0] = 0x10;
+        chip8.V[3] = 0x20;
+
+        chip8.execute_opcode(0xB300).unwrap();
+
+        assert_eq!(chip8.PC, 0x310);
+    }
+
+    #[test]
+    fn test_jump_with_offset_uses_vx() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8
+            .set_quirks(Quirks {
+                jump_v0_uses_vx: true,
+                ..Quirks::for_mode(&Mode::Chip8)
+            })
+            .unwrap();
+        chip8.V[0] = 0x10;
+        chip8.V[3] = 0x20;
+
+        chip8.execute_opcode(0xB300).unwrap();
+
+        assert_eq!(chip8.PC, 0x320);
+    }
+
+    #[test]
+    fn test_jump_with_offset_wraps() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.V[0] = 0xFF;
+
+        chip8.execute_opcode(0xBFFF).unwrap();
+
+        assert_eq!(chip8.PC, 0x0FE);
+    }
+
+    #[test]
+    fn test_load_rom_hot_swap() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+
+        // LD V0, 0x05; JP 0x202
+        chip8.load_rom(&[0x60, 0x05, 0x12, 0x02]).unwrap();
+        for _ in 0..4 {
+            chip8.emulate_cycle().unwrap();
+        }
+        assert_eq!(chip8.V[0], 0x05);
+        assert_eq!(chip8.PC, 0x202);
+
+        // LD V1, 0x07
+        chip8.load_rom(&[0x61, 0x07]).unwrap();
+        assert_eq!(chip8.memory[0x200..0x204], [0x61, 0x07, 0x00, 0x00]);
+        assert_eq!(chip8.memory[..CHIP8_FONTSET.len()], CHIP8_FONTSET);
+        assert_eq!(chip8.PC, ROM_START_ADDRESS);
+        assert_eq!(chip8.V[0], 0);
+    }
+
+    #[test]
+    fn test_load_rom_too_large() {
+        let mut chip8 = create_test_chip8(Mode::Chip8);
+        chip8.load_rom(&[0x60, 0x05]).unwrap();
+        chip8.emulate_cycle().unwrap();
+
+        let rom = vec![0xFF; MEMORY_SIZE - ROM_START_ADDRESS + 1];
+        assert!(chip8.load_rom(&rom).is_err());
+
+        // The current program keeps running
+        assert_eq!(chip8.memory[0x200..0x202], [0x60, 0x05]);
+        assert_eq!(chip8.V[0], 0x05);
+    }
 }
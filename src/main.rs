@@ -1,11 +1,14 @@
+use std::fs;
 use std::io;
+use std::process;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use sdl2::pixels::Color;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
-    /// Specify the emulation mode (Chip8, SuperChip, XOChip)
+    /// Specify the emulation mode (Chip8, SuperChip, XOChip, or auto to guess it from the ROM)
     #[arg(short, long, value_name = "MODE", default_value_t = String::from("Chip8"))]
     mode: String,
 
@@ -13,31 +16,287 @@ struct Cli {
     #[arg(long, value_name = "SCALE", default_value_t = 10)]
     scale: u32,
 
-    /// Adjust the execution speed (in Hz)
-    #[arg(long, value_name = "SPEED", default_value_t = 700)]
-    speed: u32,
+    /// Set the horizontal display scaling factor [default: SCALE]
+    #[arg(long, value_name = "SCALE_X")]
+    scale_x: Option<u32>,
 
-    /// Path to the ROM file
-    #[arg(value_name = "ROM_PATH")]
-    rom_path: String,
+    /// Set the vertical display scaling factor, e.g. twice SCALE_X for tall pixels [default: SCALE]
+    #[arg(long, value_name = "SCALE_Y")]
+    scale_y: Option<u32>,
+
+    /// Adjust the execution speed (in Hz) [default: 700, or the tickrate from --options]
+    #[arg(long, value_name = "SPEED")]
+    speed: Option<u32>,
+
+    /// Apply the speed, colors, and quirks from an Octo options JSON file; other flags take
+    /// precedence
+    #[arg(long, value_name = "PATH")]
+    options: Option<String>,
+
+    /// Open a stereo audio device instead of mono
+    #[arg(long)]
+    stereo: bool,
+
+    /// Set the audio buffer size in samples (a power of two); smaller is lower latency
+    #[arg(long, value_name = "SAMPLES")]
+    audio_buffer: Option<u16>,
+
+    /// Warn about instructions whose behavior differs between interpreters
+    #[arg(long)]
+    strict: bool,
+
+    /// Stop a program that runs this many cycles without drawing or reading new input
+    #[arg(long, value_name = "CYCLES")]
+    max_cycles: Option<u64>,
+
+    /// Present at most every Nth frame with display changes, to favor CPU throughput
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    frame_skip: u32,
+
+    /// Keyboard layout for the keypad keys (qwerty, azerty, dvorak)
+    #[arg(long, value_name = "LAYOUT", default_value_t = emul8tor::input::Layout::Qwerty)]
+    layout: emul8tor::input::Layout,
+
+    /// Set the color of lit pixels (#RRGGBB, RRGGBB, or #RGB)
+    #[arg(long, value_name = "COLOR", value_parser = emul8tor::video::parse_color)]
+    fg: Option<Color>,
+
+    /// Set the background color (#RRGGBB, RRGGBB, or #RGB)
+    #[arg(long, value_name = "COLOR", value_parser = emul8tor::video::parse_color)]
+    bg: Option<Color>,
+
+    /// Set up to four comma-separated colors, indexed by pixel value; --fg and --bg take precedence
+    #[arg(
+        long,
+        value_name = "COLORS",
+        value_parser = emul8tor::video::parse_color,
+        value_delimiter = ','
+    )]
+    palette: Vec<Color>,
+
+    /// Surround the display with a border of this many physical pixels
+    #[arg(long, value_name = "PIXELS", default_value_t = 0)]
+    border: u32,
+
+    /// Set the border color (#RRGGBB, RRGGBB, or #RGB)
+    #[arg(long, value_name = "COLOR", value_parser = emul8tor::video::parse_color, default_value = "#000000")]
+    border_color: Color,
+
+    /// Filtering used when scaling the display up to the window (nearest, linear)
+    #[arg(long, value_name = "FILTER", default_value_t = emul8tor::video::Filter::Nearest)]
+    filter: emul8tor::video::Filter,
+
+    /// Poke a byte into memory after the ROM loads, in hex (e.g. 2A4=FF); may be repeated
+    #[arg(long = "patch", value_name = "ADDR=VAL", value_parser = emul8tor::parse_patch)]
+    patches: Vec<(usize, u8)>,
+
+    /// Start with the sound muted; M toggles it at runtime
+    #[arg(long)]
+    mute: bool,
+
+    /// Stream every rendered frame over TCP to the given address and accept keys from it
+    #[cfg(feature = "net")]
+    #[arg(long, value_name = "ADDR")]
+    stream: Option<String>,
+
+    /// Run the ROM without a window, save the display to this PNG file, and exit
+    #[arg(long, value_name = "PATH", requires = "cycles")]
+    render_frame: Option<String>,
+
+    /// Run the ROM without a window as fast as possible, report the achieved speed, and exit
+    #[arg(long, requires = "cycles", conflicts_with = "render_frame")]
+    bench: bool,
+
+    /// Number of cycles to run with --render-frame or --bench
+    #[arg(long, value_name = "N")]
+    cycles: Option<u64>,
+
+    /// Print the available emulation modes and exit
+    #[arg(long)]
+    list_modes: bool,
+
+    /// Print the available quirks with their default in each mode and exit
+    #[arg(long)]
+    quirks_help: bool,
+
+    /// Pick a ROM from the .ch8, .c8, and .xo files in this directory with the keypad
+    /// (2/8 to move, 5 to load)
+    #[arg(long, value_name = "DIR", conflicts_with = "rom_paths")]
+    rom_dir: Option<String>,
+
+    /// Paths to the ROM files; PageUp/PageDown switch between them
+    #[arg(
+        value_name = "ROM_PATH",
+        required_unless_present_any = ["list_modes", "quirks_help", "rom_dir"]
+    )]
+    rom_paths: Vec<String>,
 }
 
 fn main() -> io::Result<()> {
     let cli = Cli::parse();
 
+    if cli.list_modes {
+        print!("{}", emul8tor::list_modes());
+    }
+    if cli.quirks_help {
+        print!("{}", emul8tor::quirks::quirks_help());
+    }
+    if cli.list_modes || cli.quirks_help {
+        return Ok(());
+    }
+
+    if cli.palette.len() > 4 {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::TooManyValues,
+                "--palette takes at most 4 colors",
+            )
+            .exit();
+    }
+    let options = match cli.options.as_deref() {
+        Some(path) => match emul8tor::options::load_options_from_json(path) {
+            Ok(options) => options,
+            Err(e) => {
+                eprintln!("Error reading options {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => emul8tor::options::Options::default(),
+    };
+    let palette = palette(&cli, options.palette);
+    let speed = cli
+        .speed
+        .or(options.speed)
+        .unwrap_or(emul8tor::DEFAULT_SPEED);
+
+    let rom_paths = match cli.rom_dir.as_deref() {
+        Some(dir) => match emul8tor::scan_rom_dir(dir) {
+            Ok(roms) if !roms.is_empty() => roms
+                .iter()
+                .map(|rom| rom.to_string_lossy().into_owned())
+                .collect(),
+            Ok(_) => {
+                eprintln!("No ROMs found in {}", dir);
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error reading directory {}: {}", dir, e);
+                process::exit(1);
+            }
+        },
+        None => cli.rom_paths.clone(),
+    };
+
     let mode = match cli.mode.as_str() {
         "Chip8" => emul8tor::Mode::Chip8,
         "SuperChip" => emul8tor::Mode::SuperChip,
         "XOChip" => emul8tor::Mode::XOChip,
+        // Unreadable ROMs are reported when they are loaded
+        "auto" => fs::read(&rom_paths[0])
+            .map(|bytes| emul8tor::detect_mode(&bytes))
+            .unwrap_or(emul8tor::Mode::Chip8),
         _ => panic!("Unavailable mode!"),
     };
 
-    match emul8tor::load_program_rom(&cli.rom_path) {
+    match emul8tor::load_program_rom(&rom_paths[0]) {
         Ok(bytes) => {
-            emul8tor::run(
-                emul8tor::Chip8::new(mode, cli.scale as usize, bytes),
-                cli.speed,
-            );
+            if let (Some(path), Some(cycles)) = (cli.render_frame.as_deref(), cli.cycles) {
+                let mut chip8 = emul8tor::Chip8::headless(mode, bytes);
+                apply_options(&mut chip8, &options);
+                apply_patches(&mut chip8, &cli.patches);
+                chip8.set_palette(palette);
+                if let Err(e) = emul8tor::render_frame(&mut chip8, speed, cycles, path) {
+                    eprintln!("Error rendering frame: {}", e);
+                    process::exit(1);
+                }
+                return Ok(());
+            }
+            if let (true, Some(cycles)) = (cli.bench, cli.cycles) {
+                let mut chip8 = emul8tor::Chip8::headless(mode, bytes);
+                apply_options(&mut chip8, &options);
+                apply_patches(&mut chip8, &cli.patches);
+                match emul8tor::bench(&mut chip8, speed, cycles) {
+                    Ok(report) => println!("{}", report),
+                    Err(e) => {
+                        eprintln!(
+                            "Error at 0x{:03X}: {}",
+                            chip8.cpu_state().PC.wrapping_sub(2),
+                            e
+                        );
+                        process::exit(1);
+                    }
+                }
+                return Ok(());
+            }
+
+            let mut chip8 = match emul8tor::Chip8::new(mode, cli.scale as usize, bytes) {
+                Ok(chip8) => chip8,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+            if cli.scale_x.is_some() || cli.scale_y.is_some() {
+                let scale_x = cli.scale_x.unwrap_or(cli.scale) as usize;
+                let scale_y = cli.scale_y.unwrap_or(cli.scale) as usize;
+                if let Err(e) = chip8.set_scale(scale_x, scale_y) {
+                    eprintln!("Error scaling the window: {}", e);
+                }
+            }
+            if cli.border > 0 {
+                if let Err(e) = chip8.set_border(cli.border as usize, cli.border_color) {
+                    eprintln!("Error adding the border: {}", e);
+                }
+            }
+            if cli.stereo {
+                if let Err(e) = chip8.set_stereo(true) {
+                    eprintln!("Error opening stereo audio: {}", e);
+                }
+            }
+            if let Some(samples) = cli.audio_buffer {
+                if let Err(e) = chip8.set_audio_buffer(samples) {
+                    eprintln!("Error opening audio: {}", e);
+                }
+            }
+            if cli.strict {
+                chip8.set_strict(true);
+                chip8.on_trace(|event| {
+                    // Timer writes are only useful for profiling, not as warnings
+                    if !matches!(event, emul8tor::trace::TraceEvent::DelayTimerSet { .. }) {
+                        eprintln!("{}", event);
+                    }
+                });
+            }
+            apply_options(&mut chip8, &options);
+            apply_patches(&mut chip8, &cli.patches);
+            chip8.set_layout(cli.layout);
+            chip8.set_palette(palette);
+            chip8.set_filter(cli.filter);
+            chip8.set_max_cycles(cli.max_cycles);
+            chip8.set_frame_skip(cli.frame_skip);
+            chip8.set_muted(cli.mute);
+            #[cfg(feature = "ctrlc")]
+            {
+                let stop_flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+                chip8.set_stop_handle(std::sync::Arc::clone(&stop_flag));
+                if let Err(e) = emul8tor::signal::install_stop_handler(stop_flag) {
+                    eprintln!("Error installing the Ctrl-C handler: {}", e);
+                }
+            }
+            #[cfg(feature = "net")]
+            if let Some(addr) = cli.stream.as_deref() {
+                if let Err(e) = emul8tor::net::connect_remote(&mut chip8, addr) {
+                    eprintln!("Error connecting to {}: {}", addr, e);
+                }
+            }
+            let outcome = if cli.rom_dir.is_some() {
+                emul8tor::run_rom_menu(chip8, speed, &rom_paths)
+            } else {
+                emul8tor::run(chip8, speed, &rom_paths)
+            };
+            if outcome == emul8tor::RunOutcome::Watchdog {
+                eprintln!("The program made no progress and was stopped");
+            }
         }
         Err(e) => {
             eprintln!("Error reading file: {}", e);
@@ -46,3 +305,60 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+/// Applies the colors and quirks of the Octo options, reporting display failures.
+fn apply_options(chip8: &mut emul8tor::Chip8, options: &emul8tor::options::Options) {
+    if let Err(e) = chip8.apply_options(options) {
+        eprintln!("Error applying options: {}", e);
+    }
+}
+
+/// Applies the memory patches, which parse_patch already checked to be in bounds.
+fn apply_patches(chip8: &mut emul8tor::Chip8, patches: &[(usize, u8)]) {
+    if let Err(e) = chip8.apply_patches(patches) {
+        eprintln!("Error applying patches: {}", e);
+    }
+}
+
+/// Builds the display palette from the color options, starting from the palette of the
+/// Octo options if there is one.
+fn palette(cli: &Cli, base: Option<emul8tor::video::Palette>) -> emul8tor::video::Palette {
+    let mut palette = base.unwrap_or(emul8tor::video::DEFAULT_PALETTE);
+    for (color, &option) in palette.iter_mut().zip(&cli.palette) {
+        *color = option;
+    }
+    if let Some(bg) = cli.bg {
+        palette[0] = bg;
+    }
+    if let Some(fg) = cli.fg {
+        palette[1] = fg;
+    }
+    palette
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_option() {
+        let cli = Cli::try_parse_from(["emul8tor", "rom.ch8"]).unwrap();
+        assert_eq!(cli.filter, emul8tor::video::Filter::Nearest);
+
+        let cli = Cli::try_parse_from(["emul8tor", "--filter", "linear", "rom.ch8"]).unwrap();
+        assert_eq!(cli.filter, emul8tor::video::Filter::Linear);
+
+        assert!(Cli::try_parse_from(["emul8tor", "--filter", "bicubic", "rom.ch8"]).is_err());
+    }
+
+    #[test]
+    fn test_patch_option() {
+        let cli = Cli::try_parse_from([
+            "emul8tor", "--patch", "2A4=FF", "--patch", "0x300=1", "rom.ch8",
+        ])
+        .unwrap();
+        assert_eq!(cli.patches, [(0x2A4, 0xFF), (0x300, 0x01)]);
+
+        assert!(Cli::try_parse_from(["emul8tor", "--patch", "2A4", "rom.ch8"]).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! Remote play over a single TCP connection.
+//!
+//! The emulator sends frames to the remote end, and the remote end sends back
+//! key-state packets.
+//!
+//! # Frames
+//!
+//! Every frame is sent uncompressed as a header with the width and height as
+//! big-endian `u32`s, followed by `width * height * 4` bytes of RGBA data.
+//!
+//! # Key packets
+//!
+//! Every key packet is two bytes long: an opcode followed by the hex value of a key.
+//!
+//! | Opcode | Meaning      |
+//! |--------|--------------|
+//! | `0x01` | Key pressed  |
+//! | `0x02` | Key released |
+//!
+//! Packets with an unknown opcode or a key above `0xF` are ignored.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, TrySendError};
+use std::thread;
+
+use sdl2::keyboard::Scancode;
+
+use crate::input::{Hotkey, Input, InputManager, Layout};
+use crate::Chip8;
+
+/// Size of the header sent before every frame: width and height as big-endian `u32`s.
+pub const FRAME_HEADER_SIZE: usize = 8;
+
+/// Size of a key packet: an opcode byte followed by a key byte.
+pub const KEY_PACKET_SIZE: usize = 2;
+
+/// Opcode of a key press packet.
+pub const KEY_PRESS: u8 = 0x01;
+
+/// Opcode of a key release packet.
+pub const KEY_RELEASE: u8 = 0x02;
+
+/// Number of frames that may wait to be sent before new frames are dropped.
+const FRAME_QUEUE_SIZE: usize = 2;
+
+/// Sends rendered frames over a TCP connection.
+pub struct FrameStreamer {
+    stream: TcpStream,
+}
+
+impl FrameStreamer {
+    /// Connects to a frame receiver.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the receiver, e.g. `127.0.0.1:8080`.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Self::from_stream(TcpStream::connect(addr)?)
+    }
+
+    /// Creates a new `FrameStreamer` instance on an established connection.
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(FrameStreamer { stream })
+    }
+
+    /// Sends a single frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - RGBA bytes of the frame.
+    /// * `width` - Width of the frame in pixels.
+    /// * `height` - Height of the frame in pixels.
+    pub fn send_frame(&mut self, buffer: &[u8], width: usize, height: usize) -> io::Result<()> {
+        let mut header = [0; FRAME_HEADER_SIZE];
+        header[..4].copy_from_slice(&(width as u32).to_be_bytes());
+        header[4..].copy_from_slice(&(height as u32).to_be_bytes());
+
+        self.stream.write_all(&header)?;
+        self.stream.write_all(&buffer[..width * height * 4])
+    }
+}
+
+/// Streams every frame rendered by `chip8` to the given address.
+///
+/// Frames are sent from a separate thread, so a slow receiver never stalls emulation;
+/// frames are dropped while it falls behind. Streaming stops after the first failed
+/// send, without interrupting emulation.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine whose frames are streamed.
+/// * `addr` - The address of the receiver.
+pub fn stream_frames(chip8: &mut Chip8, addr: impl ToSocketAddrs) -> io::Result<()> {
+    install_streamer(chip8, FrameStreamer::connect(addr)?);
+    Ok(())
+}
+
+/// Streams every frame rendered by `chip8` to the given address, and applies the key
+/// packets received on the same connection on top of the local input.
+///
+/// # Arguments
+///
+/// * `chip8` - The machine to play remotely.
+/// * `addr` - The address of the remote end.
+pub fn connect_remote(chip8: &mut Chip8, addr: impl ToSocketAddrs) -> io::Result<()> {
+    let stream = TcpStream::connect(addr)?;
+    let remote_input = NetworkInput::new(stream.try_clone()?)?;
+    install_streamer(chip8, FrameStreamer::from_stream(stream)?);
+
+    let local_input = std::mem::replace(&mut chip8.input, Box::new(InputManager::headless()));
+    chip8.input = Box::new(remote_input.with_local(local_input));
+    Ok(())
+}
+
+fn install_streamer(chip8: &mut Chip8, mut streamer: FrameStreamer) {
+    let (sender, receiver) = mpsc::sync_channel::<(Vec<u8>, usize, usize)>(FRAME_QUEUE_SIZE);
+    thread::spawn(move || {
+        for (buffer, width, height) in receiver {
+            if let Err(e) = streamer.send_frame(&buffer, width, height) {
+                eprintln!("Error streaming frame: {}", e);
+                break;
+            }
+        }
+    });
+
+    let mut sender = Some(sender);
+    chip8.on_frame(move |buffer, width, height| {
+        if let Some(queue) = sender.as_ref() {
+            match queue.try_send((buffer[..width * height * 4].to_vec(), width, height)) {
+                Ok(()) | Err(TrySendError::Full(_)) => {}
+                // The sending thread stopped after a failed send
+                Err(TrySendError::Disconnected(_)) => sender = None,
+            }
+        }
+    });
+}
+
+/// A key packet sent by the remote end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyPacket {
+    Press(u8),
+    Release(u8),
+}
+
+impl KeyPacket {
+    /// Parses a single key packet.
+    ///
+    /// # Returns
+    ///
+    /// The parsed packet, or `None` if the packet is malformed.
+    pub fn parse(bytes: [u8; KEY_PACKET_SIZE]) -> Option<Self> {
+        match bytes {
+            [KEY_PRESS, hex_key @ 0x0..=0xF] => Some(KeyPacket::Press(hex_key)),
+            [KEY_RELEASE, hex_key @ 0x0..=0xF] => Some(KeyPacket::Release(hex_key)),
+            _ => None,
+        }
+    }
+
+    /// Applies the packet to an input source.
+    pub fn apply(self, input: &mut dyn Input) {
+        match self {
+            KeyPacket::Press(hex_key) => input.press_key(hex_key),
+            KeyPacket::Release(hex_key) => input.release_key(hex_key),
+        }
+    }
+}
+
+/// Splits a byte stream into key packets, keeping incomplete packets until the rest arrives.
+#[derive(Default)]
+pub struct KeyPacketDecoder {
+    pending: Vec<u8>,
+}
+
+impl KeyPacketDecoder {
+    /// Creates a new `KeyPacketDecoder` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds received bytes, applying every complete packet to `input`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The received bytes.
+    /// * `input` - The input source the packets are applied to.
+    pub fn feed(&mut self, bytes: &[u8], input: &mut dyn Input) {
+        self.pending.extend_from_slice(bytes);
+
+        let complete = self.pending.len() - self.pending.len() % KEY_PACKET_SIZE;
+        for packet in self.pending[..complete].chunks_exact(KEY_PACKET_SIZE) {
+            if let Some(packet) = KeyPacket::parse([packet[0], packet[1]]) {
+                packet.apply(input);
+            }
+        }
+        self.pending.drain(..complete);
+    }
+}
+
+/// Receives key packets over a TCP connection.
+///
+/// Everything except the keypad state, like quitting and hotkeys, is delegated to the
+/// local input source.
+pub struct NetworkInput {
+    stream: Option<TcpStream>,
+    decoder: KeyPacketDecoder,
+    local: Box<dyn Input>,
+}
+
+impl NetworkInput {
+    /// Creates a new `NetworkInput` instance reading from `stream`, without local input.
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(NetworkInput {
+            stream: Some(stream),
+            decoder: KeyPacketDecoder::new(),
+            local: Box::new(InputManager::headless()),
+        })
+    }
+
+    /// Sets the local input source that keeps working alongside the remote one.
+    pub fn with_local(mut self, local: Box<dyn Input>) -> Self {
+        self.local = local;
+        self
+    }
+
+    fn receive(&mut self) {
+        let mut buffer = [0; 256];
+        while let Some(stream) = self.stream.as_mut() {
+            match stream.read(&mut buffer) {
+                Ok(0) => self.stream = None,
+                Ok(len) => self.decoder.feed(&buffer[..len], self.local.as_mut()),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    eprintln!("Error receiving input: {}", e);
+                    self.stream = None;
+                }
+            }
+        }
+    }
+}
+
+impl Input for NetworkInput {
+    fn update(&mut self) {
+        self.local.update();
+        self.receive();
+    }
+
+    fn is_key_pressed(&self, hex_key: u8) -> bool {
+        self.local.is_key_pressed(hex_key)
+    }
+
+    fn get_next_released_key(&mut self) -> Option<u8> {
+        self.local.get_next_released_key()
+    }
+
+    fn press_key(&mut self, hex_key: u8) {
+        self.local.press_key(hex_key);
+    }
+
+    fn release_key(&mut self, hex_key: u8) {
+        self.local.release_key(hex_key);
+    }
+
+    fn should_quit(&self) -> bool {
+        self.local.should_quit()
+    }
+
+    fn is_focused(&self) -> bool {
+        self.local.is_focused()
+    }
+
+    fn take_hotkeys(&mut self) -> Vec<Hotkey> {
+        self.local.take_hotkeys()
+    }
+
+    fn set_quit_key(&mut self, quit_key: Option<Scancode>) {
+        self.local.set_quit_key(quit_key);
+    }
+
+    fn set_layout(&mut self, layout: Layout) {
+        self.local.set_layout(layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    use crate::{run_headless, Mode, MEMORY_SIZE};
+
+    #[test]
+    fn test_stream_frames() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut chip8 = Chip8::headless(Mode::Chip8, [0; MEMORY_SIZE]);
+        chip8.load_rom(&[0x12, 0x00]).unwrap();
+
+        stream_frames(&mut chip8, listener.local_addr().unwrap()).unwrap();
+        run_headless(&mut chip8, 60, 1);
+
+        let (mut receiver, _) = listener.accept().unwrap();
+        let mut header = [0; FRAME_HEADER_SIZE];
+        receiver.read_exact(&mut header).unwrap();
+        assert_eq!(header, [0, 0, 0, 64, 0, 0, 0, 32]);
+
+        let mut frame = vec![0; 64 * 32 * 4];
+        receiver.read_exact(&mut frame).unwrap();
+        assert_eq!(frame[..4], [0x00, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn test_stream_frames_slow_receiver() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut chip8 = Chip8::headless(Mode::Chip8, [0; MEMORY_SIZE]);
+        chip8.load_rom(&[0x12, 0x00]).unwrap();
+
+        stream_frames(&mut chip8, listener.local_addr().unwrap()).unwrap();
+        // Far more data than the socket buffers hold, while nothing is read
+        run_headless(&mut chip8, 60, 5000);
+
+        let (mut receiver, _) = listener.accept().unwrap();
+        let mut header = [0; FRAME_HEADER_SIZE];
+        receiver.read_exact(&mut header).unwrap();
+        assert_eq!(header, [0, 0, 0, 64, 0, 0, 0, 32]);
+    }
+
+    #[test]
+    fn test_key_packet_press() {
+        let mut input = InputManager::headless();
+        let mut decoder = KeyPacketDecoder::new();
+
+        decoder.feed(&[KEY_PRESS, 0xA], &mut input);
+
+        assert!(input.is_key_pressed(0xA));
+    }
+
+    #[test]
+    fn test_key_packet_split_and_malformed() {
+        let mut input = InputManager::headless();
+        let mut decoder = KeyPacketDecoder::new();
+
+        decoder.feed(&[0x7F, 0x1, KEY_PRESS, 0x10, KEY_PRESS], &mut input);
+        assert!((0x0..=0xF).all(|hex_key| !input.is_key_pressed(hex_key)));
+
+        decoder.feed(&[0x3], &mut input);
+        assert!(input.is_key_pressed(0x3));
+
+        decoder.feed(&[KEY_RELEASE, 0x3], &mut input);
+        assert!(!input.is_key_pressed(0x3));
+    }
+}
@@ -0,0 +1,464 @@
+//! Octo options, the JSON settings that Octo ROMs are often distributed with.
+//!
+//! Octo stores the intended speed, colors, and quirks of a ROM in an object like
+//! `{"tickrate": 20, "fillColor": "#FFCC00", "shiftQuirks": false, ...}`. Known fields
+//! are mapped to their emulator counterparts, all other fields are ignored.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use sdl2::pixels::Color;
+
+use crate::quirks::{LoadStoreIncrement, Quirks};
+use crate::video::{parse_color, Palette, DEFAULT_PALETTE};
+use crate::Mode;
+
+/// Settings read from an Octo options object. Fields that are not set keep the
+/// emulator defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Options {
+    /// Execution speed in instructions per second, from `tickrate` (instructions per frame).
+    pub speed: Option<u32>,
+    /// Display colors from `backgroundColor`, `fillColor`, `fillColor2`, and `blendColor`.
+    pub palette: Option<Palette>,
+    /// Color shown while the buzzer sounds, from `buzzColor`.
+    pub buzz_color: Option<Color>,
+    /// `shiftQuirks`: `8xy6` and `8xyE` shift `Vx` in place.
+    pub shift_quirks: Option<bool>,
+    /// `loadStoreQuirks`: `Fx55` and `Fx65` leave `I` unchanged.
+    pub load_store_quirks: Option<bool>,
+    /// `jumpQuirks`: `Bxnn` jumps to `xnn + Vx`.
+    pub jump_quirks: Option<bool>,
+    /// `logicQuirks`: `8xy1`, `8xy2`, and `8xy3` reset `VF`.
+    pub logic_quirks: Option<bool>,
+    /// `clipQuirks`: `Dxyn` clips sprites at the edges instead of wrapping them.
+    pub clip_quirks: Option<bool>,
+}
+
+impl Options {
+    /// Parses an Octo options object.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - The JSON text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error if the text is not a JSON object, or if a known
+    /// field has the wrong type.
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let mut parser = Parser {
+            bytes: json.as_bytes(),
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.parse_document()?;
+        let fields = match value {
+            Json::Object(fields) => fields,
+            _ => return Err(invalid_data("Options must be a JSON object")),
+        };
+
+        let mut options = Options::default();
+        let mut colors: [Option<Color>; 4] = [None; 4];
+        for (name, value) in &fields {
+            match name.as_str() {
+                "tickrate" => {
+                    let tickrate = value.as_number(name)?;
+                    options.speed = Some((tickrate.max(1.0) * 60.0).round() as u32);
+                }
+                "backgroundColor" => colors[0] = Some(value.as_color(name)?),
+                "fillColor" => colors[1] = Some(value.as_color(name)?),
+                "fillColor2" => colors[2] = Some(value.as_color(name)?),
+                "blendColor" => colors[3] = Some(value.as_color(name)?),
+                "buzzColor" => options.buzz_color = Some(value.as_color(name)?),
+                "shiftQuirks" => options.shift_quirks = Some(value.as_bool(name)?),
+                "loadStoreQuirks" => options.load_store_quirks = Some(value.as_bool(name)?),
+                "jumpQuirks" => options.jump_quirks = Some(value.as_bool(name)?),
+                "logicQuirks" => options.logic_quirks = Some(value.as_bool(name)?),
+                "clipQuirks" => options.clip_quirks = Some(value.as_bool(name)?),
+                _ => {}
+            }
+        }
+
+        if colors.iter().any(Option::is_some) {
+            let mut palette = DEFAULT_PALETTE;
+            for (color, option) in palette.iter_mut().zip(colors) {
+                if let Some(option) = option {
+                    *color = option;
+                }
+            }
+            options.palette = Some(palette);
+        }
+        Ok(options)
+    }
+
+    /// Returns the quirks of a mode with the quirks set in the options applied on top.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode whose quirks are the defaults.
+    pub fn quirks(&self, mode: &Mode) -> Quirks {
+        let mut quirks = Quirks::for_mode(mode);
+        if let Some(shift_quirks) = self.shift_quirks {
+            quirks.shift_uses_vy = !shift_quirks;
+        }
+        if let Some(load_store_quirks) = self.load_store_quirks {
+            quirks.load_store_increment = if load_store_quirks {
+                LoadStoreIncrement::None
+            } else {
+                LoadStoreIncrement::XPlusOne
+            };
+        }
+        if let Some(jump_quirks) = self.jump_quirks {
+            quirks.jump_v0_uses_vx = jump_quirks;
+        }
+        if let Some(logic_quirks) = self.logic_quirks {
+            quirks.reset_vf_on_logic = logic_quirks;
+        }
+        if let Some(clip_quirks) = self.clip_quirks {
+            quirks.clip_sprites = clip_quirks;
+        }
+        quirks
+    }
+}
+
+/// Reads an Octo options file.
+///
+/// # Arguments
+///
+/// * `path` - The JSON file to read.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or is not a valid options object.
+pub fn load_options_from_json(path: impl AsRef<Path>) -> io::Result<Options> {
+    Options::from_json(&fs::read_to_string(path)?)
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// A parsed JSON value.
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    /// Arrays only appear in ignored fields, so their items are skipped.
+    Array,
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn as_number(&self, name: &str) -> io::Result<f64> {
+        match self {
+            Json::Number(number) => Ok(*number),
+            _ => Err(invalid_data(format!("{} must be a number", name))),
+        }
+    }
+
+    fn as_bool(&self, name: &str) -> io::Result<bool> {
+        match self {
+            Json::Bool(value) => Ok(*value),
+            _ => Err(invalid_data(format!("{} must be a boolean", name))),
+        }
+    }
+
+    /// Reads a color in any format accepted by `video::parse_color`, e.g. `#RRGGBB`.
+    fn as_color(&self, name: &str) -> io::Result<Color> {
+        match self {
+            Json::String(text) => {
+                parse_color(text).map_err(|e| invalid_data(format!("{}: {}", name, e)))
+            }
+            _ => Err(invalid_data(format!("{} must be a color string", name))),
+        }
+    }
+}
+
+/// Maximum nesting of arrays and objects, which keeps deeply nested input from
+/// overflowing the stack of the recursive parser.
+const MAX_DEPTH: usize = 128;
+
+/// A small recursive-descent JSON parser.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    /// Number of arrays and objects currently being parsed.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn parse_document(&mut self) -> io::Result<Json> {
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.position != self.bytes.len() {
+            return Err(self.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> io::Result<Json> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_nested(Self::parse_object),
+            Some(b'[') => self.parse_nested(Self::parse_array),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b't') => self.parse_literal("true", Json::Bool(true)),
+            Some(b'f') => self.parse_literal("false", Json::Bool(false)),
+            Some(b'n') => self.parse_literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_nested(&mut self, parse: fn(&mut Self) -> io::Result<Json>) -> io::Result<Json> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> io::Result<Json> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let name = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            fields.push((name, self.parse_value()?));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b'}') => return Ok(Json::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> io::Result<Json> {
+        self.expect(b'[')?;
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array);
+        }
+
+        loop {
+            self.parse_value()?;
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b']') => return Ok(Json::Array),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> io::Result<String> {
+        self.expect(b'"')?;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some(b'"') => return Ok(text),
+                Some(b'\\') => {
+                    let escaped = match self.next() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let hex = self.take(4)?;
+                            let code = u32::from_str_radix(hex, 16)
+                                .map_err(|_| self.error("invalid unicode escape"))?;
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    text.push(escaped);
+                }
+                Some(_) => {
+                    // Copy a whole UTF-8 sequence at once
+                    let start = self.position - 1;
+                    while self.peek().is_some_and(|byte| byte & 0xC0 == 0x80) {
+                        self.position += 1;
+                    }
+                    text.push_str(
+                        std::str::from_utf8(&self.bytes[start..self.position])
+                            .map_err(|_| self.error("invalid UTF-8"))?,
+                    );
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> io::Result<Json> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> io::Result<Json> {
+        if self.take(literal.len())? != literal {
+            return Err(self.error("expected a value"));
+        }
+        Ok(value)
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&str> {
+        let end = self.position + len;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| self.error("unexpected end"))?;
+        self.position = end;
+        std::str::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    fn expect(&mut self, byte: u8) -> io::Result<()> {
+        if self.next() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek();
+        self.position += 1;
+        byte
+    }
+
+    fn error(&self, message: &str) -> io::Error {
+        invalid_data(format!(
+            "Invalid options JSON at byte {}: {}",
+            self.position, message
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r##"{
+        "tickrate": 20,
+        "fillColor": "#FFCC00",
+        "fillColor2": "#FF6600",
+        "blendColor": "#662200",
+        "backgroundColor": "#996600",
+        "buzzColor": "#FFAA00",
+        "quietColor": "#000000",
+        "shiftQuirks": true,
+        "loadStoreQuirks": false,
+        "vfOrderQuirks": false,
+        "jumpQuirks": true,
+        "logicQuirks": false,
+        "clipQuirks": false,
+        "screenRotation": 0,
+        "fontStyle": "octo",
+        "touchInputMode": "none",
+        "extras": {"list": [1, -2.5e3, null, "é"]}
+    }"##;
+
+    #[test]
+    fn test_from_json() {
+        let options = Options::from_json(SAMPLE).unwrap();
+
+        assert_eq!(options.speed, Some(1200));
+        assert_eq!(
+            options.palette,
+            Some([
+                Color::RGB(0x99, 0x66, 0x00),
+                Color::RGB(0xFF, 0xCC, 0x00),
+                Color::RGB(0xFF, 0x66, 0x00),
+                Color::RGB(0x66, 0x22, 0x00),
+            ])
+        );
+        assert_eq!(options.buzz_color, Some(Color::RGB(0xFF, 0xAA, 0x00)));
+
+        let quirks = options.quirks(&Mode::Chip8);
+        assert!(!quirks.shift_uses_vy);
+        assert!(quirks.jump_v0_uses_vx);
+        assert!(!quirks.reset_vf_on_logic);
+        assert!(!quirks.clip_sprites);
+        assert_eq!(quirks.load_store_increment, LoadStoreIncrement::XPlusOne);
+    }
+
+    #[test]
+    fn test_from_json_partial() {
+        let options = Options::from_json(r##"{"fillColor": "#0F0"}"##).unwrap();
+
+        let palette = options.palette.unwrap();
+        assert_eq!(palette[0], DEFAULT_PALETTE[0]);
+        assert_eq!(palette[1], Color::RGB(0x00, 0xFF, 0x00));
+        assert_eq!(options.speed, None);
+        assert_eq!(
+            options.quirks(&Mode::SuperChip),
+            Quirks::for_mode(&Mode::SuperChip)
+        );
+    }
+
+    #[test]
+    fn test_from_json_invalid() {
+        for json in [
+            "[]",
+            "{\"tickrate\": \"fast\"}",
+            "{\"fillColor\": \"#FFFF\"}",
+            "{\"fillColor\": 255}",
+            "{\"shiftQuirks\": true",
+            "{} {}",
+        ] {
+            let error = Options::from_json(json).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_from_json_deeply_nested() {
+        let nested = format!(
+            "{{\"extras\": {}{}}}",
+            "[".repeat(MAX_DEPTH - 1),
+            "]".repeat(MAX_DEPTH - 1)
+        );
+        assert!(Options::from_json(&nested).is_ok());
+
+        let error = Options::from_json(&"[".repeat(100_000)).unwrap_err();
+        assert!(error.to_string().contains("nested too deeply"), "{}", error);
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+
+use crate::Mode;
+
+/// Behaviors that differ between CHIP-8 interpreters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// `Bxnn` jumps to `xnn + Vx` instead of `Bnnn` jumping to `nnn + V0`.
+    pub jump_v0_uses_vx: bool,
+    /// `8xy6` and `8xyE` shift `Vy` into `Vx` instead of shifting `Vx` in place.
+    pub shift_uses_vy: bool,
+    /// `8xy1`, `8xy2`, and `8xy3` reset `VF` to 0 after writing their result.
+    pub reset_vf_on_logic: bool,
+    /// `0nnn` SYS calls are treated as unknown opcodes instead of being ignored.
+    pub sys_is_error: bool,
+    /// Lores sprites are drawn as 2x2 blocks on the hires display, like SuperChip
+    /// hardware, so switching resolution keeps the display contents.
+    pub lores_doubled_pixels: bool,
+    /// Memory accesses past the end of memory wrap around to address 0 instead
+    /// of being clamped to the last address.
+    pub memory_wrap: bool,
+    /// `00FB` and `00FC` wrap the pixels scrolled out around to the opposite edge
+    /// instead of clearing the vacated columns.
+    pub wrap_scroll: bool,
+    /// How far `Fx55` and `Fx65` advance `I` after storing or loading registers.
+    pub load_store_increment: LoadStoreIncrement,
+    /// `1260` at the start of the program switches to the 64x64 hires mode of the
+    /// COSMAC VIP and jumps to `2C0`, and `0230` clears the display.
+    pub vip_hires: bool,
+    /// `Fx0A` completes on the release of a key that was already held when it started,
+    /// instead of waiting for a key that is pressed and released afterwards.
+    pub fx0a_accept_held: bool,
+    /// `Dxyn` clips the parts of a sprite past the right and bottom edges instead of
+    /// wrapping them around to the opposite edge. The sprite origin always wraps.
+    pub clip_sprites: bool,
+}
+
+/// How far `Fx55` and `Fx65` advance `I`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadStoreIncrement {
+    /// `I` is left unchanged, like SuperChip.
+    None,
+    /// `I` advances by `x`, like the CHIP-48.
+    X,
+    /// `I` advances by `x + 1`, past the last register, like the original CHIP-8.
+    XPlusOne,
+}
+
+impl fmt::Display for LoadStoreIncrement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LoadStoreIncrement::None => "none",
+            LoadStoreIncrement::X => "x",
+            LoadStoreIncrement::XPlusOne => "x+1",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A quirk that an instruction's behavior depends on, reported in strict mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quirk {
+    /// `8xy6` and `8xyE` shift either `Vx` or `Vy`.
+    Shift,
+    /// `Fx55` and `Fx65` may or may not advance `I`.
+    LoadStoreIncrement,
+    /// `Bnnn` adds either `V0` or `Vx`.
+    JumpOffset,
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Quirk::Shift => "shift",
+            Quirk::LoadStoreIncrement => "load/store increment",
+            Quirk::JumpOffset => "jump offset",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Quirks {
+    /// Returns the quirks that match the given emulation mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The emulation mode.
+    pub fn for_mode(mode: &Mode) -> Self {
+        Quirks {
+            jump_v0_uses_vx: *mode == Mode::SuperChip,
+            shift_uses_vy: *mode != Mode::SuperChip,
+            reset_vf_on_logic: *mode == Mode::Chip8,
+            sys_is_error: false,
+            lores_doubled_pixels: false,
+            memory_wrap: false,
+            wrap_scroll: false,
+            load_store_increment: if *mode == Mode::SuperChip {
+                LoadStoreIncrement::None
+            } else {
+                LoadStoreIncrement::XPlusOne
+            },
+            vip_hires: false,
+            fx0a_accept_held: true,
+            clip_sprites: *mode != Mode::XOChip,
+        }
+    }
+}
+
+/// The name, description, and formatted value accessor of a quirk.
+type QuirkField = (&'static str, &'static str, fn(&Quirks) -> String);
+
+/// Returns the name, description, and accessor of every quirk.
+fn quirk_fields() -> [QuirkField; 11] {
+    [
+        (
+            "jump_v0_uses_vx",
+            "Bxnn jumps to xnn + Vx instead of Bnnn jumping to nnn + V0",
+            |quirks| quirks.jump_v0_uses_vx.to_string(),
+        ),
+        (
+            "shift_uses_vy",
+            "8xy6 and 8xyE shift Vy into Vx instead of shifting Vx in place",
+            |quirks| quirks.shift_uses_vy.to_string(),
+        ),
+        (
+            "reset_vf_on_logic",
+            "8xy1, 8xy2, and 8xy3 reset VF to 0",
+            |quirks| quirks.reset_vf_on_logic.to_string(),
+        ),
+        (
+            "sys_is_error",
+            "0nnn SYS calls stop execution instead of being ignored",
+            |quirks| quirks.sys_is_error.to_string(),
+        ),
+        (
+            "lores_doubled_pixels",
+            "Lores sprites are drawn as 2x2 blocks on the hires display",
+            |quirks| quirks.lores_doubled_pixels.to_string(),
+        ),
+        (
+            "memory_wrap",
+            "Memory accesses past the end wrap to 0 instead of clamping to the last address",
+            |quirks| quirks.memory_wrap.to_string(),
+        ),
+        (
+            "wrap_scroll",
+            "00FB and 00FC wrap pixels around the opposite edge instead of clearing them",
+            |quirks| quirks.wrap_scroll.to_string(),
+        ),
+        (
+            "load_store_increment",
+            "How far Fx55 and Fx65 advance I (none, x, or x+1)",
+            |quirks| quirks.load_store_increment.to_string(),
+        ),
+        (
+            "vip_hires",
+            "1260 at 0x200 switches to the 64x64 COSMAC VIP hires mode and 0230 clears it",
+            |quirks| quirks.vip_hires.to_string(),
+        ),
+        (
+            "fx0a_accept_held",
+            "Fx0A completes on the release of a key held before it started",
+            |quirks| quirks.fx0a_accept_held.to_string(),
+        ),
+        (
+            "clip_sprites",
+            "Dxyn clips sprites at the right and bottom edges instead of wrapping them",
+            |quirks| quirks.clip_sprites.to_string(),
+        ),
+    ]
+}
+
+/// Lists every quirk with a description and its default in each emulation mode.
+pub fn quirks_help() -> String {
+    let mut help = String::new();
+    for (name, description, value) in quirk_fields() {
+        let defaults = Mode::ALL
+            .iter()
+            .map(|mode| format!("{}={}", mode.name(), value(&Quirks::for_mode(mode))))
+            .collect::<Vec<_>>()
+            .join(" ");
+        help.push_str(&format!(
+            "{}\n    {}\n    {}\n",
+            name, description, defaults
+        ));
+    }
+    help
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quirks_help() {
+        let help = quirks_help();
+
+        assert!(help.contains("jump_v0_uses_vx"), "{}", help);
+        assert!(
+            help.contains("Chip8=false SuperChip=true XOChip=false"),
+            "{}",
+            help
+        );
+        assert_eq!(help.lines().count(), quirk_fields().len() * 3);
+    }
+
+    #[test]
+    fn test_for_mode() {
+        assert!(!Quirks::for_mode(&Mode::Chip8).jump_v0_uses_vx);
+        assert!(Quirks::for_mode(&Mode::SuperChip).jump_v0_uses_vx);
+        assert!(!Quirks::for_mode(&Mode::XOChip).jump_v0_uses_vx);
+        assert!(Quirks::for_mode(&Mode::Chip8).shift_uses_vy);
+        assert!(!Quirks::for_mode(&Mode::SuperChip).shift_uses_vy);
+        assert!(Quirks::for_mode(&Mode::Chip8).reset_vf_on_logic);
+        assert!(!Quirks::for_mode(&Mode::XOChip).reset_vf_on_logic);
+        assert!(!Quirks::for_mode(&Mode::Chip8).sys_is_error);
+        assert!(!Quirks::for_mode(&Mode::SuperChip).lores_doubled_pixels);
+        assert!(!Quirks::for_mode(&Mode::XOChip).memory_wrap);
+        assert!(!Quirks::for_mode(&Mode::XOChip).wrap_scroll);
+        assert!(!Quirks::for_mode(&Mode::Chip8).vip_hires);
+        assert!(Quirks::for_mode(&Mode::Chip8).fx0a_accept_held);
+        assert!(Quirks::for_mode(&Mode::SuperChip).clip_sprites);
+        assert!(!Quirks::for_mode(&Mode::XOChip).clip_sprites);
+        assert_eq!(
+            Quirks::for_mode(&Mode::SuperChip).load_store_increment,
+            LoadStoreIncrement::None
+        );
+        assert_eq!(
+            Quirks::for_mode(&Mode::XOChip).load_store_increment,
+            LoadStoreIncrement::XPlusOne
+        );
+    }
+}
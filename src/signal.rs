@@ -0,0 +1,42 @@
+//! Clean shutdown on Ctrl-C.
+//!
+//! The handler only sets the stop flag installed with `Chip8::set_stop_handle`, so
+//! `run` returns on its next iteration after pausing the audio and presenting the
+//! last frame.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Sets `stop_flag` when the process receives Ctrl-C (SIGINT), instead of it being
+/// killed right away.
+///
+/// # Arguments
+///
+/// * `stop_flag` - The flag to set, usually shared with `Chip8::set_stop_handle`.
+///
+/// # Errors
+///
+/// Returns an error if a handler is already installed or the signal can't be handled.
+pub fn install_stop_handler(stop_flag: Arc<AtomicBool>) -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(stop_callback(stop_flag))
+}
+
+/// Returns the callback invoked by the signal handler.
+fn stop_callback(stop_flag: Arc<AtomicBool>) -> impl FnMut() + Send + 'static {
+    move || stop_flag.store(true, Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_callback() {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let mut callback = stop_callback(Arc::clone(&stop_flag));
+        assert!(!stop_flag.load(Ordering::Relaxed));
+
+        callback();
+        assert!(stop_flag.load(Ordering::Relaxed));
+    }
+}
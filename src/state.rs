@@ -0,0 +1,293 @@
+use std::fmt;
+use std::io;
+
+use crate::{MAX_STACK_LEVELS, MEMORY_SIZE, V_COUNT};
+
+/// Magic bytes at the start of every save state file.
+pub const SAVE_STATE_MAGIC: [u8; 4] = *b"E8ST";
+
+/// Version of the save state format, bumped whenever the layout changes.
+pub const SAVE_STATE_VERSION: u16 = 2;
+
+/// Snapshot of the CPU registers, stack, and timers.
+#[allow(non_snake_case)]
+#[derive(Clone, PartialEq)]
+pub struct CpuState {
+    pub V: [u8; V_COUNT],
+    pub I: u16,
+    pub PC: usize,
+    pub stack: [usize; MAX_STACK_LEVELS],
+    pub SP: usize,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+impl CpuState {
+    /// Returns the return addresses currently on the stack, oldest first.
+    fn active_stack(&self) -> &[usize] {
+        &self.stack[..self.SP.min(MAX_STACK_LEVELS)]
+    }
+}
+
+/// Complete machine state, as stored in save state files.
+#[derive(Clone, PartialEq)]
+pub struct Chip8State {
+    pub cpu: CpuState,
+    pub memory: [u8; MEMORY_SIZE],
+    /// The register waiting for a key release, if any.
+    pub release_key_register: Option<usize>,
+    /// Whether the program has exited and the machine waits for a reset.
+    pub halted: bool,
+    pub lores: bool,
+    pub planes: u8,
+    pub width: usize,
+    pub height: usize,
+    /// Display pixels, row by row.
+    pub pixels: Vec<u8>,
+}
+
+impl Chip8State {
+    /// Serializes the state, prefixed with the magic bytes and the format version.
+    ///
+    /// All multi-byte values are stored big-endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MEMORY_SIZE + self.pixels.len() + 128);
+        bytes.extend_from_slice(&SAVE_STATE_MAGIC);
+        bytes.extend_from_slice(&SAVE_STATE_VERSION.to_be_bytes());
+
+        let cpu = &self.cpu;
+        bytes.extend_from_slice(&cpu.V);
+        bytes.extend_from_slice(&cpu.I.to_be_bytes());
+        bytes.extend_from_slice(&(cpu.PC as u16).to_be_bytes());
+        for address in cpu.stack {
+            bytes.extend_from_slice(&(address as u16).to_be_bytes());
+        }
+        bytes.extend_from_slice(&[cpu.SP as u8, cpu.delay_timer, cpu.sound_timer]);
+
+        bytes.push(
+            self.release_key_register
+                .map_or(0xFF, |register| register as u8),
+        );
+        bytes.extend_from_slice(&[self.halted as u8, self.lores as u8, self.planes]);
+        bytes.extend_from_slice(&self.memory);
+
+        bytes.extend_from_slice(&(self.width as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.height as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.pixels);
+        bytes
+    }
+
+    /// Deserializes a state written by `to_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error if the magic bytes or the version don't match,
+    /// or if the data is truncated or inconsistent.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(SAVE_STATE_MAGIC.len())? != SAVE_STATE_MAGIC {
+            return Err(invalid_data("Not a save state file".to_string()));
+        }
+        let version = reader.u16()?;
+        if version != SAVE_STATE_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported save state version {} (expected {})",
+                version, SAVE_STATE_VERSION
+            )));
+        }
+
+        let mut cpu = CpuState {
+            V: reader.take(V_COUNT)?.try_into().unwrap(),
+            I: reader.u16()?,
+            PC: reader.u16()? as usize,
+            stack: [0; MAX_STACK_LEVELS],
+            SP: 0,
+            delay_timer: 0,
+            sound_timer: 0,
+        };
+        for address in cpu.stack.iter_mut() {
+            *address = reader.u16()? as usize;
+        }
+        cpu.SP = reader.u8()? as usize;
+        cpu.delay_timer = reader.u8()?;
+        cpu.sound_timer = reader.u8()?;
+
+        let release_key_register = match reader.u8()? {
+            0xFF => None,
+            register => Some(register as usize),
+        };
+        let halted = reader.u8()? != 0;
+        let lores = reader.u8()? != 0;
+        let planes = reader.u8()?;
+        let memory = reader.take(MEMORY_SIZE)?.try_into().unwrap();
+
+        let width = reader.u16()? as usize;
+        let height = reader.u16()? as usize;
+        let pixels = reader.take(width * height)?.to_vec();
+
+        if !reader.bytes.is_empty() {
+            return Err(invalid_data("Corrupted save state".to_string()));
+        }
+
+        let state = Chip8State {
+            cpu,
+            memory,
+            release_key_register,
+            halted,
+            lores,
+            planes,
+            width,
+            height,
+            pixels,
+        };
+        state.validate()?;
+        Ok(state)
+    }
+
+    /// Checks that the state can be restored, e.g. when it was built by hand.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error if `PC`, `SP`, or a return address is out of range,
+    /// the register waiting for a key release doesn't exist, or the pixels don't fill a
+    /// nonempty display.
+    pub fn validate(&self) -> io::Result<()> {
+        let cpu = &self.cpu;
+        if cpu.PC >= MEMORY_SIZE
+            || cpu.SP > MAX_STACK_LEVELS
+            || cpu
+                .active_stack()
+                .iter()
+                .any(|&address| address >= MEMORY_SIZE)
+            || self
+                .release_key_register
+                .is_some_and(|register| register >= V_COUNT)
+            || self.width == 0
+            || self.height == 0
+            || self.pixels.len() != self.width * self.height
+        {
+            return Err(invalid_data("Corrupted save state".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads fields from the front of a byte slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid_data("Truncated save state".to_string()));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+}
+
+/// Formats the state as an aligned, multi-line block.
+impl fmt::Debug for CpuState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "PC=0x{:03X} I=0x{:03X} SP={:<2} DT=0x{:02X} ST=0x{:02X}",
+            self.PC, self.I, self.SP, self.delay_timer, self.sound_timer
+        )?;
+
+        for (row, registers) in self.V.chunks(8).enumerate() {
+            let line = registers
+                .iter()
+                .enumerate()
+                .map(|(i, value)| format!("V{:X}=0x{:02X}", row * 8 + i, value))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{}", line)?;
+        }
+
+        let stack = self
+            .active_stack()
+            .iter()
+            .map(|address| format!("0x{:03X}", address))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "stack=[{}]", stack)
+    }
+}
+
+/// Formats the state on a single line.
+impl fmt::Display for CpuState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PC=0x{:03X} I=0x{:03X} V=[", self.PC, self.I)?;
+        for (i, value) in self.V.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:02X}", value)?;
+        }
+        write!(
+            f,
+            "] SP={} DT=0x{:02X} ST=0x{:02X}",
+            self.SP, self.delay_timer, self.sound_timer
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_state() -> CpuState {
+        CpuState {
+            V: [0; V_COUNT],
+            I: 0,
+            PC: 0x200,
+            stack: [0; MAX_STACK_LEVELS],
+            SP: 0,
+            delay_timer: 0,
+            sound_timer: 0,
+        }
+    }
+
+    #[test]
+    fn test_debug_format() {
+        let mut state = create_test_state();
+        state.V[0xA] = 0x3C;
+        state.stack[0] = 0x202;
+        state.SP = 1;
+        state.delay_timer = 0x10;
+
+        let formatted = format!("{:?}", state);
+        assert!(formatted.contains("V0=0x00"), "{}", formatted);
+        assert!(formatted.contains("VA=0x3C"), "{}", formatted);
+        assert!(formatted.contains("PC=0x200"), "{}", formatted);
+        assert!(formatted.contains("DT=0x10"), "{}", formatted);
+        assert!(formatted.contains("stack=[0x202]"), "{}", formatted);
+        assert_eq!(formatted.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_display_format() {
+        let mut state = create_test_state();
+        state.V[0x1] = 0xFF;
+
+        let formatted = state.to_string();
+        assert!(formatted.starts_with("PC=0x200 I=0x000 V=[00 FF 00"));
+        assert_eq!(formatted.lines().count(), 1);
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+use crate::quirks::Quirk;
+
+/// An event reported to the trace hook installed with `Chip8::on_trace`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// A `0nnn` SYS call was ignored.
+    Sys { address: usize, nnn: u16 },
+    /// An instruction whose behavior depends on a quirk was executed in strict mode.
+    QuirkDependent { address: usize, quirk: Quirk },
+    /// An `Fx15` instruction set the delay timer.
+    DelayTimerSet { address: usize, value: u8 },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceEvent::Sys { address, nnn } => {
+                write!(f, "0x{:03X}: ignored SYS 0x{:03X}", address, nnn)
+            }
+            TraceEvent::QuirkDependent { address, quirk } => {
+                write!(
+                    f,
+                    "0x{:03X}: behavior depends on the {} quirk",
+                    address, quirk
+                )
+            }
+            TraceEvent::DelayTimerSet { address, value } => {
+                write!(f, "0x{:03X}: delay timer set to {}", address, value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let event = TraceEvent::Sys {
+            address: 0x204,
+            nnn: 0x123,
+        };
+        assert_eq!(event.to_string(), "0x204: ignored SYS 0x123");
+
+        let event = TraceEvent::QuirkDependent {
+            address: 0x206,
+            quirk: Quirk::LoadStoreIncrement,
+        };
+        assert_eq!(
+            event.to_string(),
+            "0x206: behavior depends on the load/store increment quirk"
+        );
+
+        let event = TraceEvent::DelayTimerSet {
+            address: 0x208,
+            value: 60,
+        };
+        assert_eq!(event.to_string(), "0x208: delay timer set to 60");
+    }
+}
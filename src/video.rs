@@ -1,9 +1,15 @@
-use sdl2::pixels::Color;
-use sdl2::rect::Point;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 
 use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ColorError;
+use crate::state::CpuState;
+use crate::CHIP8_FONTSET;
 
 const X_DIM_LORES: usize = 64;
 const Y_DIM_LORES: usize = 32;
@@ -13,10 +19,138 @@ const Y_DIM_HIRES: usize = 64;
 
 const WINDOW_TITLE: &str = "emul8tor";
 
-/// Resolution modes.
-pub enum Resolution {
-    Low,
-    High,
+/// Default colors, indexed by pixel value.
+pub const DEFAULT_PALETTE: Palette = [
+    Color::BLACK,
+    Color::WHITE,
+    Color::RGB(0xAA, 0xAA, 0xAA),
+    Color::RGB(0x55, 0x55, 0x55),
+];
+
+/// Built-in color themes that `cycle_palette` rotates through after the palette set
+/// with `set_palette`.
+const BUILTIN_PALETTES: [Palette; 3] = [
+    DEFAULT_PALETTE,
+    // Octo's default colors
+    [
+        Color::RGB(0x99, 0x66, 0x00),
+        Color::RGB(0xFF, 0xCC, 0x00),
+        Color::RGB(0xFF, 0x66, 0x00),
+        Color::RGB(0x66, 0x22, 0x00),
+    ],
+    // Green LCD
+    [
+        Color::RGB(0x0F, 0x38, 0x0F),
+        Color::RGB(0x9B, 0xBC, 0x0F),
+        Color::RGB(0x8B, 0xAC, 0x0F),
+        Color::RGB(0x30, 0x62, 0x30),
+    ],
+];
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 5;
+
+const HUD_PIXEL_SIZE: usize = 2;
+const HUD_MARGIN: usize = 1;
+const HUD_BACKGROUND: Color = Color::BLACK;
+const HUD_FOREGROUND: Color = Color::GREEN;
+const HUD_CELL_WIDTH: usize = (GLYPH_WIDTH + 1) * HUD_PIXEL_SIZE;
+const HUD_CELL_HEIGHT: usize = (GLYPH_HEIGHT + 1) * HUD_PIXEL_SIZE;
+
+const BEEP_INDICATOR_SIZE: usize = 2;
+const DEFAULT_BEEP_COLOR: Color = Color::RED;
+
+const DEFAULT_BORDER_COLOR: Color = Color::BLACK;
+
+/// Display colors, indexed by pixel value.
+pub type Palette = [Color; 4];
+
+/// Grid of font glyph indices, with `None` for blank cells.
+pub type GlyphGrid = Vec<Vec<Option<u8>>>;
+
+/// How the display is filtered when it is scaled up to the window size.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// Nearest neighbor, which keeps the pixels sharp.
+    #[default]
+    Nearest,
+    /// Linear interpolation, which smooths the pixel edges.
+    Linear,
+}
+
+impl Filter {
+    /// Returns the value of the `SDL_RENDER_SCALE_QUALITY` hint for the filter.
+    fn scale_quality(&self) -> &'static str {
+        match self {
+            Filter::Nearest => "nearest",
+            Filter::Linear => "linear",
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nearest" => Ok(Filter::Nearest),
+            "linear" => Ok(Filter::Linear),
+            _ => Err(format!("Unknown filter {} (expected nearest or linear)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.scale_quality())
+    }
+}
+
+/// Logical dimensions of the display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    width: usize,
+    height: usize,
+}
+
+#[allow(non_upper_case_globals)]
+impl Resolution {
+    /// The 64x32 CHIP-8 resolution.
+    pub const Low: Resolution = Resolution::new(X_DIM_LORES, Y_DIM_LORES);
+
+    /// The 128x64 SuperChip and XO-CHIP resolution.
+    pub const High: Resolution = Resolution::new(X_DIM_HIRES, Y_DIM_HIRES);
+
+    /// The 64x48 resolution of the ETI-660.
+    pub const Eti660: Resolution = Resolution::new(64, 48);
+
+    /// The 64x64 hires mode of the COSMAC VIP.
+    pub const VipHires: Resolution = Resolution::new(64, 64);
+
+    /// Creates a resolution with arbitrary dimensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the display in pixels.
+    /// * `height` - Height of the display in pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    pub const fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "Resolution must not be empty");
+        Resolution { width, height }
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
 }
 
 /// Manages display rendering using SDL2.
@@ -24,7 +158,24 @@ pub enum Resolution {
 pub struct DisplayManager {
     canvas: Option<Canvas<Window>>,
     VRAM: Vec<Vec<u8>>,
+    resolution: Resolution,
     update_needed: bool,
+    dirty_rects: Vec<Rect>,
+    changes: u64,
+    palette: Palette,
+    base_palette: Palette,
+    palette_index: usize,
+    scale_x: usize,
+    scale_y: usize,
+    beep_indicator: bool,
+    beeping: bool,
+    beep_color: Color,
+    filter: Filter,
+    texture_pending: bool,
+    hud: bool,
+    stats: bool,
+    border: usize,
+    border_color: Color,
 }
 
 impl DisplayManager {
@@ -33,7 +184,7 @@ impl DisplayManager {
     /// # Arguments
     ///
     /// * `sdl_context` - A reference to an initialized SDL context.
-    /// * `resolution` - A selected resolution mode.
+    /// * `resolution` - A selected resolution.
     /// * `scale` - A display scaling factor.
     ///
     /// # Errors
@@ -43,22 +194,37 @@ impl DisplayManager {
         sdl_context: &sdl2::Sdl,
         resolution: Resolution,
         scale: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        Self::with_scales(sdl_context, resolution, scale, scale)
+    }
+
+    /// Creates a new `DisplayManager` instance with independent horizontal and vertical
+    /// scaling, e.g. to stretch pixels vertically like on a CRT.
+    ///
+    /// # Arguments
+    ///
+    /// * `sdl_context` - A reference to an initialized SDL context.
+    /// * `resolution` - A selected resolution.
+    /// * `scale_x` - The horizontal display scaling factor.
+    /// * `scale_y` - The vertical display scaling factor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to get the video subsystem or create the window or canvas.
+    pub fn with_scales(
+        sdl_context: &sdl2::Sdl,
+        resolution: Resolution,
+        scale_x: usize,
+        scale_y: usize,
     ) -> Result<Self, Box<dyn Error>> {
         let video_subsystem = sdl_context
             .video()
             .map_err(|e| format!("Failed to get SDL2 video subsystem: {}", e))?;
 
-        let (x_dim, y_dim, window_scale) = match resolution {
-            Resolution::Low => (X_DIM_LORES, Y_DIM_LORES, scale),
-            Resolution::High => (X_DIM_HIRES, Y_DIM_HIRES, scale / 2),
-        };
+        let (window_width, window_height) = Self::window_dimensions(&resolution, scale_x, scale_y);
 
         let window = video_subsystem
-            .window(
-                WINDOW_TITLE,
-                (x_dim * scale) as u32,
-                (y_dim * window_scale) as u32,
-            )
+            .window(WINDOW_TITLE, window_width, window_height)
             .position_centered()
             .build()
             .map_err(|e| format!("Failed to create window: {}", e))?;
@@ -69,24 +235,78 @@ impl DisplayManager {
             .map_err(|e| format!("Failed to create canvas: {}", e))?;
 
         canvas
-            .set_scale(scale as f32, scale as f32)
+            .set_scale(scale_x as f32, scale_y as f32)
             .map_err(|e| format!("Failed to set scale: {}", e))?;
 
-        canvas.set_draw_color(Color::BLACK);
+        canvas.set_draw_color(DEFAULT_PALETTE[0]);
         canvas.clear();
         canvas.present();
 
+        let mut display_manager = Self::headless(resolution);
+        display_manager.canvas = Some(canvas);
+        display_manager.scale_x = scale_x;
+        display_manager.scale_y = scale_y;
+        Ok(display_manager)
+    }
+
+    /// Creates a new `DisplayManager` instance without a window.
+    ///
+    /// VRAM is fully functional, but nothing is presented on screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolution` - A selected resolution.
+    pub fn headless(resolution: Resolution) -> Self {
         #[allow(non_snake_case)]
-        let VRAM = match resolution {
-            Resolution::Low => vec![vec![0; X_DIM_LORES]; Y_DIM_LORES],
-            Resolution::High => vec![vec![0; X_DIM_HIRES]; Y_DIM_HIRES],
-        };
+        let VRAM = Self::create_vram(&resolution);
 
-        Ok(DisplayManager {
-            canvas: Some(canvas),
+        DisplayManager {
+            canvas: None,
             VRAM,
+            resolution,
             update_needed: false,
-        })
+            dirty_rects: Vec::new(),
+            changes: 0,
+            palette: DEFAULT_PALETTE,
+            base_palette: DEFAULT_PALETTE,
+            palette_index: 0,
+            scale_x: 1,
+            scale_y: 1,
+            beep_indicator: false,
+            beeping: false,
+            beep_color: DEFAULT_BEEP_COLOR,
+            filter: Filter::Nearest,
+            texture_pending: false,
+            hud: false,
+            stats: false,
+            border: 0,
+            border_color: DEFAULT_BORDER_COLOR,
+        }
+    }
+
+    /// Switches the display to another resolution, clearing its contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolution` - A selected resolution.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window.
+    pub fn set_resolution(&mut self, resolution: Resolution) -> Result<(), Box<dyn Error>> {
+        let (window_width, window_height) =
+            self.bordered_dimensions(&resolution, self.scale_x, self.scale_y);
+        if let Some(canvas) = self.canvas.as_mut() {
+            canvas
+                .window_mut()
+                .set_size(window_width, window_height)
+                .map_err(|e| format!("Failed to resize window: {}", e))?;
+        }
+
+        self.VRAM = Self::create_vram(&resolution);
+        self.resolution = resolution;
+        self.dirty_rects.clear();
+        self.clear()
     }
 
     /// Returns the height of the display.
@@ -99,6 +319,104 @@ impl DisplayManager {
         self.VRAM[0].len()
     }
 
+    /// Returns the current resolution.
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Returns the display scaling factor, or the horizontal one if the scales differ.
+    pub fn scale(&self) -> usize {
+        self.scale_x
+    }
+
+    /// Returns the horizontal display scaling factor.
+    pub fn scale_x(&self) -> usize {
+        self.scale_x
+    }
+
+    /// Returns the vertical display scaling factor.
+    pub fn scale_y(&self) -> usize {
+        self.scale_y
+    }
+
+    /// Changes the horizontal and vertical scaling factors, resizing the window.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale_x` - The horizontal display scaling factor.
+    /// * `scale_y` - The vertical display scaling factor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window.
+    pub fn set_scale(&mut self, scale_x: usize, scale_y: usize) -> Result<(), Box<dyn Error>> {
+        let (window_width, window_height) =
+            self.bordered_dimensions(&self.resolution, scale_x, scale_y);
+        if let Some(canvas) = self.canvas.as_mut() {
+            canvas
+                .window_mut()
+                .set_size(window_width, window_height)
+                .map_err(|e| format!("Failed to resize window: {}", e))?;
+            canvas
+                .set_scale(scale_x as f32, scale_y as f32)
+                .map_err(|e| format!("Failed to set scale: {}", e))?;
+        }
+
+        self.scale_x = scale_x;
+        self.scale_y = scale_y;
+        self.draw_border()?;
+        self.redraw();
+        Ok(())
+    }
+
+    /// Returns the window dimensions in physical pixels, i.e. the resolution times the
+    /// scale, plus the border on each side.
+    pub fn window_size(&self) -> (u32, u32) {
+        self.bordered_dimensions(&self.resolution, self.scale_x, self.scale_y)
+    }
+
+    /// Surrounds the display with a solid border, resizing the window to fit it.
+    ///
+    /// # Arguments
+    ///
+    /// * `thickness` - Width of the border on each side in physical pixels, or 0 for none.
+    /// * `color` - Color of the border.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to resize the window.
+    pub fn set_border(&mut self, thickness: usize, color: Color) -> Result<(), Box<dyn Error>> {
+        self.border = thickness;
+        self.border_color = color;
+        let (window_width, window_height) = self.window_size();
+        if let Some(canvas) = self.canvas.as_mut() {
+            canvas
+                .window_mut()
+                .set_size(window_width, window_height)
+                .map_err(|e| format!("Failed to resize window: {}", e))?;
+        }
+
+        self.draw_border()?;
+        self.redraw();
+        Ok(())
+    }
+
+    /// Returns the area of the window that the display is drawn to, in physical pixels.
+    pub fn play_area(&self) -> Rect {
+        let (width, height) = Self::window_dimensions(&self.resolution, self.scale_x, self.scale_y);
+        Rect::new(self.border as i32, self.border as i32, width, height)
+    }
+
+    /// Returns the value of the pixel at the given coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate of the pixel.
+    /// * `y` - Y coordinate of the pixel.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.VRAM[y][x]
+    }
+
     /// Sets the pixel at the given coordinates.
     ///
     /// # Arguments
@@ -109,9 +427,12 @@ impl DisplayManager {
     ///
     /// # Returns
     ///
-    /// Returns 1 if the pixel was already set to the given value, 0 otherwise.
+    /// Returns the bits of `value` that were already set in the pixel, i.e. the collision.
     pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) -> u8 {
         self.update_needed = true;
+        if value != 0 {
+            self.mark_dirty(Rect::new(x as i32, y as i32, 1, 1));
+        }
 
         let previous_value = self.VRAM[y][x];
         self.draw_pixel(x, y, previous_value ^ value);
@@ -120,108 +441,691 @@ impl DisplayManager {
     }
 
     /// Clears the display and resets the VRAM.
-    pub fn clear(&mut self) {
-        self.update_needed = true;
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to redraw the border.
+    pub fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        self.mark_all_dirty();
         self.VRAM.iter_mut().for_each(|row| row.fill(0));
         if let Some(canvas) = self.canvas.as_mut() {
-            canvas.set_draw_color(Color::BLACK);
+            canvas.set_draw_color(self.palette[0]);
             canvas.clear();
         }
+        // Clearing the canvas also clears the border
+        if self.border > 0 {
+            self.draw_border()?;
+        }
+        Ok(())
+    }
+
+    /// Takes the regions of the display that changed since the last call.
+    ///
+    /// Touching or overlapping changes are merged, so every pixel is covered by at most
+    /// one rectangle.
+    ///
+    /// # Returns
+    ///
+    /// The changed regions in display coordinates, in no particular order.
+    pub fn take_dirty_rects(&mut self) -> Vec<Rect> {
+        std::mem::take(&mut self.dirty_rects)
+    }
+
+    /// Returns the number of draw operations on the display so far.
+    ///
+    /// Every operation counts, including ones that leave the pixels as they were, like
+    /// drawing the same sprite twice, so two counts differ if the display was drawn to
+    /// in between.
+    pub fn change_count(&self) -> u64 {
+        self.changes
+    }
+
+    /// Selects the filter used when the display is scaled up to the window size.
+    ///
+    /// With `Filter::Nearest`, pixels are drawn straight to the window. With
+    /// `Filter::Linear`, the display is uploaded to a texture that SDL2 scales with
+    /// linear interpolation.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter.
+    pub fn set_filter(&mut self, filter: Filter) {
+        if self.filter != filter {
+            self.filter = filter;
+            self.redraw();
+        }
+    }
+
+    /// Returns the filter used when the display is scaled up.
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// Sets the display colors and redraws the display with them.
+    ///
+    /// The palette becomes the first one that `cycle_palette` rotates through.
+    ///
+    /// # Arguments
+    ///
+    /// * `palette` - Colors indexed by pixel value.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.base_palette = palette;
+        self.palette_index = 0;
+        self.apply_palette(palette);
+    }
+
+    fn apply_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.redraw();
+    }
+
+    /// Returns the display colors.
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// Switches to the next palette, wrapping around after the last one.
+    ///
+    /// The palette set with `set_palette` comes first, followed by the built-in palettes
+    /// that differ from it. Only the colors change; the pixel values stay the same.
+    pub fn cycle_palette(&mut self) {
+        let cycle = self.palette_cycle();
+        self.palette_index = (self.palette_index + 1) % cycle.len();
+        self.apply_palette(cycle[self.palette_index]);
+    }
+
+    /// Returns the position of the current palette in the `cycle_palette` rotation,
+    /// where 0 is the palette set with `set_palette`.
+    pub fn palette_index(&self) -> usize {
+        self.palette_index
+    }
+
+    fn palette_cycle(&self) -> Vec<Palette> {
+        let mut cycle = vec![self.base_palette];
+        cycle.extend(
+            BUILTIN_PALETTES
+                .into_iter()
+                .filter(|&palette| palette != self.base_palette),
+        );
+        cycle
+    }
+
+    /// Writes the display contents as RGBA bytes, row by row, using the current palette.
+    ///
+    /// Works without a window, which makes it suitable for headless rendering and tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `out` - Output buffer of at least `width() * height() * 4` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output buffer is too small.
+    pub fn render_to_buffer(&self, out: &mut [u8]) {
+        assert!(
+            out.len() >= self.width() * self.height() * 4,
+            "Output buffer is too small"
+        );
+
+        let pixels = self.VRAM.iter().flatten();
+        for (rgba, &pixel) in out.chunks_exact_mut(4).zip(pixels) {
+            let color = self.palette[pixel as usize];
+            rgba.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    /// Returns `true` if the display changed since the last `render`, which clears it.
+    pub fn is_dirty(&self) -> bool {
+        self.update_needed
     }
 
     /// Renders the display by presenting the canvas if any changes were made.
-    pub fn render(&mut self) {
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to draw the display texture or the beep indicator.
+    pub fn render(&mut self) -> Result<(), Box<dyn Error>> {
         if self.update_needed {
             self.update_needed = false;
+            self.draw_texture()?;
+
+            if self.beep_indicator {
+                self.draw_beep_indicator()?;
+            }
+
+            if let Some(canvas) = self.canvas.as_mut() {
+                canvas.present();
+            }
+        }
+        Ok(())
+    }
+
+    /// Enables or disables the beep indicator overlay in the top-right corner.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to restore the pixels under the indicator.
+    pub fn set_beep_indicator(&mut self, enabled: bool) -> Result<(), Box<dyn Error>> {
+        if self.beep_indicator != enabled {
+            self.beep_indicator = enabled;
+            self.update_needed = true;
+            // Restore the pixels that were covered by the indicator
+            if !enabled {
+                self.draw_beep_indicator()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the color of the beep indicator, red by default.
+    pub fn set_beep_color(&mut self, color: Color) {
+        self.beep_color = color;
+        self.update_needed |= self.beep_indicator && self.beeping;
+    }
+
+    /// Returns the color of the beep indicator.
+    pub fn beep_color(&self) -> Color {
+        self.beep_color
+    }
+
+    /// Updates whether sound is currently playing, as shown by the beep indicator.
+    pub fn set_beeping(&mut self, beeping: bool) {
+        if self.beeping != beeping {
+            self.beeping = beeping;
+            self.update_needed |= self.beep_indicator;
+        }
+    }
+
+    /// Scrolls the display down.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - Number of pixel rows to scroll.
+    pub fn scroll_down(&mut self, rows: usize) {
+        let rows = rows.min(self.height()) as isize;
+        self.scroll_vertical(rows, u8::MAX);
+    }
+
+    /// Scrolls the display up.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - Number of pixel rows to scroll.
+    pub fn scroll_up(&mut self, rows: usize) {
+        let rows = rows.min(self.height()) as isize;
+        self.scroll_vertical(-rows, u8::MAX);
+    }
+
+    /// Scrolls the display to the right by 4 pixels.
+    pub fn scroll_right(&mut self) {
+        self.scroll_horizontal(4, u8::MAX, false);
+    }
+
+    /// Scrolls the display to the left by 4 pixels.
+    pub fn scroll_left(&mut self) {
+        self.scroll_horizontal(-4, u8::MAX, false);
+    }
+
+    /// Scrolls the pixels of the selected bitplanes horizontally, leaving the other
+    /// planes in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Number of pixels to scroll to the right, or to the left if negative.
+    /// * `planes` - Bitmask of the planes to scroll.
+    /// * `wrap` - Whether pixels scrolled out reappear on the opposite edge instead of
+    ///   the vacated columns being cleared.
+    pub fn scroll_horizontal(&mut self, offset: isize, planes: u8, wrap: bool) {
+        self.mark_all_dirty();
+        let width = self.width() as isize;
+        // Scrolling by the width or more clears the rows unless they wrap around
+        let offset = if wrap {
+            offset.rem_euclid(width)
+        } else {
+            offset.clamp(-width, width)
+        };
+
+        for y in 0..self.height() {
+            let row = self.VRAM[y].clone();
+            for x in 0..width {
+                let source = x - offset;
+                let scrolled = if wrap || (0..width).contains(&source) {
+                    row[source.rem_euclid(width) as usize]
+                } else {
+                    0
+                };
+                let kept = row[x as usize] & !planes;
+                self.draw_pixel(x as usize, y, kept | (scrolled & planes));
+            }
+        }
+    }
+
+    /// Scrolls the pixels of the selected bitplanes vertically, leaving the other planes
+    /// in place. The vacated rows are cleared.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Number of pixel rows to scroll down, or up if negative.
+    /// * `planes` - Bitmask of the planes to scroll.
+    pub fn scroll_vertical(&mut self, offset: isize, planes: u8) {
+        if offset == 0 {
+            return;
+        }
+        self.mark_all_dirty();
+        let height = self.height() as isize;
+        // Scrolling by the height or more clears the selected planes
+        let offset = offset.clamp(-height, height);
+
+        let vram = self.VRAM.clone();
+        for (y, row) in vram.iter().enumerate() {
+            let source = y as isize - offset;
+            let source_row = (0..height)
+                .contains(&source)
+                .then(|| &vram[source as usize]);
+            for (x, &pixel) in row.iter().enumerate() {
+                let scrolled = source_row.map_or(0, |source_row| source_row[x]);
+                let kept = pixel & !planes;
+                self.draw_pixel(x, y, kept | (scrolled & planes));
+            }
+        }
+    }
+
+    /// Returns the window dimensions in physical pixels for the given resolution.
+    fn window_dimensions(resolution: &Resolution, scale_x: usize, scale_y: usize) -> (u32, u32) {
+        (
+            (resolution.width() * scale_x) as u32,
+            (resolution.height() * scale_y) as u32,
+        )
+    }
+
+    /// Returns the window dimensions in physical pixels for the given resolution,
+    /// including the border.
+    fn bordered_dimensions(
+        &self,
+        resolution: &Resolution,
+        scale_x: usize,
+        scale_y: usize,
+    ) -> (u32, u32) {
+        let (width, height) = Self::window_dimensions(resolution, scale_x, scale_y);
+        let border = 2 * self.border as u32;
+        (width + border, height + border)
+    }
+
+    /// Fills the window outside of the play area with the border color, and restricts
+    /// drawing to the play area.
+    fn draw_border(&mut self) -> Result<(), Box<dyn Error>> {
+        let play_area = self.play_area();
+        let (window_width, window_height) = self.window_size();
+
+        if let Some(canvas) = self.canvas.as_mut() {
+            // The viewport is given in physical pixels, so it doesn't depend on the scale
+            canvas
+                .set_scale(1.0, 1.0)
+                .map_err(|e| format!("Failed to set scale: {}", e))?;
+            canvas.set_viewport(None);
+            if self.border > 0 {
+                let border = self.border as u32;
+                canvas.set_draw_color(self.border_color);
+                canvas
+                    .fill_rects(&[
+                        Rect::new(0, 0, window_width, border),
+                        Rect::new(0, play_area.bottom(), window_width, border),
+                        Rect::new(0, 0, border, window_height),
+                        Rect::new(play_area.right(), 0, border, window_height),
+                    ])
+                    .map_err(|e| format!("Failed to draw border: {}", e))?;
+            }
+            canvas.set_viewport(play_area);
+            canvas
+                .set_scale(self.scale_x as f32, self.scale_y as f32)
+                .map_err(|e| format!("Failed to set scale: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Creates a blank VRAM for the given resolution.
+    fn create_vram(resolution: &Resolution) -> Vec<Vec<u8>> {
+        vec![vec![0; resolution.width()]; resolution.height()]
+    }
+
+    /// Draws the beep indicator, or the VRAM content underneath it when it is hidden.
+    fn draw_beep_indicator(&mut self) -> Result<(), Box<dyn Error>> {
+        let width = self.width();
+        let visible = self.beep_indicator && self.beeping;
+        if !visible && self.filter == Filter::Linear {
+            // The texture covers the pixels underneath the indicator
+            self.texture_pending = self.canvas.is_some();
+            return self.draw_texture();
+        }
+
+        if let Some(canvas) = self.canvas.as_mut() {
+            for y in 0..BEEP_INDICATOR_SIZE {
+                for x in width - BEEP_INDICATOR_SIZE..width {
+                    let color = if visible {
+                        self.beep_color
+                    } else {
+                        self.palette[self.VRAM[y][x] as usize]
+                    };
+                    canvas.set_draw_color(color);
+                    canvas
+                        .draw_point(Point::new(x as i32, y as i32))
+                        .map_err(|e| format!("Failed to draw beep indicator: {}", e))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Enables or disables the register overlay drawn by `render_hud`.
+    pub fn set_hud(&mut self, enabled: bool) {
+        if self.hud != enabled {
+            self.hud = enabled;
+            // Restore the pixels that were covered by the overlay
+            if !enabled {
+                self.redraw();
+            }
+        }
+    }
+
+    /// Returns `true` if the register overlay is enabled.
+    pub fn is_hud_enabled(&self) -> bool {
+        self.hud
+    }
+
+    /// Draws the register overlay into the top rows of the window, if it is enabled.
+    ///
+    /// The overlay shows `PC` and `I` on the first line, followed by `V0..V7` and `V8..VF`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The CPU state to show.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to draw the overlay.
+    pub fn render_hud(&mut self, state: &CpuState) -> Result<(), Box<dyn Error>> {
+        if !self.hud {
+            return Ok(());
+        }
+        self.update_needed = true;
+        self.draw_overlay(&hud_layout(state), 0, 0)
+    }
+
+    /// Enables or disables the speed overlay drawn by `render_stats`.
+    pub fn set_stats(&mut self, enabled: bool) {
+        if self.stats != enabled {
+            self.stats = enabled;
+            // Restore the pixels that were covered by the overlay
+            if !enabled {
+                self.redraw();
+            }
+        }
+    }
+
+    /// Returns `true` if the speed overlay is enabled.
+    pub fn is_stats_enabled(&self) -> bool {
+        self.stats
+    }
+
+    /// Draws the measured frame rate and instructions per second into the bottom-right
+    /// corner of the window, if the speed overlay is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `fps` - Frames presented per second.
+    /// * `ips` - Instructions executed per second.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if SDL2 fails to draw the overlay.
+    pub fn render_stats(&mut self, fps: u32, ips: u32) -> Result<(), Box<dyn Error>> {
+        if !self.stats {
+            return Ok(());
+        }
+        self.update_needed = true;
+
+        let layout = stats_layout(fps, ips);
+        let (width, height) = Self::overlay_size(&layout);
+        let left = (self.width() * self.scale_x).saturating_sub(width);
+        let top = (self.height() * self.scale_y).saturating_sub(height);
+        self.draw_overlay(&layout, left, top)
+    }
+
+    /// Returns the size of an overlay in physical pixels, including its margin.
+    fn overlay_size(layout: &GlyphGrid) -> (usize, usize) {
+        let columns = layout.iter().map(Vec::len).max().unwrap_or(0);
+        (
+            columns * HUD_CELL_WIDTH + 2 * HUD_MARGIN * HUD_PIXEL_SIZE,
+            layout.len() * HUD_CELL_HEIGHT + 2 * HUD_MARGIN * HUD_PIXEL_SIZE,
+        )
+    }
+
+    /// Draws glyphs on a solid box, with the top-left corner at the given physical pixel.
+    fn draw_overlay(
+        &mut self,
+        layout: &GlyphGrid,
+        left: usize,
+        top: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let (width, height) = Self::overlay_size(layout);
+        // Draw the display first, so it doesn't cover the overlay later
+        self.draw_texture()?;
+
+        if let Some(canvas) = self.canvas.as_mut() {
+            // Draw in physical pixels, so the overlay fits regardless of the resolution
+            canvas
+                .set_scale(1.0, 1.0)
+                .map_err(|e| format!("Failed to set scale: {}", e))?;
+
+            canvas.set_draw_color(HUD_BACKGROUND);
+            canvas
+                .fill_rect(Rect::new(
+                    left as i32,
+                    top as i32,
+                    width as u32,
+                    height as u32,
+                ))
+                .map_err(|e| format!("Failed to draw overlay: {}", e))?;
+
+            canvas.set_draw_color(HUD_FOREGROUND);
+            for (row, line) in layout.iter().enumerate() {
+                for (column, glyph) in line.iter().enumerate() {
+                    let Some(glyph) = glyph else { continue };
+                    let glyph_x = left + HUD_MARGIN * HUD_PIXEL_SIZE + column * HUD_CELL_WIDTH;
+                    let glyph_y = top + HUD_MARGIN * HUD_PIXEL_SIZE + row * HUD_CELL_HEIGHT;
 
-            if let Some(canvas) = self.canvas.as_mut() {
-                canvas.present();
+                    let sprite = &CHIP8_FONTSET[*glyph as usize * GLYPH_HEIGHT..][..GLYPH_HEIGHT];
+                    for (y, byte) in sprite.iter().enumerate() {
+                        for x in 0..GLYPH_WIDTH {
+                            if (byte >> (7 - x)) & 1 != 0 {
+                                canvas
+                                    .fill_rect(Rect::new(
+                                        (glyph_x + x * HUD_PIXEL_SIZE) as i32,
+                                        (glyph_y + y * HUD_PIXEL_SIZE) as i32,
+                                        HUD_PIXEL_SIZE as u32,
+                                        HUD_PIXEL_SIZE as u32,
+                                    ))
+                                    .map_err(|e| format!("Failed to draw overlay: {}", e))?;
+                            }
+                        }
+                    }
+                }
             }
+
+            canvas
+                .set_scale(self.scale_x as f32, self.scale_y as f32)
+                .map_err(|e| format!("Failed to set scale: {}", e))?;
         }
+        Ok(())
     }
 
-    /// Scrolls the display down.
+    /// Replaces the display contents with a ROM picker that lists the entries by their
+    /// hex index, with a marker in front of the selected one.
+    ///
+    /// Only the page of entries around the selection that fits the display is shown.
     ///
     /// # Arguments
     ///
-    /// * `rows` - Number of pixel rows to scroll.
-    pub fn scroll_down(&mut self, rows: usize) {
-        let width = self.width();
-        let height = self.height();
+    /// * `count` - Number of entries.
+    /// * `selected` - Index of the selected entry.
+    pub fn draw_menu(&mut self, count: usize, selected: usize) {
+        let cell_height = GLYPH_HEIGHT + 1;
+        let rows = (self.height() / cell_height).max(1);
+        let first = selected - selected % rows;
+        let digits = format!("{:X}", count.saturating_sub(1)).len().max(2);
 
-        // Move each row n rows down
-        if rows < height {
-            for y in (rows..height).rev() {
-                for x in 0..width {
-                    self.draw_pixel(x, y, self.VRAM[y - rows][x]);
-                }
+        self.mark_all_dirty();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                self.draw_pixel(x, y, 0);
             }
         }
 
-        // Clear the top n rows
-        for y in 0..rows {
-            for x in 0..width {
-                self.draw_pixel(x, y, 0);
+        for (row, entry) in (first..count.min(first + rows)).enumerate() {
+            let top = 1 + row * cell_height;
+            if entry == selected {
+                for y in top + 1..top + GLYPH_HEIGHT - 1 {
+                    for x in 1..4 {
+                        self.draw_pixel(x, y, 1);
+                    }
+                }
+            }
+            for (column, glyph) in hex_glyphs(entry, digits).into_iter().flatten().enumerate() {
+                self.draw_glyph(6 + column * (GLYPH_WIDTH + 1), top, glyph);
             }
         }
     }
 
-    /// Scrolls the display up.
+    /// Shows a subtitle after the emulator name in the window title.
     ///
     /// # Arguments
     ///
-    /// * `rows` - Number of pixel rows to scroll.
-    pub fn scroll_up(&mut self, rows: usize) {
-        let width = self.width();
-        let height = self.height();
+    /// * `subtitle` - The subtitle, e.g. a ROM name, or `None` for just the emulator name.
+    pub fn set_subtitle(&mut self, subtitle: Option<&str>) {
+        if let Some(canvas) = self.canvas.as_mut() {
+            let title = match subtitle {
+                Some(subtitle) => format!("{} - {}", WINDOW_TITLE, subtitle),
+                None => WINDOW_TITLE.to_string(),
+            };
+            // Titles containing NUL bytes can't be shown and are skipped
+            let _ = canvas.window_mut().set_title(&title);
+        }
+    }
 
-        // Move each row n rows up
-        if rows < height {
-            for y in 0..height - rows {
-                for x in 0..width {
-                    self.draw_pixel(x, y, self.VRAM[y + rows][x]);
-                }
-            }
+    /// Renders the display contents as text, with `#` for set and `.` for clear pixels.
+    ///
+    /// Each row of the display is terminated by a newline, which makes the output
+    /// handy for debugging and test failure messages.
+    pub fn to_ascii(&self) -> String {
+        let mut ascii = String::with_capacity((self.width() + 1) * self.height());
+        for row in &self.VRAM {
+            ascii.extend(row.iter().map(|&pixel| if pixel != 0 { '#' } else { '.' }));
+            ascii.push('\n');
         }
+        ascii
+    }
 
-        // Clear the bottom n rows
-        for y in height - rows..height {
-            for x in 0..width {
-                self.draw_pixel(x, y, 0);
+    /// Packs the display contents into bits, 8 pixels per byte, row by row.
+    ///
+    /// The leftmost pixel is the most significant bit. A pixel is set if it is set in any
+    /// plane, so XO-CHIP colors are not preserved.
+    pub fn to_packed_bits(&self) -> Vec<u8> {
+        let mut bits = vec![0; (self.width() * self.height()).div_ceil(8)];
+        for (index, &pixel) in self.VRAM.iter().flatten().enumerate() {
+            if pixel != 0 {
+                bits[index / 8] |= 0x80 >> (index % 8);
             }
         }
+        bits
     }
 
-    /// Scrolls the display to the right by 4 pixels.
-    pub fn scroll_right(&mut self) {
-        let width = self.width();
-        let height = self.height();
+    /// Replaces the display contents with pixels packed by `to_packed_bits`.
+    ///
+    /// Set pixels are drawn to the first plane.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - Packed pixels of at least `width() * height() / 8` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are fewer bits than pixels, in which case the display is
+    /// left untouched.
+    pub fn from_packed_bits(&mut self, bits: &[u8]) -> Result<(), Box<dyn Error>> {
+        let (width, height) = (self.width(), self.height());
+        if bits.len() * 8 < width * height {
+            return Err(format!(
+                "Packed bitmap is too small: {} bytes for {}x{} pixels",
+                bits.len(),
+                width,
+                height
+            )
+            .into());
+        }
 
-        // Move each column 4 pixels to the right
+        self.mark_all_dirty();
         for y in 0..height {
-            for x in (4..width).rev() {
-                self.draw_pixel(x, y, self.VRAM[y][x - 4]);
-            }
-            // Clear the left 4 pixels of each row
-            for x in 0..4 {
-                self.draw_pixel(x, y, 0);
+            for x in 0..width {
+                let index = y * width + x;
+                self.draw_pixel(x, y, (bits[index / 8] >> (7 - index % 8)) & 1);
             }
         }
+        Ok(())
     }
 
-    /// Scrolls the display to the left by 4 pixels.
-    pub fn scroll_left(&mut self) {
-        let width = self.width();
-        let height = self.height();
+    /// Adds a changed region, merging it with the regions it touches.
+    fn mark_dirty(&mut self, mut rect: Rect) {
+        self.update_needed = true;
+        self.changes += 1;
 
-        // Move each column 4 pixels to the left
-        for y in 0..height {
-            for x in 0..width - 4 {
-                self.draw_pixel(x, y, self.VRAM[y][x + 4]);
+        // Rects that overlap or share an edge are merged into their bounding box
+        let touches = |a: &Rect, b: &Rect| {
+            a.left() <= b.right()
+                && b.left() <= a.right()
+                && a.top() <= b.bottom()
+                && b.top() <= a.bottom()
+        };
+        while let Some(index) = self
+            .dirty_rects
+            .iter()
+            .position(|dirty| touches(dirty, &rect))
+        {
+            let dirty = self.dirty_rects.swap_remove(index);
+            let left = rect.left().min(dirty.left());
+            let top = rect.top().min(dirty.top());
+            let right = rect.right().max(dirty.right());
+            let bottom = rect.bottom().max(dirty.bottom());
+            rect = Rect::new(left, top, (right - left) as u32, (bottom - top) as u32);
+        }
+        self.dirty_rects.push(rect);
+    }
+
+    /// Marks the whole display as changed.
+    fn mark_all_dirty(&mut self) {
+        self.update_needed = true;
+        self.changes += 1;
+        self.dirty_rects = vec![Rect::new(0, 0, self.width() as u32, self.height() as u32)];
+    }
+
+    /// Redraws every pixel from the VRAM content.
+    fn redraw(&mut self) {
+        self.mark_all_dirty();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                self.draw_pixel(x, y, self.VRAM[y][x]);
             }
-            // Clear the right 4 pixels of each row
-            for x in width - 4..width {
-                self.draw_pixel(x, y, 0);
+        }
+    }
+
+    /// Draws a font glyph with its top left corner at the given coordinates, clipped to
+    /// the display.
+    fn draw_glyph(&mut self, left: usize, top: usize, glyph: u8) {
+        let sprite = &CHIP8_FONTSET[glyph as usize * GLYPH_HEIGHT..][..GLYPH_HEIGHT];
+        for (y, byte) in sprite.iter().enumerate() {
+            for x in 0..GLYPH_WIDTH {
+                if left + x < self.width() && top + y < self.height() {
+                    self.draw_pixel(left + x, top + y, (byte >> (7 - x)) & 1);
+                }
             }
         }
     }
@@ -230,17 +1134,137 @@ impl DisplayManager {
     fn draw_pixel(&mut self, x: usize, y: usize, value: u8) {
         self.VRAM[y][x] = value;
         if let Some(canvas) = self.canvas.as_mut() {
-            let color = if self.VRAM[y][x] != 0 {
-                Color::WHITE
-            } else {
-                Color::BLACK
-            };
-            canvas.set_draw_color(color);
+            // Filtered pixels are drawn all at once by draw_texture
+            if self.filter == Filter::Linear {
+                self.texture_pending = true;
+                return;
+            }
+            canvas.set_draw_color(self.palette[self.VRAM[y][x] as usize]);
             canvas
                 .draw_point(Point::new(x as i32, y as i32))
                 .expect("Failed to draw point");
         }
     }
+
+    /// Uploads the VRAM content to a texture and draws it scaled with the filter, if
+    /// pixels changed since the last upload.
+    fn draw_texture(&mut self) -> Result<(), Box<dyn Error>> {
+        if !std::mem::take(&mut self.texture_pending) {
+            return Ok(());
+        }
+        let (width, height) = (self.width(), self.height());
+        let mut rgba = vec![0; width * height * 4];
+        self.render_to_buffer(&mut rgba);
+
+        if let Some(canvas) = self.canvas.as_mut() {
+            // SDL2 reads the scale quality when a texture is created
+            sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", self.filter.scale_quality());
+            let texture_creator = canvas.texture_creator();
+            let mut texture = texture_creator
+                .create_texture_streaming(PixelFormatEnum::RGBA32, width as u32, height as u32)
+                .map_err(|e| format!("Failed to create texture: {}", e))?;
+            texture
+                .update(None, &rgba, width * 4)
+                .map_err(|e| format!("Failed to update texture: {}", e))?;
+            canvas
+                .copy(&texture, None, Rect::new(0, 0, width as u32, height as u32))
+                .map_err(|e| format!("Failed to draw texture: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Lays out the register overlay text as a grid of hex digit glyphs.
+///
+/// # Arguments
+///
+/// * `state` - The CPU state to lay out.
+///
+/// # Returns
+///
+/// One row with `PC` and `I`, followed by one row for `V0..V7` and one for `V8..VF`.
+pub fn hud_layout(state: &CpuState) -> GlyphGrid {
+    let mut layout = Vec::new();
+
+    let mut first_line = hex_glyphs(state.PC, 4);
+    first_line.push(None);
+    first_line.extend(hex_glyphs(state.I as usize, 4));
+    layout.push(first_line);
+
+    for registers in state.V.chunks(8) {
+        let mut line = Vec::new();
+        for (i, &value) in registers.iter().enumerate() {
+            if i > 0 {
+                line.push(None);
+            }
+            line.extend(hex_glyphs(value as usize, 2));
+        }
+        layout.push(line);
+    }
+
+    layout
+}
+
+/// Lays out the speed overlay text as a grid of decimal digit glyphs.
+///
+/// # Arguments
+///
+/// * `fps` - Frames presented per second.
+/// * `ips` - Instructions executed per second.
+///
+/// # Returns
+///
+/// One row for each value, right-aligned so the rows end in the same column.
+pub fn stats_layout(fps: u32, ips: u32) -> GlyphGrid {
+    let lines = [decimal_glyphs(fps), decimal_glyphs(ips)];
+    let columns = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut padded = vec![None; columns - line.len()];
+            padded.extend(line);
+            padded
+        })
+        .collect()
+}
+
+/// Parses a color written as `#RRGGBB`, `RRGGBB`, or the shorthand `#RGB`.
+///
+/// # Arguments
+///
+/// * `text` - The color to parse.
+pub fn parse_color(text: &str) -> Result<Color, ColorError> {
+    let hex = match text.strip_prefix('#') {
+        Some(hex) if hex.len() == 3 => hex.chars().flat_map(|digit| [digit, digit]).collect(),
+        Some(hex) if hex.len() == 6 => hex.to_string(),
+        None if text.len() == 6 => text.to_string(),
+        _ => return Err(ColorError::Length(text.to_string())),
+    };
+    if !hex.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        return Err(ColorError::InvalidDigit(text.to_string()));
+    }
+
+    let rgb =
+        u32::from_str_radix(&hex, 16).map_err(|_| ColorError::InvalidDigit(text.to_string()))?;
+    Ok(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+/// Returns the glyphs of a value's decimal representation.
+fn decimal_glyphs(value: u32) -> Vec<Option<u8>> {
+    value
+        .to_string()
+        .bytes()
+        .map(|digit| Some(digit - b'0'))
+        .collect()
+}
+
+/// Returns the glyphs of a value's hex representation, padded with zeros to `digits`.
+fn hex_glyphs(value: usize, digits: usize) -> Vec<Option<u8>> {
+    (0..digits)
+        .rev()
+        .map(|digit| Some(((value >> (digit * 4)) & 0xF) as u8))
+        .collect()
 }
 
 #[cfg(test)]
@@ -248,11 +1272,34 @@ mod tests {
     use super::*;
 
     fn create_test_display_manager() -> DisplayManager {
-        DisplayManager {
-            canvas: None,
-            VRAM: vec![vec![0; X_DIM_LORES]; Y_DIM_LORES],
-            update_needed: false,
-        }
+        DisplayManager::headless(Resolution::Low)
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#FFCC00"), Ok(Color::RGB(0xFF, 0xCC, 0x00)));
+        assert_eq!(parse_color("ffcc00"), Ok(Color::RGB(0xFF, 0xCC, 0x00)));
+        assert_eq!(parse_color("#F80"), Ok(Color::RGB(0xFF, 0x88, 0x00)));
+    }
+
+    #[test]
+    fn test_parse_color_rejects_invalid() {
+        assert_eq!(
+            parse_color("F80"),
+            Err(ColorError::Length(String::from("F80")))
+        );
+        assert_eq!(
+            parse_color("#GG0000"),
+            Err(ColorError::InvalidDigit(String::from("#GG0000")))
+        );
+        assert_eq!(
+            parse_color("#+F0000"),
+            Err(ColorError::InvalidDigit(String::from("#+F0000")))
+        );
+        assert_eq!(
+            parse_color("12345").unwrap_err().to_string(),
+            "Invalid color \"12345\": expected #RRGGBB, RRGGBB, or #RGB"
+        );
     }
 
     #[test]
@@ -281,7 +1328,7 @@ mod tests {
     fn test_clear() {
         let mut display_manager = create_test_display_manager();
         display_manager.set_pixel(10, 10, 1);
-        display_manager.clear();
+        display_manager.clear().unwrap();
         assert!(display_manager
             .VRAM
             .iter()
@@ -292,8 +1339,331 @@ mod tests {
     fn test_render() {
         let mut display_manager = create_test_display_manager();
         display_manager.set_pixel(10, 10, 1);
-        display_manager.render();
+        display_manager.render().unwrap();
+        assert!(!display_manager.update_needed);
+    }
+
+    #[test]
+    fn test_is_dirty() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.render().unwrap();
+        assert!(!display_manager.is_dirty());
+
+        display_manager.set_pixel(10, 10, 1);
+        assert!(display_manager.is_dirty());
+        assert!(display_manager.is_dirty());
+
+        display_manager.render().unwrap();
+        assert!(!display_manager.is_dirty());
+    }
+
+    #[test]
+    fn test_cycle_palette() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_pixel(0, 0, 1);
+        assert_eq!(display_manager.palette_index(), 0);
+
+        display_manager.cycle_palette();
+        assert_eq!(display_manager.palette_index(), 1);
+        assert_eq!(display_manager.palette, BUILTIN_PALETTES[1]);
+        assert_eq!(display_manager.VRAM[0][0], 1);
+
+        for _ in 1..BUILTIN_PALETTES.len() {
+            display_manager.cycle_palette();
+        }
+        assert_eq!(display_manager.palette_index(), 0);
+        assert_eq!(display_manager.palette, DEFAULT_PALETTE);
+    }
+
+    #[test]
+    fn test_cycle_palette_starts_from_custom_palette() {
+        let mut display_manager = create_test_display_manager();
+        let custom = [Color::BLUE, Color::YELLOW, Color::CYAN, Color::MAGENTA];
+        display_manager.cycle_palette();
+        display_manager.set_palette(custom);
+        assert_eq!(display_manager.palette_index(), 0);
+
+        display_manager.cycle_palette();
+        assert_eq!(display_manager.palette, DEFAULT_PALETTE);
+        for _ in 0..BUILTIN_PALETTES.len() {
+            display_manager.cycle_palette();
+        }
+        assert_eq!(display_manager.palette_index(), 0);
+        assert_eq!(display_manager.palette, custom);
+
+        // A built-in palette set by the user isn't repeated in the cycle
+        display_manager.set_palette(BUILTIN_PALETTES[1]);
+        display_manager.cycle_palette();
+        assert_eq!(display_manager.palette, DEFAULT_PALETTE);
+        display_manager.cycle_palette();
+        assert_eq!(display_manager.palette, BUILTIN_PALETTES[2]);
+        display_manager.cycle_palette();
+        assert_eq!(display_manager.palette, BUILTIN_PALETTES[1]);
+    }
+
+    #[test]
+    fn test_draw_menu() {
+        let mut display_manager = create_test_display_manager();
+
+        display_manager.draw_menu(7, 6);
+        let ascii = display_manager.to_ascii();
+        let lines: Vec<&str> = ascii.lines().collect();
+
+        // Five entries fit on a page, so the second page shows entries 05 and 06
+        assert_eq!(&lines[1][6..15], "####.####", "\n{}", ascii);
+        assert_eq!(&lines[8][..4], ".###", "\n{}", ascii);
+        assert_eq!(&lines[1][..4], "....", "\n{}", ascii);
+        assert!(
+            lines[13..].iter().all(|line| !line.contains('#')),
+            "\n{}",
+            ascii
+        );
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut display_manager = create_test_display_manager();
+        assert_eq!(display_manager.filter(), Filter::Nearest);
+
+        display_manager.set_pixel(0, 0, 1);
+        display_manager.set_filter(Filter::Linear);
+        assert_eq!(display_manager.filter(), Filter::Linear);
+        assert_eq!(display_manager.pixel(0, 0), 1);
+        // Without a window there is no texture to draw
+        assert!(!display_manager.texture_pending);
+
+        assert_eq!("Linear".parse(), Ok(Filter::Linear));
+        assert_eq!(Filter::Nearest.to_string(), "nearest");
+        assert!("bicubic".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_render_to_buffer() {
+        let mut display_manager = create_test_display_manager();
+        let mut buffer = vec![0; display_manager.width() * display_manager.height() * 4];
+
+        // Draw the sprite row 0b1010_0000 at the top-left corner
+        display_manager.set_pixel(0, 0, 1);
+        display_manager.set_pixel(2, 0, 1);
+        display_manager.render_to_buffer(&mut buffer);
+
+        #[rustfmt::skip]
+        let expected_row_start = [
+            0xFF, 0xFF, 0xFF, 0xFF,
+            0x00, 0x00, 0x00, 0xFF,
+            0xFF, 0xFF, 0xFF, 0xFF,
+            0x00, 0x00, 0x00, 0xFF,
+        ];
+        assert_eq!(buffer[..16], expected_row_start);
+        assert!(buffer[16..]
+            .chunks(4)
+            .all(|rgba| rgba == [0x00, 0x00, 0x00, 0xFF]));
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let mut display_manager = create_test_display_manager();
+
+        // Draw a diagonal line from the top-left corner
+        for i in 0..4 {
+            display_manager.set_pixel(i, i, 1);
+        }
+
+        let ascii = display_manager.to_ascii();
+        let rows: Vec<&str> = ascii.lines().collect();
+        assert_eq!(rows.len(), display_manager.height());
+        assert!(rows.iter().all(|row| row.len() == display_manager.width()));
+
+        let corner = rows[..4]
+            .iter()
+            .map(|row| &row[..4])
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(corner, "#...\n.#..\n..#.\n...#", "\n{}", ascii);
+        assert_eq!(ascii.matches('#').count(), 4, "\n{}", ascii);
+    }
+
+    #[test]
+    fn test_packed_bits() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_pixel(0, 0, 1);
+        display_manager.set_pixel(7, 0, 2);
+        display_manager.set_pixel(9, 0, 3);
+        display_manager.set_pixel(63, 31, 1);
+
+        let bits = display_manager.to_packed_bits();
+        assert_eq!(bits.len(), 64 * 32 / 8);
+        assert_eq!(bits[..2], [0b1000_0001, 0b0100_0000]);
+        assert_eq!(bits[255], 0b0000_0001);
+        assert_eq!(bits.iter().map(|byte| byte.count_ones()).sum::<u32>(), 4);
+
+        let mut restored = create_test_display_manager();
+        restored.from_packed_bits(&bits).unwrap();
+        assert_eq!(restored.to_ascii(), display_manager.to_ascii());
+        assert_eq!(restored.to_packed_bits(), bits);
+        assert_eq!(restored.pixel(7, 0), 1);
+
+        assert!(restored.from_packed_bits(&bits[1..]).is_err());
+        assert_eq!(restored.to_packed_bits(), bits);
+    }
+
+    #[test]
+    fn test_hud_layout() {
+        let mut state = CpuState {
+            V: [0; 16],
+            I: 0x3AB,
+            PC: 0x200,
+            stack: [0; 16],
+            SP: 0,
+            delay_timer: 0,
+            sound_timer: 0,
+        };
+        state.V[0x0] = 0x1F;
+        state.V[0xF] = 0xE0;
+
+        let layout = hud_layout(&state);
+        assert_eq!(layout.len(), 3);
+        #[rustfmt::skip]
+        let first_line = [
+            Some(0x0), Some(0x2), Some(0x0), Some(0x0),
+            None,
+            Some(0x0), Some(0x3), Some(0xA), Some(0xB),
+        ];
+        assert_eq!(layout[0], first_line);
+        assert_eq!(layout[1].len(), 8 * 2 + 7);
+        assert_eq!(layout[1][..3], [Some(0x1), Some(0xF), None]);
+        assert_eq!(layout[2][layout[2].len() - 2..], [Some(0xE), Some(0x0)]);
+    }
+
+    #[test]
+    fn test_stats_layout() {
+        assert_eq!(
+            stats_layout(60, 700),
+            [
+                vec![None, Some(6), Some(0)],
+                vec![Some(7), Some(0), Some(0)],
+            ]
+        );
+        assert_eq!(
+            stats_layout(59, 0),
+            [vec![Some(5), Some(9)], vec![None, Some(0)]]
+        );
+    }
+
+    #[test]
+    fn test_set_resolution() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_pixel(10, 10, 1);
+
+        display_manager.set_resolution(Resolution::High).unwrap();
+
+        assert_eq!(display_manager.width(), X_DIM_HIRES);
+        assert_eq!(display_manager.height(), Y_DIM_HIRES);
+        assert_eq!(display_manager.VRAM[10][10], 0);
+    }
+
+    #[test]
+    fn test_dirty_rects() {
+        let mut display_manager = create_test_display_manager();
+
+        display_manager.set_pixel(1, 1, 1);
+        display_manager.set_pixel(2, 1, 1);
+        display_manager.set_pixel(60, 30, 1);
+
+        let mut rects = display_manager.take_dirty_rects();
+        rects.sort_by_key(|rect| rect.x());
+        assert_eq!(rects, [Rect::new(1, 1, 2, 1), Rect::new(60, 30, 1, 1)]);
+        assert!(display_manager.take_dirty_rects().is_empty());
+
+        // Drawing a blank pixel changes nothing
+        display_manager.set_pixel(5, 5, 0);
+        assert!(display_manager.take_dirty_rects().is_empty());
+
+        display_manager.scroll_left();
+        assert_eq!(
+            display_manager.take_dirty_rects(),
+            [Rect::new(0, 0, 64, 32)]
+        );
+    }
+
+    #[test]
+    fn test_custom_resolution() {
+        let mut display_manager = DisplayManager::headless(Resolution::new(64, 48));
+
+        assert_eq!(display_manager.width(), 64);
+        assert_eq!(display_manager.height(), 48);
+        assert_eq!(Resolution::new(64, 48), Resolution::Eti660);
+
+        display_manager.set_pixel(63, 47, 1);
+        assert_eq!(display_manager.VRAM[47][63], 1);
+    }
+
+    #[test]
+    fn test_dimension_accessors() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_scale(8, 8).unwrap();
+
+        assert_eq!(display_manager.resolution(), Resolution::Low);
+        assert_eq!(display_manager.scale(), 8);
+        assert_eq!(display_manager.window_size(), (512, 256));
+
+        display_manager.set_resolution(Resolution::High).unwrap();
+        assert_eq!(display_manager.resolution(), Resolution::High);
+        assert_eq!(display_manager.window_size(), (1024, 512));
+    }
+
+    #[test]
+    fn test_window_dimensions() {
+        assert_eq!(
+            DisplayManager::window_dimensions(&Resolution::Low, 10, 10),
+            (640, 320)
+        );
+        assert_eq!(
+            DisplayManager::window_dimensions(&Resolution::Eti660, 10, 10),
+            (640, 480)
+        );
+    }
+
+    #[test]
+    fn test_border() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_scale(10, 10).unwrap();
+        assert_eq!(display_manager.play_area(), Rect::new(0, 0, 640, 320));
+
+        display_manager.set_border(8, Color::GRAY).unwrap();
+        assert_eq!(display_manager.play_area(), Rect::new(8, 8, 640, 320));
+        assert_eq!(display_manager.window_size(), (656, 336));
+
+        display_manager.set_resolution(Resolution::High).unwrap();
+        assert_eq!(display_manager.play_area(), Rect::new(8, 8, 1280, 640));
+        assert_eq!(display_manager.window_size(), (1296, 656));
+    }
+
+    #[test]
+    fn test_independent_scales() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_scale(10, 20).unwrap();
+
+        assert_eq!(display_manager.scale_x(), 10);
+        assert_eq!(display_manager.scale_y(), 20);
+        assert_eq!(display_manager.window_size(), (640, 640));
+
+        display_manager.set_resolution(Resolution::High).unwrap();
+        assert_eq!(display_manager.window_size(), (1280, 1280));
+    }
+
+    #[test]
+    fn test_beep_indicator_requests_render() {
+        let mut display_manager = create_test_display_manager();
+
+        // The indicator is opt-in, so beeping alone doesn't require a render
+        display_manager.set_beeping(true);
         assert!(!display_manager.update_needed);
+
+        display_manager.set_beep_indicator(true).unwrap();
+        display_manager.render().unwrap();
+        display_manager.set_beeping(false);
+        assert!(display_manager.update_needed);
     }
 
     #[test]
@@ -306,11 +1676,12 @@ mod tests {
         }
 
         display_manager.scroll_down(1);
+        let ascii = display_manager.to_ascii();
 
         // Ensure the second row is now all 1s and the first row is all 0s
         for x in 0..display_manager.width() {
-            assert_eq!(display_manager.VRAM[1][x], 1);
-            assert_eq!(display_manager.VRAM[0][x], 0);
+            assert_eq!(display_manager.VRAM[1][x], 1, "\n{}", ascii);
+            assert_eq!(display_manager.VRAM[0][x], 0, "\n{}", ascii);
         }
     }
 
@@ -325,11 +1696,12 @@ mod tests {
         }
 
         display_manager.scroll_up(1);
+        let ascii = display_manager.to_ascii();
 
         // Ensure the second to last row is now all 1s and the last row is all 0s
         for x in 0..display_manager.width() {
-            assert_eq!(display_manager.VRAM[last_row - 1][x], 1);
-            assert_eq!(display_manager.VRAM[last_row][x], 0);
+            assert_eq!(display_manager.VRAM[last_row - 1][x], 1, "\n{}", ascii);
+            assert_eq!(display_manager.VRAM[last_row][x], 0, "\n{}", ascii);
         }
     }
 
@@ -343,16 +1715,72 @@ mod tests {
         }
 
         display_manager.scroll_right();
+        let ascii = display_manager.to_ascii();
 
         // Ensure the fifth column is now all 1s and the first four columns are all 0s
         for y in 0..display_manager.height() {
-            assert_eq!(display_manager.VRAM[y][4], 1);
+            assert_eq!(display_manager.VRAM[y][4], 1, "\n{}", ascii);
             for x in 0..4 {
-                assert_eq!(display_manager.VRAM[y][x], 0);
+                assert_eq!(display_manager.VRAM[y][x], 0, "\n{}", ascii);
             }
         }
     }
 
+    #[test]
+    fn test_scroll_horizontal_wrap() {
+        let mut display_manager = create_test_display_manager();
+        let last_col = display_manager.width() - 1;
+        for y in 0..display_manager.height() {
+            display_manager.set_pixel(0, y, 1);
+            display_manager.set_pixel(last_col, y, 1);
+        }
+
+        display_manager.scroll_horizontal(4, u8::MAX, true);
+
+        // The last column wraps around to the fourth column
+        for y in 0..display_manager.height() {
+            assert_eq!(display_manager.VRAM[y][3], 1);
+            assert_eq!(display_manager.VRAM[y][4], 1);
+            assert_eq!(display_manager.VRAM[y][last_col], 0);
+        }
+
+        display_manager.scroll_horizontal(-4, u8::MAX, false);
+
+        // Scrolling back without wrapping clears the vacated columns
+        for y in 0..display_manager.height() {
+            assert_eq!(display_manager.VRAM[y][0], 1);
+            assert_eq!(display_manager.VRAM[y][last_col], 0);
+            assert!(display_manager.VRAM[y][last_col - 3..]
+                .iter()
+                .all(|&p| p == 0));
+        }
+    }
+
+    #[test]
+    fn test_scroll_vertical_selected_planes() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_pixel(0, 0, 0b11);
+
+        display_manager.scroll_vertical(2, 0b01);
+        assert_eq!(display_manager.VRAM[0][0], 0b10);
+        assert_eq!(display_manager.VRAM[2][0], 0b01);
+
+        display_manager.scroll_vertical(-40, 0b10);
+        assert_eq!(display_manager.VRAM[0][0], 0);
+        assert_eq!(display_manager.VRAM[2][0], 0b01);
+    }
+
+    #[test]
+    fn test_scroll_horizontal_selected_planes() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_pixel(0, 0, 0b11);
+
+        display_manager.scroll_horizontal(4, 0b10, false);
+
+        assert_eq!(display_manager.VRAM[0][0], 0b01);
+        assert_eq!(display_manager.VRAM[0][4], 0b10);
+    }
+
     #[test]
     fn test_scroll_left() {
         let mut display_manager = create_test_display_manager();
@@ -364,13 +1792,38 @@ mod tests {
         }
 
         display_manager.scroll_left();
+        let ascii = display_manager.to_ascii();
 
         // Ensure the last column minus 4 is now all 1s and the last four columns are all 0s
         for y in 0..display_manager.height() {
-            assert_eq!(display_manager.VRAM[y][last_col - 4], 1);
+            assert_eq!(display_manager.VRAM[y][last_col - 4], 1, "\n{}", ascii);
             for x in last_col - 3..=last_col {
-                assert_eq!(display_manager.VRAM[y][x], 0);
+                assert_eq!(display_manager.VRAM[y][x], 0, "\n{}", ascii);
             }
         }
     }
+
+    #[test]
+    fn test_scroll_past_dimensions() {
+        let mut display_manager = create_test_display_manager();
+        display_manager.set_pixel(5, 5, 1);
+
+        display_manager.scroll_horizontal(65, u8::MAX, true);
+        assert_eq!(display_manager.VRAM[5][6], 1);
+        display_manager.scroll_horizontal(isize::MIN, u8::MAX, false);
+        display_manager.scroll_down(100);
+        display_manager.scroll_up(usize::MAX);
+        assert!(!display_manager.to_ascii().contains('#'));
+
+        // Displays narrower than a 4 pixel scroll are cleared without panicking
+        let mut display_manager = DisplayManager::headless(Resolution::new(2, 2));
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            display_manager.set_pixel(x, y, 1);
+        }
+        display_manager.scroll_right();
+        display_manager.scroll_left();
+        display_manager.scroll_down(3);
+        display_manager.scroll_up(3);
+        assert_eq!(display_manager.to_ascii(), "..\n..\n");
+    }
 }
@@ -1,15 +1,50 @@
-use std::sync::Mutex;
-use std::sync::Once;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
 
-static INIT: Once = Once::new();
-static mut SDL_CONTEXT: Option<Mutex<sdl2::Sdl>> = None;
+type Job = Box<dyn FnOnce(&sdl2::Sdl) + Send>;
 
-pub fn setup() -> &'static Mutex<sdl2::Sdl> {
-    unsafe {
-        INIT.call_once(|| {
+static INIT_COUNT: AtomicUsize = AtomicUsize::new(0);
+static SDL_THREAD: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+
+/// Runs `f` with the shared SDL context and returns its result.
+///
+/// `sdl2::Sdl` can't be sent to other threads, so the context is owned by a dedicated
+/// thread and tests run on it one at a time. Panics are forwarded to the caller.
+pub fn with_sdl<R: Send + 'static>(f: impl FnOnce(&sdl2::Sdl) -> R + Send + 'static) -> R {
+    let sender = SDL_THREAD.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        thread::spawn(move || {
             let sdl_context = sdl2::init().expect("Failed to initialize SDL2");
-            SDL_CONTEXT = Some(Mutex::new(sdl_context));
+            INIT_COUNT.fetch_add(1, Ordering::SeqCst);
+            for job in receiver {
+                job(&sdl_context);
+            }
         });
-        SDL_CONTEXT.as_ref().expect("SDL_CONTEXT not initialized")
+        Mutex::new(sender)
+    });
+
+    let (result_sender, result_receiver) = mpsc::channel();
+    let job: Job = Box::new(move |sdl_context| {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(sdl_context)));
+        let _ = result_sender.send(result);
+    });
+    sender
+        .lock()
+        .expect("Failed to lock SDL_THREAD")
+        .send(job)
+        .expect("SDL thread stopped");
+
+    match result_receiver.recv().expect("SDL thread stopped") {
+        Ok(result) => result,
+        Err(payload) => panic::resume_unwind(payload),
     }
 }
+
+/// Returns how many times the shared SDL context was initialized.
+#[allow(dead_code)]
+pub fn init_count() -> usize {
+    INIT_COUNT.load(Ordering::SeqCst)
+}
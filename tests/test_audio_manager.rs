@@ -4,21 +4,61 @@ mod common;
 
 #[test]
 fn test_audio_manager_creation() {
-    let sdl_context = common::setup().lock().expect("Failed to lock SDL_CONTEXT");
-    let audio_manager =
-        audio::AudioManager::new(&sdl_context).expect("Failed to create AudioManager");
-    assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    common::with_sdl(|sdl_context| {
+        let audio_manager =
+            audio::AudioManager::new(sdl_context).expect("Failed to create AudioManager");
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    });
+}
+
+#[test]
+fn test_audio_manager_with_buffer_size() {
+    common::with_sdl(|sdl_context| {
+        let audio_manager = audio::AudioManager::new_with_spec(sdl_context, 48000, 512)
+            .expect("Failed to create AudioManager");
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+
+        assert!(audio::AudioManager::new_with_spec(sdl_context, 48000, 500).is_err());
+    });
 }
 
 #[test]
 fn test_audio_manager_start_stop() {
-    let sdl_context = common::setup().lock().expect("Failed to lock SDL_CONTEXT");
-    let audio_manager =
-        audio::AudioManager::new(&sdl_context).expect("Failed to create AudioManager");
+    common::with_sdl(|sdl_context| {
+        let mut audio_manager =
+            audio::AudioManager::new(sdl_context).expect("Failed to create AudioManager");
+
+        audio_manager.start();
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+
+        audio_manager.stop();
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    });
+}
 
-    audio_manager.start();
-    assert_eq!(audio_manager.status(), AudioStatus::Playing);
+#[test]
+fn test_audio_manager_muted() {
+    common::with_sdl(|sdl_context| {
+        let mut audio_manager =
+            audio::AudioManager::new(sdl_context).expect("Failed to create AudioManager");
+
+        audio_manager.set_muted(true);
+        audio_manager.start();
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+
+        audio_manager.set_muted(false);
+        assert_eq!(audio_manager.status(), AudioStatus::Playing);
+    });
+}
+
+#[test]
+fn test_sdl_initialized_once() {
+    let threads: Vec<_> = (0..4)
+        .map(|_| std::thread::spawn(|| common::with_sdl(|_| ())))
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
 
-    audio_manager.stop();
-    assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    assert_eq!(common::init_count(), 1);
 }
@@ -0,0 +1,51 @@
+use emul8tor::instruction::Instruction;
+use emul8tor::options::Options;
+use emul8tor::quirks::Quirks;
+use emul8tor::video::DEFAULT_PALETTE;
+use emul8tor::{assemble_program, run_headless, Chip8, Mode};
+use sdl2::pixels::Color;
+
+#[test]
+fn test_new_for_test_runs_without_sdl() {
+    let program = assemble_program(&[
+        Instruction::LdVxByte { x: 0, kk: 0x05 },
+        Instruction::AddVxByte { x: 0, kk: 0x03 },
+        Instruction::LdI { nnn: 0x300 },
+        Instruction::Jp { nnn: 0x206 },
+    ]);
+    let mut chip8 = Chip8::new_for_test(Mode::Chip8, program);
+
+    run_headless(&mut chip8, 60, 4);
+
+    let state = chip8.cpu_state();
+    assert_eq!(state.V[0], 0x08);
+    assert_eq!(state.I, 0x300);
+    assert_eq!(state.PC, 0x206);
+}
+
+#[test]
+fn test_apply_octo_options() {
+    let options = Options::from_json(
+        r##"{"tickrate": 20, "fillColor": "#FFCC00", "backgroundColor": "#996600",
+            "shiftQuirks": true, "jumpQuirks": true, "clipQuirks": false}"##,
+    )
+    .unwrap();
+    let mut chip8 = Chip8::new_for_test(Mode::Chip8, assemble_program(&[]));
+
+    chip8.apply_options(&options).unwrap();
+
+    assert_eq!(
+        *chip8.quirks(),
+        Quirks {
+            shift_uses_vy: false,
+            jump_v0_uses_vx: true,
+            clip_sprites: false,
+            ..Quirks::for_mode(&Mode::Chip8)
+        }
+    );
+    let palette = chip8.palette();
+    assert_eq!(palette[0], Color::RGB(0x99, 0x66, 0x00));
+    assert_eq!(palette[1], Color::RGB(0xFF, 0xCC, 0x00));
+    assert_eq!(palette[2..], DEFAULT_PALETTE[2..]);
+    assert_eq!(options.speed, Some(1200));
+}
@@ -0,0 +1,40 @@
+use emul8tor::golden::{assert_golden, snapshot, Snapshot};
+use emul8tor::instruction::Instruction;
+use emul8tor::{assemble_program, Chip8, Mode};
+
+/// Draws the font digits 0, 8, and F side by side and loops.
+fn digits_rom() -> Chip8 {
+    let mut program = vec![Instruction::LdVxByte { x: 1, kk: 2 }];
+    for (column, digit) in [0x0, 0x8, 0xF].into_iter().enumerate() {
+        program.extend([
+            Instruction::LdVxByte {
+                x: 0,
+                kk: 2 + column as u8 * 6,
+            },
+            Instruction::LdVxByte { x: 2, kk: digit },
+            Instruction::LdFVx { x: 2 },
+            Instruction::DrawSprite { x: 0, y: 1, n: 5 },
+        ]);
+    }
+    let end = 0x200 + program.len() as u16 * 2;
+    program.push(Instruction::Jp { nnn: end });
+    Chip8::new_for_test(Mode::Chip8, assemble_program(&program))
+}
+
+#[test]
+fn test_golden_ascii() {
+    assert_golden(
+        &mut digits_rom(),
+        100,
+        Snapshot::Ascii,
+        "tests/golden/digits.txt",
+    );
+}
+
+#[test]
+fn test_rgba_hash_is_stable() {
+    let first = snapshot(&mut digits_rom(), 100, Snapshot::RgbaHash).unwrap();
+    let second = snapshot(&mut digits_rom(), 100, Snapshot::RgbaHash).unwrap();
+    assert!(first.starts_with("64x32 rgba fnv1a "));
+    assert_eq!(first, second);
+}
@@ -0,0 +1,18 @@
+use emul8tor::audio::AudioManager;
+use emul8tor::{assemble_program, Chip8, Mode};
+use sdl2::audio::AudioStatus;
+mod common;
+
+#[test]
+fn test_chip8_with_shared_context() {
+    common::with_sdl(|sdl_context| {
+        let chip8 = Chip8::with_context(sdl_context, Mode::Chip8, 10, assemble_program(&[]))
+            .expect("Failed to create Chip8");
+        let audio_manager = AudioManager::new(sdl_context).expect("Failed to create AudioManager");
+
+        assert!(*chip8.mode() == Mode::Chip8);
+        assert_eq!(audio_manager.status(), AudioStatus::Paused);
+    });
+
+    assert_eq!(common::init_count(), 1);
+}